@@ -1,9 +1,17 @@
+use crate::db::dict::autocomplete::AutocompleteIndexes;
+use crate::db::dict::inflight::InflightQueries;
+use crate::db::dict::km_spellcheck::KmSpellLexicon;
 use sqlx::{SqlitePool};
-use tokio::sync::RwLock;
+use std::sync::Arc;
+use tokio::sync::{OnceCell, RwLock};
 
 pub struct AppState {
     pub dict_pool: RwLock<Option<SqlitePool>>,
     pub init_error: RwLock<Option<String>>,
+    pub autocomplete: AutocompleteIndexes,
+    pub inflight: InflightQueries,
+    // word lists of the spellchecker, loaded on first use
+    pub km_spell_lexicon: OnceCell<Arc<KmSpellLexicon>>,
 }
 
 impl AppState {
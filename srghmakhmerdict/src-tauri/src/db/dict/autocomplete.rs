@@ -0,0 +1,362 @@
+use crate::app_state::AppState;
+use crate::khmer::collation;
+use crate::khmer::normalize::normalize_khmer;
+use crate::russian::morph::fold_ru_word;
+use fst::automaton::{Automaton, Str};
+use fst::raw::Output;
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::cmp::Ordering;
+use std::sync::Arc;
+use tauri::{State, command};
+use tokio::sync::OnceCell;
+use super::common::{EnShortDefinitionSource, KmShortDefinitionSource, RuShortDefinitionSource};
+use super::en::EN_HAS_DEFINITION;
+use super::headword_pattern::HeadwordPattern;
+use super::km::KM_IS_VERIFIED;
+use super::ru::RU_SHORT_DESC_SOURCE;
+use super::source_priority::short_definition_sql;
+
+const DEFAULT_AUTOCOMPLETE_LIMIT: usize = 20;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Completion<S> {
+    pub word: String,
+    pub is_verified: bool,
+    // None if the word has no short definition
+    pub source: Option<S>,
+}
+
+#[derive(sqlx::FromRow)]
+struct CompletionRow<S> {
+    #[sqlx(rename = "Word")]
+    word: String,
+    is_verified: bool,
+    source: Option<S>,
+}
+
+struct Entry<S> {
+    word: Box<str>,
+    is_verified: bool,
+    source: Option<S>,
+}
+
+/// A headword found by `PrefixIndex::keys_along`, `len` is how many pieces it spans
+pub struct KeyMatch<'a> {
+    pub len: usize,
+    pub word: &'a str,
+    pub is_verified: bool,
+}
+
+/// Headwords of one dictionary, FST from the lookup key (see `to_key`) to the entries with that key.
+/// The FST value packs `start << 32 | count`, entries are sorted by key so every key owns a contiguous run
+pub struct PrefixIndex<S> {
+    map: Map<Vec<u8>>,
+    entries: Vec<Entry<S>>,
+    to_key: fn(&str) -> String,
+    // alphabetical order of headwords, Khmer dictionary order for km
+    compare_words: fn(&str, &str) -> Ordering,
+}
+
+impl<S: Copy> PrefixIndex<S> {
+    fn build(
+        rows: Vec<CompletionRow<S>>,
+        to_key: fn(&str) -> String,
+        compare_words: fn(&str, &str) -> Ordering,
+    ) -> Result<Self, String> {
+        let mut keyed: Vec<(String, Entry<S>)> = rows
+            .into_iter()
+            .map(|r| {
+                let key = to_key(&r.word);
+                (key, Entry { word: r.word.into_boxed_str(), is_verified: r.is_verified, source: r.source })
+            })
+            .filter(|(key, _)| !key.is_empty())
+            .collect();
+        // FST keys have to be inserted in byte order
+        keyed.sort_by(|(a_key, a), (b_key, b)| a_key.as_bytes().cmp(b_key.as_bytes()).then_with(|| compare_words(&a.word, &b.word)));
+
+        let mut builder = MapBuilder::memory();
+        let mut entries = Vec::with_capacity(keyed.len());
+        let mut keyed = keyed.into_iter().peekable();
+        while let Some((key, entry)) = keyed.next() {
+            let start = entries.len() as u64;
+            entries.push(entry);
+            while let Some((_, same)) = keyed.next_if(|(next_key, _)| *next_key == key) {
+                entries.push(same);
+            }
+            let count = entries.len() as u64 - start;
+            builder.insert(key.as_bytes(), (start << 32) | count).map_err(|e| e.to_string())?;
+        }
+
+        let map = builder.into_map();
+        Ok(Self { map, entries, to_key, compare_words })
+    }
+
+    /// Lookup key of a word or query, equal keys count as the same word
+    pub fn key(&self, word: &str) -> String {
+        (self.to_key)(word)
+    }
+
+    // the FST value of a key is (start << 32) | count into `entries`
+    fn entries_of(&self, value: u64) -> &[Entry<S>] {
+        let (start, count) = ((value >> 32) as usize, (value & 0xFFFF_FFFF) as usize);
+        &self.entries[start..start + count]
+    }
+
+    /// Whether `word` is a verified headword, false if it isn't one at all
+    pub fn is_verified(&self, word: &str) -> bool {
+        self.map
+            .get(self.key(word))
+            .is_some_and(|value| self.entries_of(value).iter().any(|e| &*e.word == word && e.is_verified))
+    }
+
+    /// For every n such that the keys of `pieces[..n]`, concatenated, are a headword key: n and that headword
+    /// (a verified one if several share the key). Walks the FST along the pieces, so it stops as soon as
+    /// no key continues them. `pieces` must already be keys (to_key of each piece)
+    pub fn keys_along<P: AsRef<str>>(&self, pieces: &[P]) -> Vec<KeyMatch<'_>> {
+        let fst = self.map.as_fst();
+        let mut node = fst.root();
+        let mut output = Output::zero();
+        let mut found = Vec::new();
+
+        for (n, piece) in pieces.iter().enumerate() {
+            for byte in piece.as_ref().bytes() {
+                let Some(i) = node.find_input(byte) else {
+                    return found;
+                };
+                let transition = node.transition(i);
+                output = output.cat(transition.out);
+                node = fst.node(transition.addr);
+            }
+            if node.is_final() {
+                let entries = self.entries_of(output.cat(node.final_output()).value());
+                let entry = entries.iter().find(|e| e.is_verified).unwrap_or(&entries[0]);
+                found.push(KeyMatch { len: n + 1, word: &entry.word, is_verified: entry.is_verified });
+            }
+        }
+
+        found
+    }
+
+    /// Top `limit` headwords starting with `prefix`: exact match, then verified, then shorter, then alphabetical
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion<S>> {
+        let prefix = (self.to_key)(prefix);
+        if prefix.is_empty() {
+            return vec![];
+        }
+        self.ranked(&prefix, |_| true, limit)
+    }
+
+    /// Top `limit` headwords matching `pattern` (see headword_pattern.rs), ranked as in `complete`
+    pub fn matching(&self, pattern: &str, limit: usize) -> Result<Vec<Completion<S>>, String> {
+        let pattern = HeadwordPattern::parse(&(self.to_key)(pattern))?;
+        Ok(self.ranked(&pattern.literal_prefix(), |key| pattern.matches(key), limit))
+    }
+
+    fn ranked(&self, key_prefix: &str, mut accept: impl FnMut(&str) -> bool, limit: usize) -> Vec<Completion<S>> {
+        if limit == 0 {
+            return vec![];
+        }
+
+        let order = |a: &(bool, usize, &Entry<S>), b: &(bool, usize, &Entry<S>)| {
+            (!a.0, !a.2.is_verified, a.1)
+                .cmp(&(!b.0, !b.2.is_verified, b.1))
+                .then_with(|| (self.compare_words)(&a.2.word, &b.2.word))
+        };
+
+        // a short prefix has a large share of the dictionary under it: keep at most 2 * limit candidates,
+        // cut back to the best `limit` whenever the buffer fills up
+        let mut stream = self.map.search(Str::new(key_prefix).starts_with()).into_stream();
+        let mut found: Vec<(bool, usize, &Entry<S>)> = Vec::with_capacity(limit.saturating_mul(2).min(1024));
+        let keep_best = |found: &mut Vec<(bool, usize, &Entry<S>)>| {
+            if found.len() > limit {
+                found.select_nth_unstable_by(limit, order);
+                found.truncate(limit);
+            }
+        };
+        while let Some((key, value)) = stream.next() {
+            if !std::str::from_utf8(key).is_ok_and(&mut accept) {
+                continue;
+            }
+            let is_exact = key == key_prefix.as_bytes();
+            for entry in self.entries_of(value) {
+                found.push((is_exact, key.len(), entry));
+                if found.len() >= limit.saturating_mul(2) {
+                    keep_best(&mut found);
+                }
+            }
+        }
+        keep_best(&mut found);
+        found.sort_by(order);
+
+        found
+            .into_iter()
+            .map(|(_, _, entry)| Completion {
+                word: entry.word.to_string(),
+                is_verified: entry.is_verified,
+                source: entry.source,
+            })
+            .collect()
+    }
+}
+
+/// Built on first use, one per dictionary. Shared, so long computations over one can move to a blocking thread
+#[derive(Default)]
+pub struct AutocompleteIndexes {
+    km: OnceCell<Arc<PrefixIndex<KmShortDefinitionSource>>>,
+    en: OnceCell<Arc<PrefixIndex<EnShortDefinitionSource>>>,
+    ru: OnceCell<Arc<PrefixIndex<RuShortDefinitionSource>>>,
+}
+
+impl AutocompleteIndexes {
+    pub async fn km_index(&self, pool: &SqlitePool) -> Result<&Arc<PrefixIndex<KmShortDefinitionSource>>, String> {
+        self.km.get_or_try_init(|| load_index(pool, km_autocomplete_sql(), normalize_khmer, collation::compare)).await
+    }
+
+    pub async fn en_index(&self, pool: &SqlitePool) -> Result<&Arc<PrefixIndex<EnShortDefinitionSource>>, String> {
+        self.en.get_or_try_init(|| load_index(pool, en_autocomplete_sql(), en_key, str::cmp)).await
+    }
+
+    pub async fn ru_index(&self, pool: &SqlitePool) -> Result<&Arc<PrefixIndex<RuShortDefinitionSource>>, String> {
+        self.ru.get_or_try_init(|| load_index(pool, ru_autocomplete_sql(), ru_key, str::cmp)).await
+    }
+}
+
+// The source is the one of the default priority
+fn km_autocomplete_sql() -> String {
+    let short = short_definition_sql::<KmShortDefinitionSource>(None);
+    format!("SELECT Word, {} AS is_verified, {} AS source FROM km_Dict", KM_IS_VERIFIED, short.source)
+}
+
+// Words without any definition are not offered
+fn en_autocomplete_sql() -> String {
+    let short = short_definition_sql::<EnShortDefinitionSource>(None);
+    format!(
+        "SELECT Word, ({}) AS is_verified, {} AS source FROM en_Dict WHERE {} IS NOT NULL",
+        EN_HAS_DEFINITION, short.source, short.definition
+    )
+}
+
+fn ru_autocomplete_sql() -> String {
+    format!("SELECT Word, 1 AS is_verified, {} AS source FROM ru_Dict", RU_SHORT_DESC_SOURCE)
+}
+
+fn en_key(word: &str) -> String {
+    word.trim().to_lowercase()
+}
+
+fn ru_key(word: &str) -> String {
+    fold_ru_word(word)
+}
+
+async fn load_index<S>(
+    pool: &SqlitePool,
+    sql: String,
+    to_key: fn(&str) -> String,
+    compare_words: fn(&str, &str) -> Ordering,
+) -> Result<Arc<PrefixIndex<S>>, String>
+where
+    S: Copy + Send + Unpin,
+    for<'r> S: sqlx::Decode<'r, sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite>,
+{
+    let rows = sqlx::query_as::<_, CompletionRow<S>>(&sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    PrefixIndex::build(rows, to_key, compare_words).map(Arc::new)
+}
+
+#[command]
+pub async fn autocomplete_km(
+    state: State<'_, AppState>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<KmShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.km_index(&pool).await?;
+
+    Ok(index.complete(&prefix, limit.unwrap_or(DEFAULT_AUTOCOMPLETE_LIMIT)))
+}
+
+#[command]
+pub async fn autocomplete_en(
+    state: State<'_, AppState>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<EnShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.en_index(&pool).await?;
+
+    Ok(index.complete(&prefix, limit.unwrap_or(DEFAULT_AUTOCOMPLETE_LIMIT)))
+}
+
+#[command]
+pub async fn autocomplete_ru(
+    state: State<'_, AppState>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<RuShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.ru_index(&pool).await?;
+
+    Ok(index.complete(&prefix, limit.unwrap_or(DEFAULT_AUTOCOMPLETE_LIMIT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(words: &[(&str, bool)]) -> PrefixIndex<()> {
+        let rows = words
+            .iter()
+            .map(|&(word, is_verified)| CompletionRow { word: word.to_string(), is_verified, source: None })
+            .collect();
+        PrefixIndex::build(rows, en_key, str::cmp).unwrap()
+    }
+
+    fn complete(index: &PrefixIndex<()>, prefix: &str, limit: usize) -> Vec<String> {
+        index.complete(prefix, limit).into_iter().map(|c| c.word).collect()
+    }
+
+    #[test]
+    fn exact_then_verified_then_shorter_then_alphabetical() {
+        let index = index(&[
+            ("cats", true),
+            ("cat", false),
+            ("catalog", true),
+            ("cab", true),
+            ("catch", false),
+            ("dog", true),
+        ]);
+        assert_eq!(complete(&index, "cat", 10), ["cat", "cats", "catalog", "catch"]);
+        assert_eq!(complete(&index, "CA", 10), ["cab", "cats", "catalog", "cat", "catch"]);
+        assert_eq!(complete(&index, "x", 10), Vec::<String>::new());
+        assert_eq!(complete(&index, "", 10), Vec::<String>::new());
+    }
+
+    #[test]
+    fn small_limits_keep_the_best_of_many() {
+        let words: Vec<String> = (0..500).map(|i| format!("a{:03}", 499 - i)).collect();
+        let mut rows: Vec<(&str, bool)> = words.iter().map(|w| (w.as_str(), false)).collect();
+        rows.push(("a", false));
+        rows.push(("a999x", true));
+        let index = index(&rows);
+        for limit in [0, 1, 2, 3, 7] {
+            let best = ["a", "a999x", "a000", "a001", "a002", "a003", "a004"];
+            let expected: Vec<&str> = best.into_iter().take(limit).collect();
+            assert_eq!(complete(&index, "a", limit), expected, "limit {}", limit);
+        }
+        assert_eq!(complete(&index, "a", 1000).len(), 502);
+    }
+
+    #[test]
+    fn is_verified_looks_up_the_exact_headword() {
+        let index = index(&[("Cat", true), ("cat", false), ("dog", true)]);
+        assert!(index.is_verified("Cat"));
+        assert!(!index.is_verified("cat"));
+        assert!(index.is_verified("dog"));
+        assert!(!index.is_verified("do"));
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[derive(Serialize, sqlx::FromRow)]
@@ -8,7 +8,7 @@ pub struct WordRow {
 }
 
 #[repr(i32)]
-#[derive(Serialize, Clone, Copy, Debug, sqlx::Type)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, sqlx::Type)]
 pub enum EnShortDefinitionSource {
     Desc = 1,
     EnKmCom = 2,
@@ -16,13 +16,13 @@ pub enum EnShortDefinitionSource {
 }
 
 #[repr(i32)]
-#[derive(Serialize, Clone, Copy, Debug, sqlx::Type)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, sqlx::Type)]
 pub enum RuShortDefinitionSource {
     Desc = 1,
 }
 
 #[repr(i32)]
-#[derive(Serialize, Clone, Copy, Debug, sqlx::Type)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, sqlx::Type)]
 pub enum KmShortDefinitionSource {
     FromCsvRawHtml = 1,
     EnKmCom = 2,
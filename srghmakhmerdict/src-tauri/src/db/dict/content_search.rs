@@ -0,0 +1,283 @@
+use serde::Serialize;
+use sqlx::SqliteConnection;
+use std::cmp::Reverse;
+use super::pagination::PageParams;
+use super::search_query::{CompiledQuery, SearchableSource, compile_query, parse_query};
+
+const SNIPPET_CONTEXT_BEFORE: usize = 40;
+const SNIPPET_MAX_CHARS: usize = 160;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SnippetHighlight {
+    // UTF-16 code units, same as JS String.prototype.slice
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SearchSnippet {
+    pub text: String,
+    pub highlights: Vec<SnippetHighlight>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ContentSearchHit<S> {
+    pub word: String,
+    pub source: S,
+    // Higher is better (negated FTS5 bm25), 0 if every term was too short for the index
+    pub score: f64,
+    pub snippet: SearchSnippet,
+}
+
+fn fold_char(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+// Char ranges of every non-overlapping case-insensitive occurrence of `needle`
+fn find_matches(haystack: &[char], needle: &[char]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return result;
+    }
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if haystack[i..i + needle.len()]
+            .iter()
+            .zip(needle)
+            .all(|(h, n)| fold_char(*h) == fold_char(*n))
+        {
+            result.push((i, i + needle.len()));
+            i += needle.len();
+        } else {
+            i += 1;
+        }
+    }
+    result
+}
+
+fn utf16_len(chars: &[char]) -> usize {
+    chars.iter().map(|c| c.len_utf16()).sum()
+}
+
+/// Cuts a window of `text` around the first occurrence of any of `terms`, or None if none of them is in it
+pub fn make_snippet(text: &str, terms: &[&str]) -> Option<SearchSnippet> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut matches: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            let needle: Vec<char> = term.trim().chars().collect();
+            find_matches(&chars, &needle)
+        })
+        .collect();
+    // one term can be a part of another (ស្រ of ស្រឡាញ់): of ranges starting at the same char the longest
+    // goes first, then the first of overlapping ranges is kept
+    matches.sort_unstable_by_key(|&(start, end)| (start, Reverse(end)));
+    matches.dedup_by(|next, prev| next.0 < prev.1);
+    let (first_start, _) = *matches.first()?;
+
+    let mut start = first_start.saturating_sub(SNIPPET_CONTEXT_BEFORE);
+    let end = (start + SNIPPET_MAX_CHARS).min(chars.len());
+    if end - start < SNIPPET_MAX_CHARS {
+        start = end.saturating_sub(SNIPPET_MAX_CHARS);
+    }
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    let offset = prefix.encode_utf16().count();
+
+    let highlights = matches
+        .into_iter()
+        .filter(|&(s, e)| s >= start && e <= end)
+        .map(|(s, e)| SnippetHighlight {
+            start: offset + utf16_len(&chars[start..s]),
+            end: offset + utf16_len(&chars[start..e]),
+        })
+        .collect();
+
+    let body: String = chars[start..end].iter().collect();
+
+    Some(SearchSnippet {
+        text: format!("{}{}{}", prefix, body, suffix),
+        highlights,
+    })
+}
+
+/// The start of `text`, without highlights
+pub fn head_snippet(text: &str) -> SearchSnippet {
+    let mut body: String = text.chars().take(SNIPPET_MAX_CHARS).collect();
+    if body.len() < text.len() {
+        body.push('…');
+    }
+    SearchSnippet { text: body, highlights: vec![] }
+}
+
+/// Picks the first allowed source (in the given order) containing a positive term of the query
+/// (in a source the term is scoped to) and cuts a snippet from its plain text.
+/// If no positive term is in any of them (e.g. a query of only NOT terms), falls back to the first
+/// non-empty allowed source without highlights, and if all of them are empty to an empty snippet of the
+/// first allowed one, so that a page has every row `total` counts. None only if no source is allowed,
+/// then no row matches anyway.
+pub fn build_hit<S: SearchableSource>(
+    word: String,
+    score: f64,
+    query: &CompiledQuery<S>,
+    sources: Vec<(S, Option<String>)>,
+) -> Option<ContentSearchHit<S>> {
+    let texts: Vec<(S, String)> = sources
+        .into_iter()
+        .filter(|(source, _)| query.allowed.contains(source))
+        .filter_map(|(source, text)| text.map(|t| (source, t)))
+        .filter(|(_, text)| !text.is_empty())
+        .collect();
+
+    let found = texts.iter().find_map(|(source, text)| {
+        let terms: Vec<&str> = query
+            .positive_terms
+            .iter()
+            .filter(|t| t.sources.as_ref().is_none_or(|only| only.contains(source)))
+            .map(|t| t.text.as_str())
+            .collect();
+        make_snippet(text, &terms).map(|snippet| (*source, snippet))
+    });
+
+    let (source, snippet) = match (found, texts.first()) {
+        (Some(found), _) => found,
+        (None, Some((source, text))) => (*source, head_snippet(text)),
+        (None, None) => (*query.allowed.first()?, SearchSnippet { text: String::new(), highlights: vec![] }),
+    };
+
+    Some(ContentSearchHit { word, source, score, snippet })
+}
+
+pub struct ContentSearchTable {
+    pub table: &'static str,
+    // select list over the `d` (the *_Dict row) and `x` (its *_Dict_text row) aliases, must include d.Word
+    pub columns: &'static str,
+}
+
+/// Parses `query` and compiles it against `t`, restricted to `sources` (all if None or empty)
+pub fn compile_content_query<S: SearchableSource>(
+    t: &ContentSearchTable,
+    query: &str,
+    sources: Option<&[S]>,
+) -> Result<CompiledQuery<S>, String> {
+    let node = parse_query::<S>(query)?;
+    Ok(compile_query(&node, t.table, sources.unwrap_or_default()))
+}
+
+/// One page of rows matching `query`, ranked by bm25 of its positive terms (then by headword length),
+/// plus the total count (taken from the cursor after the first page)
+pub async fn fetch_content_search_page<R, S>(
+    conn: &mut SqliteConnection,
+    t: &ContentSearchTable,
+    query: &CompiledQuery<S>,
+    params: &PageParams,
+) -> Result<(Vec<R>, i64), String>
+where
+    for<'r> R: sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    // terms too short for the trigram index don't rank, only filter
+    let score_sql = match query.rank_match {
+        Some(_) => format!(
+            "COALESCE((SELECT -rank FROM {table}_fts WHERE {table}_fts MATCH ? AND {table}_fts.rowid = d.rowid), 0.0)",
+            table = t.table
+        ),
+        None => "0.0".to_string(),
+    };
+    let from_where = format!(
+        "FROM {table} d JOIN {table}_text x ON x.dict_rowid = d.rowid WHERE {}",
+        query.where_sql,
+        table = t.table
+    );
+    let ranked_sql = format!(
+        "SELECT {}, {} AS score {} ORDER BY score DESC, LENGTH(d.Word) ASC LIMIT ? OFFSET ?",
+        t.columns, score_sql, from_where
+    );
+
+    let mut ranked = sqlx::query_as::<_, R>(&ranked_sql);
+    if let Some(rank_match) = &query.rank_match {
+        ranked = ranked.bind(rank_match);
+    }
+    for param in &query.params {
+        ranked = ranked.bind(param);
+    }
+    let rows = ranked
+        .bind(params.fetch_limit())
+        .bind(params.offset())
+        .fetch_all(&mut *conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total = match params.total_estimate() {
+        Some(total) => total,
+        None => {
+            let count_sql = format!("SELECT COUNT(*) {}", from_where);
+            let mut count = sqlx::query_scalar::<_, i64>(&count_sql);
+            for param in &query.params {
+                count = count.bind(param);
+            }
+            count.fetch_one(&mut *conn).await.map_err(|e| e.to_string())?
+        }
+    };
+
+    Ok((rows, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the highlighted parts of a snippet, sliced by UTF-16 offsets as the frontend does
+    fn highlighted(snippet: &SearchSnippet) -> Vec<String> {
+        let units: Vec<u16> = snippet.text.encode_utf16().collect();
+        snippet
+            .highlights
+            .iter()
+            .map(|h| String::from_utf16(&units[h.start..h.end]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn offsets_are_utf16_units() {
+        // 🐈 is two UTF-16 units, Khmer chars one each
+        let snippet = make_snippet("🐈 ឆ្មា is a Cat 🐈 cat", &["cat", "ឆ្មា"]).unwrap();
+        assert_eq!(highlighted(&snippet), ["ឆ្មា", "Cat", "cat"]);
+        assert_eq!((snippet.highlights[0].start, snippet.highlights[0].end), (3, 7));
+    }
+
+    #[test]
+    fn containing_term_wins() {
+        let snippet = make_snippet("ខ្ញុំស្រឡាញ់អ្នក", &["ស្រ", "ស្រឡាញ់"]).unwrap();
+        assert_eq!(highlighted(&snippet), ["ស្រឡាញ់"]);
+        let snippet = make_snippet("ខ្ញុំស្រឡាញ់អ្នក", &["ស្រឡាញ់", "ឡាញ់អ្នក"]).unwrap();
+        assert_eq!(highlighted(&snippet), ["ស្រឡាញ់"]);
+    }
+
+    #[test]
+    fn window_is_clamped_to_the_text() {
+        let text = format!("{}needle{}", "a".repeat(100), "b".repeat(200));
+        let snippet = make_snippet(&text, &["needle"]).unwrap();
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert_eq!(snippet.text.chars().count(), SNIPPET_MAX_CHARS + 2);
+        assert_eq!(snippet.highlights[0].start, 1 + SNIPPET_CONTEXT_BEFORE);
+        assert_eq!(highlighted(&snippet), ["needle"]);
+
+        // near the end the window moves back to stay full
+        let text = format!("{}needle", "a".repeat(300));
+        let snippet = make_snippet(&text, &["needle"]).unwrap();
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with("needle"));
+        assert_eq!(snippet.text.chars().count(), SNIPPET_MAX_CHARS + 1);
+        assert_eq!(highlighted(&snippet), ["needle"]);
+
+        // a short text is shown whole
+        let snippet = make_snippet("a needle", &["NEEDLE"]).unwrap();
+        assert_eq!(snippet.text, "a needle");
+        assert_eq!(highlighted(&snippet), ["needle"]);
+
+        assert!(make_snippet("a needle", &["thread"]).is_none());
+    }
+}
@@ -1,16 +1,18 @@
 use crate::app_state::AppState;
+use crate::english::lemmatize::{LemmaStep, lemma_candidates};
 use serde::Serialize;
-use std::collections::HashMap;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use tauri::ipc::Channel;
 use tauri::{State, command};
+use super::content_search::{ContentSearchHit, ContentSearchTable, build_hit, compile_content_query, fetch_content_search_page};
+use super::search_query::CompiledQuery;
+use super::pagination::{Page, PageParams};
+use super::stream::{BatchSender, StreamEvent, StreamedDetail, for_each_row};
+use super::source_priority::{SourcePreferences, short_definition_sql};
 use super::common::{WordRow, ShortDefinitionEn, EnShortDefinitionSource, validate_words_not_empty, get_placeholders, to_strict_map, to_optional_map, to_optional_map_wrap, fetch_many};
 
-// TODO: make COALESCE fields provided by user
-const EN_SHORT_DESC_COALESCE: &str = "COALESCE(Desc, en_km_com, Desc_en_only)";
-const EN_SHORT_DESC_SOURCE: &str = "(CASE
-    WHEN Desc IS NOT NULL THEN 1
-    WHEN en_km_com IS NOT NULL THEN 2
-    WHEN Desc_en_only IS NOT NULL THEN 3
-    ELSE 0 END)";
+pub const EN_HAS_DEFINITION: &str = "Desc IS NOT NULL OR en_km_com IS NOT NULL";
 
 #[derive(Serialize, sqlx::FromRow)]
 pub struct WordDetailEn {
@@ -26,6 +28,17 @@ pub struct WordDetailEn {
     #[sqlx()]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub en_km_com: Option<String>,
+    // set when the requested form was resolved by the lemmatizer
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lemma: Option<EnLemmaMatch>,
+}
+
+/// How a form that is neither a headword nor in en_Extension was resolved, e.g. running -> run by -ing
+#[derive(Serialize, Clone, Debug)]
+pub struct EnLemmaMatch {
+    pub lemma: String,
+    pub steps: Vec<LemmaStep>,
 }
 
 #[derive(Serialize, sqlx::FromRow)]
@@ -42,6 +55,21 @@ pub struct WordDetailEnRaw {
     pub en_km_com: Option<String>,
 }
 
+impl WordDetailEn {
+    /// Drops the sources the user hid
+    pub fn without_excluded(mut self, prefs: Option<&SourcePreferences<EnShortDefinitionSource>>) -> Self {
+        let Some(prefs) = prefs else { return self };
+        for source in &prefs.excluded {
+            match source {
+                EnShortDefinitionSource::Desc => self.desc = None,
+                EnShortDefinitionSource::EnKmCom => self.en_km_com = None,
+                EnShortDefinitionSource::DescEnOnly => self.desc_en_only = None,
+            }
+        }
+        self
+    }
+}
+
 impl From<WordDetailEnRaw> for WordDetailEn {
     fn from(raw: WordDetailEnRaw) -> Self {
         Self {
@@ -49,6 +77,7 @@ impl From<WordDetailEnRaw> for WordDetailEn {
             desc: raw.desc,
             desc_en_only: raw.desc_en_only,
             en_km_com: raw.en_km_com,
+            lemma: None,
         }
     }
 }
@@ -56,7 +85,7 @@ impl From<WordDetailEnRaw> for WordDetailEn {
 #[command]
 pub async fn get_en_words(state: State<'_, AppState>) -> Result<Vec<String>, String> {
     let pool = state.get_pool().await?;
-    let sql = "SELECT Word FROM en_Dict WHERE Desc IS NOT NULL OR en_km_com IS NOT NULL ORDER BY Word ASC";
+    let sql = format!("SELECT Word FROM en_Dict WHERE {} ORDER BY Word ASC", EN_HAS_DEFINITION);
     let rows = sqlx::query_as::<_, WordRow>(&sql)
         .fetch_all(&pool)
         .await
@@ -64,41 +93,199 @@ pub async fn get_en_words(state: State<'_, AppState>) -> Result<Vec<String>, Str
     Ok(rows.into_iter().map(|r| r.word).collect())
 }
 
+#[command]
+pub async fn stream_en_words(
+    state: State<'_, AppState>,
+    on_event: Channel<StreamEvent<String>>,
+) -> Result<(), String> {
+    let pool = state.get_pool().await?;
+    let sql = format!("SELECT Word FROM en_Dict WHERE {} ORDER BY Word ASC", EN_HAS_DEFINITION);
+
+    let mut sender = BatchSender::new(&on_event);
+    for_each_row::<WordRow>(&pool, &sql, &[], |r| sender.push(r.word)).await?;
+    sender.finish()
+}
+
+#[command]
+pub async fn get_en_words_page(
+    state: State<'_, AppState>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+) -> Result<Page<String>, String> {
+    let params = PageParams::new(cursor, page_size)?;
+    let pool = state.get_pool().await?;
+
+    let sql = format!(
+        "SELECT Word FROM en_Dict WHERE ({}) AND Word > ? ORDER BY Word ASC LIMIT ?",
+        EN_HAS_DEFINITION
+    );
+    let rows = sqlx::query_as::<_, WordRow>(&sql)
+        .bind(params.after_word())
+        .bind(params.fetch_limit())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total = match params.total_estimate() {
+        Some(total) => total,
+        None => sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM en_Dict WHERE {}", EN_HAS_DEFINITION))
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+
+    Ok(params.keyset_page(rows, total, |r| r.word.clone(), |r| Some(r.word)))
+}
+
+/// First lemmatizer candidate that is a headword with a definition
+async fn resolve_en_lemma(pool: &SqlitePool, word: &str) -> Result<Option<EnLemmaMatch>, String> {
+    let candidates = lemma_candidates(word);
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+
+    let sql = format!(
+        "SELECT Word FROM en_Dict WHERE Word IN ({}) AND ({})",
+        get_placeholders(candidates.len()),
+        EN_HAS_DEFINITION
+    );
+    let lemmas: Vec<String> = candidates.iter().map(|c| c.lemma.clone()).collect();
+    let rows: Vec<WordRow> = fetch_many(pool, &lemmas, sql).await?;
+    let found: HashSet<String> = rows.into_iter().map(|r| r.word).collect();
+
+    Ok(candidates
+        .into_iter()
+        .find(|c| found.contains(&c.lemma))
+        .map(|c| EnLemmaMatch { lemma: c.lemma, steps: c.steps }))
+}
+
 #[command]
 pub async fn get_word_detail_en(
     state: State<'_, AppState>,
     word: String,
     use_extension_db: bool,
+    sources: Option<SourcePreferences<EnShortDefinitionSource>>,
 ) -> Result<Option<WordDetailEn>, String> {
     let pool = state.get_pool().await?;
-    let sql = if use_extension_db {
-        "SELECT DISTINCT d.* FROM en_Dict d JOIN en_Extension e ON d.Word = e.Extension WHERE e.Word = ?"
-    } else {
-        "SELECT * FROM en_Dict WHERE Word = ?"
-    };
-    let row = sqlx::query_as::<_, WordDetailEn>(&sql)
-        .bind(word)
+    let direct_sql = "SELECT * FROM en_Dict WHERE Word = ?";
+    let extension_sql = "SELECT DISTINCT d.* FROM en_Dict d JOIN en_Extension e ON d.Word = e.Extension WHERE e.Word = ?";
+
+    let sqls: &[&str] = if use_extension_db { &[direct_sql, extension_sql] } else { &[direct_sql] };
+    for sql in sqls {
+        let row = sqlx::query_as::<_, WordDetailEn>(sql)
+            .bind(&word)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(row) = row {
+            return Ok(Some(row.without_excluded(sources.as_ref())));
+        }
+    }
+
+    if !use_extension_db {
+        return Ok(None);
+    }
+
+    // forms en_Extension doesn't list: irregular, rare, British spellings, wrong case
+    let Some(lemma) = resolve_en_lemma(&pool, &word).await? else { return Ok(None) };
+    let row = sqlx::query_as::<_, WordDetailEn>(direct_sql)
+        .bind(&lemma.lemma)
         .fetch_optional(&pool)
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(row)
+    Ok(row.map(|r| WordDetailEn { lemma: Some(lemma), ..r }.without_excluded(sources.as_ref())))
+}
+
+/// HTML-free text of the definitions (see plain_text_index.rs), for TTS and copying. Same keys as WordDetailEn
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct WordPlainTextEn {
+    #[sqlx(rename = "Desc")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[sqlx(rename = "Desc_en_only")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc_en_only: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub en_km_com: Option<String>,
+}
+
+#[command]
+pub async fn get_word_plain_text_en(state: State<'_, AppState>, word: String) -> Result<Option<WordPlainTextEn>, String> {
+    let pool = state.get_pool().await?;
+    let sql = "SELECT x.* FROM en_Dict d JOIN en_Dict_text x ON x.dict_rowid = d.rowid WHERE d.Word = ?";
+    sqlx::query_as::<_, WordPlainTextEn>(sql)
+        .bind(&word)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+const EN_CONTENT_SEARCH: ContentSearchTable = ContentSearchTable {
+    table: "en_Dict",
+    columns: "d.Word, x.Desc, x.en_km_com, x.Desc_en_only",
+};
+
+#[derive(sqlx::FromRow)]
+pub struct EnContentSearchRow {
+    #[sqlx(rename = "Word")]
+    pub word: String,
+    pub score: f64,
+    #[sqlx(rename = "Desc")]
+    pub desc: Option<String>,
+    pub en_km_com: Option<String>,
+    #[sqlx(rename = "Desc_en_only")]
+    pub desc_en_only: Option<String>,
+}
+
+impl EnContentSearchRow {
+    fn into_hit(self, query: &CompiledQuery<EnShortDefinitionSource>) -> Option<ContentSearchHit<EnShortDefinitionSource>> {
+        build_hit(
+            self.word,
+            self.score,
+            query,
+            vec![
+                (EnShortDefinitionSource::Desc, self.desc),
+                (EnShortDefinitionSource::EnKmCom, self.en_km_com),
+                (EnShortDefinitionSource::DescEnOnly, self.desc_en_only),
+            ],
+        )
+    }
+}
+
+/// One page of `search_en_content`, also used by the global search
+pub async fn find_en_content(
+    conn: &mut SqliteConnection,
+    query: &str,
+    sources: Option<&[EnShortDefinitionSource]>,
+    params: &PageParams,
+) -> Result<Page<ContentSearchHit<EnShortDefinitionSource>>, String> {
+    let compiled = compile_content_query(&EN_CONTENT_SEARCH, query, sources)?;
+
+    let (rows, total) = fetch_content_search_page::<EnContentSearchRow, _>(conn, &EN_CONTENT_SEARCH, &compiled, params).await?;
+
+    Ok(params.offset_page(rows, total, |r| r.into_hit(&compiled)))
 }
 
 #[command]
 pub async fn search_en_content(
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<String>, String> {
+    sources: Option<Vec<EnShortDefinitionSource>>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+    request_key: Option<String>,
+) -> Result<Page<ContentSearchHit<EnShortDefinitionSource>>, String> {
+    let params = PageParams::new(cursor, page_size)?;
     let pool = state.get_pool().await?;
-    let pattern = format!("%{}%", query);
-    let sql = "SELECT Word FROM en_Dict WHERE Desc LIKE ? ORDER BY LENGTH(Word) ASC LIMIT 50";
-    let rows = sqlx::query_as::<_, WordRow>(&sql)
-        .bind(pattern)
-        .fetch_all(&pool)
+
+    state
+        .inflight
+        .run(request_key.as_deref(), |scope| async move {
+            let mut conn = scope.acquire(&pool).await?;
+            find_en_content(&mut conn, &query, sources.as_deref(), &params).await
+        })
         .await
-        .map_err(|e| e.to_string())?;
-    Ok(rows.into_iter().map(|r| r.word).collect())
 }
 
 #[derive(Serialize, sqlx::FromRow)]
@@ -141,53 +328,65 @@ pub async fn get_en_km_com_images_ocr(
 pub struct EnWordDetailShortRow {
     #[sqlx(rename = "Word")]
     pub word: String,
-    pub definition: String,
-    pub source: EnShortDefinitionSource,
+    // NULL if none of the allowed sources has a definition
+    pub definition: Option<String>,
+    pub source: Option<EnShortDefinitionSource>,
+}
+
+impl EnWordDetailShortRow {
+    fn into_short(self) -> Option<ShortDefinitionEn> {
+        Some(ShortDefinitionEn { definition: self.definition?, source: self.source? })
+    }
 }
 
 #[command]
 pub async fn en_for_many_short_description_none_if_word_not_found(
     state: State<'_, AppState>,
     words: Vec<String>,
+    sources: Option<SourcePreferences<EnShortDefinitionSource>>,
 ) -> Result<HashMap<String, Option<ShortDefinitionEn>>, String> {
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
+    let short = short_definition_sql(sources.as_ref());
     let sql = format!(
         "SELECT Word, {} as definition, {} as source FROM en_Dict WHERE Word IN ({})",
-        EN_SHORT_DESC_COALESCE,
-        EN_SHORT_DESC_SOURCE,
+        short.definition,
+        short.source,
         get_placeholders(words.len())
     );
 
     let rows: Vec<EnWordDetailShortRow> = fetch_many(&pool, &words, sql).await?;
 
-    Ok(to_optional_map(words, rows, |r| r.word.clone(), |r| Some(ShortDefinitionEn { definition: r.definition, source: r.source })))
+    Ok(to_optional_map(words, rows, |r| r.word.clone(), |r| r.into_short()))
 }
 
 #[command]
 pub async fn en_for_many_short_description_throws_if_word_not_found(
     state: State<'_, AppState>,
     words: Vec<String>,
+    sources: Option<SourcePreferences<EnShortDefinitionSource>>,
 ) -> Result<HashMap<String, ShortDefinitionEn>, String> {
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
+    let short = short_definition_sql(sources.as_ref());
     let sql = format!(
         "SELECT Word, {} as definition, {} as source FROM en_Dict WHERE Word IN ({})",
-        EN_SHORT_DESC_COALESCE,
-        EN_SHORT_DESC_SOURCE,
+        short.definition,
+        short.source,
         get_placeholders(words.len())
     );
 
     let rows: Vec<EnWordDetailShortRow> = fetch_many(&pool, &words, sql).await?;
 
-    to_strict_map(
-        words,
-        rows,
-        |r| r.word.clone(),
-        |r| ShortDefinitionEn { definition: r.definition, source: r.source }
-    )
+    // a word with no definition from the allowed sources counts as not found
+    let rows: Vec<(String, ShortDefinitionEn)> = rows.into_iter().filter_map(|r| {
+        let word = r.word.clone();
+        r.into_short().map(|d| (word, d))
+    }).collect();
+
+    to_strict_map(words, rows, |(w, _)| w.clone(), |(_, d)| d)
 }
 
 #[command]
@@ -220,3 +419,26 @@ pub async fn en_for_many_full_details_none_if_word_not_found(
 
     Ok(to_optional_map_wrap(words, rows, |r| r.word.clone(), WordDetailEn::from))
 }
+
+#[command]
+pub async fn stream_en_full_details(
+    state: State<'_, AppState>,
+    words: Vec<String>,
+    on_event: Channel<StreamEvent<StreamedDetail<WordDetailEn>>>,
+) -> Result<(), String> {
+    validate_words_not_empty(&words)?;
+
+    let pool = state.get_pool().await?;
+    let sql = format!("SELECT * FROM en_Dict WHERE Word IN ({})", get_placeholders(words.len()));
+
+    let mut sender = BatchSender::new(&on_event);
+    let mut sent: HashSet<String> = HashSet::new();
+
+    for_each_row::<WordDetailEnRaw>(&pool, &sql, &words, |raw| {
+        sent.insert(raw.word.clone());
+        sender.push(StreamedDetail { word: raw.word.clone(), detail: Some(WordDetailEn::from(raw)) })
+    })
+    .await?;
+
+    sender.finish_details(&words, &sent)
+}
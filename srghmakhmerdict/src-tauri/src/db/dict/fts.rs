@@ -0,0 +1,131 @@
+use sqlx::{SqliteConnection, SqlitePool};
+
+// Bump when the layout of the *_fts tables changes
+const FTS_VERSION: &str = "2";
+
+// The trigram tokenizer indexes every 3-char window, so it works for Khmer (no spaces between words)
+// but can't match anything shorter than 3 chars
+pub const FTS_MIN_QUERY_CHARS: usize = 3;
+
+// Contentless tables: only the index is stored, rowid points back to the *_Dict row.
+// Filled from the *_Dict_text tables (see plain_text_index.rs), so markup is never indexed.
+// Column order of km_Dict_fts follows KmShortDefinitionSource, of en_Dict_fts follows EnShortDefinitionSource.
+const CREATE_FTS_TABLES_SQL: &str = "
+    DROP TABLE IF EXISTS en_Dict_fts;
+    DROP TABLE IF EXISTS km_Dict_fts;
+    DROP TABLE IF EXISTS ru_Dict_fts;
+
+    CREATE VIRTUAL TABLE en_Dict_fts USING fts5(
+        Desc, en_km_com, Desc_en_only,
+        content='', tokenize='trigram'
+    );
+    CREATE VIRTUAL TABLE km_Dict_fts USING fts5(
+        from_csv_rawHtml, en_km_com, Desc, from_chuon_nath, wiktionary, from_russian_wiki, gorgoniev,
+        content='', tokenize='trigram'
+    );
+    CREATE VIRTUAL TABLE ru_Dict_fts USING fts5(
+        Desc,
+        content='', tokenize='trigram'
+    );
+";
+
+const FILL_FTS_TABLES_SQL: &str = "
+    INSERT INTO en_Dict_fts (rowid, Desc, en_km_com, Desc_en_only)
+        SELECT dict_rowid, Desc, en_km_com, Desc_en_only FROM en_Dict_text;
+
+    INSERT INTO km_Dict_fts (rowid, from_csv_rawHtml, en_km_com, Desc, from_chuon_nath, wiktionary, from_russian_wiki, gorgoniev)
+        SELECT
+            dict_rowid,
+            from_csv_rawHtml,
+            en_km_com,
+            Desc,
+            -- original (km) and translated text are searched as one source
+            NULLIF(TRIM(COALESCE(from_chuon_nath, '') || ' ' || COALESCE(from_chuon_nath_translated, '')), ''),
+            Wiktionary,
+            from_russian_wiki,
+            gorgoniev
+        FROM km_Dict_text;
+
+    INSERT INTO ru_Dict_fts (rowid, Desc)
+        SELECT dict_rowid, Desc FROM ru_Dict_text;
+";
+
+/// Version the derived table(s) recorded under `key` in the metadata table were last built with, None if never.
+/// Every builder compares it with its own version constant and rebuilds on a difference, so bumping the constant
+/// rebuilds the table on existing installs
+pub async fn index_version(pool: &SqlitePool, key: &str) -> Result<Option<String>, String> {
+    sqlx::query("CREATE TABLE IF NOT EXISTS metadata (key TEXT PRIMARY KEY, value TEXT)")
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    sqlx::query_scalar::<_, String>("SELECT value FROM metadata WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Records `version` under `key`, in the transaction that built the table
+pub async fn set_index_version(conn: &mut SqliteConnection, key: &str, version: &str) -> Result<(), String> {
+    sqlx::query("INSERT OR REPLACE INTO metadata (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(version)
+        .execute(conn)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Builds en_Dict_fts / km_Dict_fts / ru_Dict_fts when FTS_VERSION changed,
+/// or the plain text they index was rebuilt
+pub async fn ensure_fts_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "fts_version").await?.as_deref() == Some(FTS_VERSION) {
+        println!("✅ FTS index is up to date (v{}).", FTS_VERSION);
+        return Ok(());
+    }
+
+    println!("🔎 Building FTS index...");
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_FTS_TABLES_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create FTS tables: {}", e))?;
+
+    sqlx::raw_sql(FILL_FTS_TABLES_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to fill FTS tables: {}", e))?;
+
+    set_index_version(&mut tx, "fts_version", FTS_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ FTS index built (v{}).", FTS_VERSION);
+
+    Ok(())
+}
+
+/// Turns user input into a single FTS5 phrase, or None if it is too short for the trigram index
+pub fn to_fts_phrase(query: &str) -> Option<String> {
+    let query = query.trim();
+    if query.chars().count() < FTS_MIN_QUERY_CHARS {
+        return None;
+    }
+    Some(format!("\"{}\"", query.replace('"', "\"\"")))
+}
+
+/// `%query%` with the LIKE wildcards of the query escaped, for `LIKE ? ESCAPE '\\'`
+pub fn to_like_pattern(query: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in query.trim().chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
@@ -0,0 +1,210 @@
+use crate::app_state::AppState;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use tauri::{State, command};
+use super::autocomplete::{Completion, PrefixIndex};
+use super::common::{EnShortDefinitionSource, KmShortDefinitionSource, RuShortDefinitionSource};
+use super::content_search::{ContentSearchHit, SearchSnippet};
+use super::en::find_en_content;
+use super::inflight::QueryScope;
+use super::km::find_km_content;
+use super::pagination::PageParams;
+use super::ru::find_ru_content;
+use super::search_query::{SearchableSource, parse_query};
+
+const DEFAULT_GLOBAL_SEARCH_LIMIT: usize = 50;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchKind {
+    ExactHeadword,
+    Prefix,
+    Content,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LanguageHit<S> {
+    pub word: String,
+    pub kind: MatchKind,
+    pub is_verified: bool,
+    // headword hits: source of the short definition, None if there is none
+    pub source: Option<S>,
+    // content hits only: negated bm25, comparable within one language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<SearchSnippet>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "language", rename_all = "lowercase")]
+pub enum GlobalSearchHit {
+    En(LanguageHit<EnShortDefinitionSource>),
+    Km(LanguageHit<KmShortDefinitionSource>),
+    Ru(LanguageHit<RuShortDefinitionSource>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Language {
+    En,
+    Km,
+    Ru,
+}
+
+impl GlobalSearchHit {
+    fn language(&self) -> Language {
+        match self {
+            GlobalSearchHit::En(_) => Language::En,
+            GlobalSearchHit::Km(_) => Language::Km,
+            GlobalSearchHit::Ru(_) => Language::Ru,
+        }
+    }
+
+    // (kind, is_verified, word, score) without the source type
+    fn parts(&self) -> (MatchKind, bool, &str, Option<f64>) {
+        match self {
+            GlobalSearchHit::En(h) => (h.kind, h.is_verified, &h.word, h.score),
+            GlobalSearchHit::Km(h) => (h.kind, h.is_verified, &h.word, h.score),
+            GlobalSearchHit::Ru(h) => (h.kind, h.is_verified, &h.word, h.score),
+        }
+    }
+}
+
+/// The dictionary whose headwords are written in the script of the query, if any
+fn query_language(query: &str) -> Option<Language> {
+    query.chars().find_map(|c| match c {
+        '\u{1780}'..='\u{17FF}' | '\u{19E0}'..='\u{19FF}' => Some(Language::Km),
+        '\u{0400}'..='\u{04FF}' => Some(Language::Ru),
+        _ if c.is_ascii_alphabetic() => Some(Language::En),
+        _ => None,
+    })
+}
+
+fn headword_hits<S: Copy>(index: &PrefixIndex<S>, query: &str, limit: usize) -> Vec<LanguageHit<S>> {
+    let key = index.key(query);
+    index
+        .complete(query, limit)
+        .into_iter()
+        .map(|Completion { word, is_verified, source }| LanguageHit {
+            kind: if index.key(&word) == key { MatchKind::ExactHeadword } else { MatchKind::Prefix },
+            word,
+            is_verified,
+            source,
+            score: None,
+            snippet: None,
+        })
+        .collect()
+}
+
+// A query that is not valid content search syntax ("(" alone) still finds headwords,
+// so only a parse error skips the content of a language; any other failure is returned
+fn is_content_query<S: SearchableSource>(query: &str) -> bool {
+    parse_query::<S>(query).is_ok()
+}
+
+fn content_hits<S: Copy>(index: &PrefixIndex<S>, items: Vec<ContentSearchHit<S>>) -> Vec<LanguageHit<S>> {
+    items
+        .into_iter()
+        .map(|hit| LanguageHit {
+            is_verified: index.is_verified(&hit.word),
+            word: hit.word,
+            kind: MatchKind::Content,
+            source: Some(hit.source),
+            score: Some(hit.score),
+            snippet: Some(hit.snippet),
+        })
+        .collect()
+}
+
+type LanguageContent = (
+    Vec<ContentSearchHit<EnShortDefinitionSource>>,
+    Vec<ContentSearchHit<KmShortDefinitionSource>>,
+    Vec<ContentSearchHit<RuShortDefinitionSource>>,
+);
+
+async fn search_language_content(
+    scope: &QueryScope,
+    pool: &SqlitePool,
+    query: &str,
+    limit: usize,
+) -> Result<LanguageContent, String> {
+    // the hits are merged into one list without a total, so no language counts its matches
+    let params = PageParams::uncounted(Some(limit as u32))?;
+    // each language holds one connection only while its own query runs
+    tokio::try_join!(
+        async {
+            if !is_content_query::<EnShortDefinitionSource>(query) {
+                return Ok(vec![]);
+            }
+            find_en_content(&mut *scope.acquire(pool).await?, query, None, &params).await.map(|p| p.items)
+        },
+        async {
+            if !is_content_query::<KmShortDefinitionSource>(query) {
+                return Ok(vec![]);
+            }
+            find_km_content(&mut *scope.acquire(pool).await?, query, None, &params).await.map(|p| p.items)
+        },
+        async {
+            if !is_content_query::<RuShortDefinitionSource>(query) {
+                return Ok(vec![]);
+            }
+            find_ru_content(&mut *scope.acquire(pool).await?, query, None, &params).await.map(|p| p.page.items)
+        },
+    )
+}
+
+/// Headwords (exact, then prefix) and definitions of all three dictionaries in one ranked list.
+/// Ties are broken in favour of the dictionary written in the script of the query
+#[command]
+pub async fn search_all(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+    request_key: Option<String>,
+) -> Result<Vec<GlobalSearchHit>, String> {
+    let query = query.trim();
+    let limit = limit.unwrap_or(DEFAULT_GLOBAL_SEARCH_LIMIT);
+    if query.is_empty() || limit == 0 {
+        return Ok(vec![]);
+    }
+
+    let pool = state.get_pool().await?;
+    let (km, en, ru) = tokio::try_join!(
+        state.autocomplete.km_index(&pool),
+        state.autocomplete.en_index(&pool),
+        state.autocomplete.ru_index(&pool),
+    )?;
+
+    let mut hits: Vec<GlobalSearchHit> = Vec::new();
+    hits.extend(headword_hits(en, query, limit).into_iter().map(GlobalSearchHit::En));
+    hits.extend(headword_hits(km, query, limit).into_iter().map(GlobalSearchHit::Km));
+    hits.extend(headword_hits(ru, query, limit).into_iter().map(GlobalSearchHit::Ru));
+    let (en_content, km_content, ru_content) = state
+        .inflight
+        .run(request_key.as_deref(), |scope| async move { search_language_content(&scope, &pool, query, limit).await })
+        .await?;
+    hits.extend(content_hits(en, en_content).into_iter().map(GlobalSearchHit::En));
+    hits.extend(content_hits(km, km_content).into_iter().map(GlobalSearchHit::Km));
+    hits.extend(content_hits(ru, ru_content).into_iter().map(GlobalSearchHit::Ru));
+
+    let preferred = query_language(query);
+    hits.sort_by(|a, b| {
+        let (a_kind, a_verified, a_word, a_score) = a.parts();
+        let (b_kind, b_verified, b_word, b_score) = b.parts();
+        a_kind
+            .cmp(&b_kind)
+            .then_with(|| (Some(b.language()) == preferred).cmp(&(Some(a.language()) == preferred)))
+            .then_with(|| b_verified.cmp(&a_verified))
+            .then_with(|| b_score.partial_cmp(&a_score).unwrap_or(Ordering::Equal))
+            .then_with(|| a_word.chars().count().cmp(&b_word.chars().count()))
+            .then_with(|| a_word.cmp(b_word))
+    });
+
+    // a word found both as a headword and in content keeps only its best hit
+    let mut seen: HashSet<(Language, String)> = HashSet::new();
+    hits.retain(|hit| seen.insert((hit.language(), hit.parts().2.to_string())));
+    hits.truncate(limit);
+
+    Ok(hits)
+}
@@ -0,0 +1,261 @@
+// Pattern search over headwords:
+//   ?        exactly one cluster: a Khmer orthographic cluster (ក្រុ), or one Latin/Cyrillic letter
+//   *        any run of clusters, also a part of one (*្រ* is any word with ្រ)
+//   [កខគ]    one of these consonants (letters), [ក-ឃ] is a range, [^កខ] any other one
+// Anything else is matched literally, after the same folding as the autocomplete keys
+// (Khmer normalization, lower case, ё = е), so a subscript only matches a subscript.
+
+use crate::app_state::AppState;
+use crate::khmer::units::{split_edit_units, starts_cluster};
+use tauri::{State, command};
+use super::autocomplete::Completion;
+use super::common::{EnShortDefinitionSource, KmShortDefinitionSource, RuShortDefinitionSource};
+
+const DEFAULT_PATTERN_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    // one unit of `split_edit_units`
+    Unit(String),
+    // ranges of letters, negated: any letter outside of them
+    Set(Vec<(char, char)>, bool),
+    AnyCluster,
+    AnyRun,
+}
+
+pub struct HeadwordPattern {
+    elements: Vec<Element>,
+}
+
+fn parse_set(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Element, String> {
+    let negated = chars.next_if_eq(&'^').is_some();
+    let mut members = Vec::new();
+    loop {
+        match chars.next() {
+            Some(']') => break,
+            Some(c) => members.push(c),
+            None => return Err("Unterminated [ in pattern".to_string()),
+        }
+    }
+
+    // "-" between two chars is a range, at the edges it is itself
+    let mut ranges = Vec::with_capacity(members.len());
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            let (from, to) = (members[i], members[i + 2]);
+            if from > to {
+                return Err(format!("Invalid range {}-{} in pattern", from, to));
+            }
+            ranges.push((from, to));
+            i += 3;
+        } else {
+            ranges.push((members[i], members[i]));
+            i += 1;
+        }
+    }
+
+    if ranges.is_empty() {
+        return Err("Empty [] in pattern".to_string());
+    }
+    Ok(Element::Set(ranges, negated))
+}
+
+impl HeadwordPattern {
+    /// `pattern` has to be folded like the keys it is matched against
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let mut elements = Vec::new();
+        let mut literal = String::new();
+        let mut chars = pattern.chars().peekable();
+
+        let flush = |literal: &mut String, elements: &mut Vec<Element>| {
+            elements.extend(split_edit_units(literal).into_iter().map(|u| Element::Unit(u.to_string())));
+            literal.clear();
+        };
+
+        while let Some(c) = chars.next() {
+            match c {
+                '?' | '*' | '[' => {
+                    flush(&mut literal, &mut elements);
+                    let element = match c {
+                        '?' => Element::AnyCluster,
+                        '*' => Element::AnyRun,
+                        _ => parse_set(&mut chars)?,
+                    };
+                    // ** is the same as *
+                    if !(element == Element::AnyRun && elements.last() == Some(&Element::AnyRun)) {
+                        elements.push(element);
+                    }
+                }
+                _ => literal.push(c),
+            }
+        }
+        flush(&mut literal, &mut elements);
+
+        if elements.iter().all(|e| *e == Element::AnyRun) {
+            return Err("Pattern needs at least one letter, [set] or ?".to_string());
+        }
+        Ok(Self { elements })
+    }
+
+    /// The literal start of the pattern, every match begins with it
+    pub fn literal_prefix(&self) -> String {
+        self.elements
+            .iter()
+            .map_while(|e| match e {
+                Element::Unit(unit) => Some(unit.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn matches(&self, key: &str) -> bool {
+        let units = split_edit_units(key);
+        let (m, n) = (self.elements.len(), units.len());
+
+        // dp[i][j]: elements[i..] match units[j..]
+        let mut dp = vec![vec![false; n + 1]; m + 1];
+        dp[m][n] = true;
+        for i in (0..m).rev() {
+            for j in (0..=n).rev() {
+                dp[i][j] = match &self.elements[i] {
+                    Element::Unit(unit) => j < n && units[j] == unit && dp[i + 1][j + 1],
+                    Element::Set(ranges, negated) => {
+                        j < n && set_matches(ranges, *negated, units[j]) && dp[i + 1][j + 1]
+                    }
+                    Element::AnyRun => dp[i + 1][j] || (j < n && dp[i][j + 1]),
+                    // the rest of the current cluster, then exactly one more cluster (or a part of it)
+                    Element::AnyCluster => {
+                        let mut starts = 0;
+                        (j..n).any(|k| {
+                            if starts_cluster(units[k]) {
+                                starts += 1;
+                            }
+                            starts == 1 && dp[i + 1][k + 1]
+                        })
+                    }
+                };
+            }
+        }
+        dp[0][0]
+    }
+}
+
+fn set_matches(ranges: &[(char, char)], negated: bool, unit: &str) -> bool {
+    let mut chars = unit.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => {
+            starts_cluster(unit) && ranges.iter().any(|&(from, to)| (from..=to).contains(&c)) != negated
+        }
+        _ => false,
+    }
+}
+
+#[command]
+pub async fn search_km_pattern(
+    state: State<'_, AppState>,
+    pattern: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<KmShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.km_index(&pool).await?;
+    index.matching(&pattern, limit.unwrap_or(DEFAULT_PATTERN_LIMIT))
+}
+
+#[command]
+pub async fn search_en_pattern(
+    state: State<'_, AppState>,
+    pattern: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<EnShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.en_index(&pool).await?;
+    index.matching(&pattern, limit.unwrap_or(DEFAULT_PATTERN_LIMIT))
+}
+
+#[command]
+pub async fn search_ru_pattern(
+    state: State<'_, AppState>,
+    pattern: String,
+    limit: Option<usize>,
+) -> Result<Vec<Completion<RuShortDefinitionSource>>, String> {
+    let pool = state.get_pool().await?;
+    let index = state.autocomplete.ru_index(&pool).await?;
+    index.matching(&pattern, limit.unwrap_or(DEFAULT_PATTERN_LIMIT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::khmer::normalize::normalize_khmer;
+
+    // folded like the km autocomplete keys, as `PrefixIndex::matching` does
+    fn matches(pattern: &str, word: &str) -> bool {
+        HeadwordPattern::parse(&normalize_khmer(pattern)).unwrap().matches(&normalize_khmer(word))
+    }
+
+    #[test]
+    fn any_run_at_the_start_middle_and_end() {
+        assert!(matches("*ការ", "ការ"));
+        assert!(matches("*ការ", "សកម្មការ"));
+        assert!(!matches("*ការ", "ការងារ"));
+        assert!(matches("ក*រ", "ការ"));
+        assert!(matches("ក*រ", "កសិករ"));
+        assert!(!matches("ក*រ", "កសិកម្ម"));
+        assert!(matches("ស្រ*", "ស្រឡាញ់"));
+        assert!(matches("ស្រ*", "ស្រ"));
+        assert!(!matches("ស្រ*", "សរ"));
+        // also inside a cluster: any word with ្រ
+        assert!(matches("*្រ*", "ក្រុម"));
+        assert!(!matches("*្រ*", "ករុណា"));
+        assert!(matches("cat**", "cats"));
+    }
+
+    #[test]
+    fn any_cluster_takes_a_whole_coeng_cluster() {
+        assert!(matches("?ា", "កា"));
+        assert!(matches("?ា", "ក្រា"));
+        assert!(matches("?", "ស្ត្រ"));
+        assert!(!matches("?", "កក"));
+        assert!(!matches("??", "ក"));
+        assert!(matches("??", "ក្រម"));
+        // the rest of a cluster and the next one
+        assert!(matches("ក?", "ក្រុម"));
+        assert!(!matches("ក??", "ក្រុម"));
+        assert!(matches("c?t", "cat"));
+        assert!(!matches("c?t", "coat"));
+    }
+
+    #[test]
+    fn sets_ranges_and_negated_sets() {
+        assert!(matches("[កខ]ា", "ខា"));
+        assert!(!matches("[កខ]ា", "គា"));
+        assert!(matches("[ក-ឃ]ា", "គា"));
+        assert!(matches("[a-c-]", "-"));
+        assert!(matches("[^កខ]ា", "គា"));
+        assert!(!matches("[^កខ]ា", "កា"));
+        // a set is one letter, never a subscript or vowel sign
+        assert!(!matches("ក[្រ]", "ក្រ"));
+        assert!(!matches("[^ក]", "ា"));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let error = |pattern: &str| HeadwordPattern::parse(pattern).err().unwrap_or_default();
+        assert_eq!(error("[កខ"), "Unterminated [ in pattern");
+        assert_eq!(error("ក["), "Unterminated [ in pattern");
+        assert_eq!(error("[]"), "Empty [] in pattern");
+        assert_eq!(error("[^]"), "Empty [] in pattern");
+        assert_eq!(error("[c-a]"), "Invalid range c-a in pattern");
+        assert_eq!(error("**"), "Pattern needs at least one letter, [set] or ?");
+        assert_eq!(error(""), "Pattern needs at least one letter, [set] or ?");
+    }
+
+    #[test]
+    fn literal_prefix_ends_at_the_first_wildcard() {
+        let prefix = |pattern: &str| HeadwordPattern::parse(pattern).unwrap().literal_prefix();
+        assert_eq!(prefix("ស្រ*ញ"), "ស្រ");
+        assert_eq!(prefix("ca[tr]s"), "ca");
+        assert_eq!(prefix("?at"), "");
+    }
+}
@@ -0,0 +1,393 @@
+use crate::app_state::AppState;
+use sqlx::pool::PoolConnection;
+use sqlx::{Sqlite, SqliteConnection, SqlitePool};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{State, command};
+use tokio::sync::Notify;
+
+/// Error of a query that was superseded by a newer one with the same key, the frontend drops it
+pub const CANCELLED: &str = "CANCELLED";
+
+// SQLite VM instructions between cancellation checks
+const PROGRESS_HANDLER_OPS: i32 = 1000;
+
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<CancelTokenInner>);
+
+#[derive(Default)]
+struct CancelTokenInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub async fn cancelled(&self) {
+        let notified = self.0.notify.notified();
+        tokio::pin!(notified);
+        // register before checking the flag, so a cancel() in between is not missed
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Handed to the query of `InflightQueries::run`
+pub struct QueryScope {
+    token: CancelToken,
+}
+
+impl QueryScope {
+    /// A pool connection whose running statement is interrupted once the query is cancelled
+    pub async fn acquire(&self, pool: &SqlitePool) -> Result<InterruptibleConnection, String> {
+        let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+        let armed = Arc::new(AtomicBool::new(true));
+
+        let token = self.token.clone();
+        let handler_armed = armed.clone();
+        conn.lock_handle()
+            .await
+            .map_err(|e| e.to_string())?
+            // returning false makes SQLite abort the statement with SQLITE_INTERRUPT
+            .set_progress_handler(PROGRESS_HANDLER_OPS, move || {
+                !(handler_armed.load(Ordering::SeqCst) && token.is_cancelled())
+            });
+
+        Ok(InterruptibleConnection { conn: Some(conn), armed, token: self.token.clone() })
+    }
+}
+
+pub struct InterruptibleConnection {
+    // only None while dropping
+    conn: Option<PoolConnection<Sqlite>>,
+    armed: Arc<AtomicBool>,
+    token: CancelToken,
+}
+
+impl Deref for InterruptibleConnection {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        self.conn.as_ref().expect("connection is only taken on drop")
+    }
+}
+
+impl DerefMut for InterruptibleConnection {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        self.conn.as_mut().expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for InterruptibleConnection {
+    fn drop(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        if self.token.is_cancelled() {
+            // a cancelled statement may still be running on the connection's worker,
+            // keep the handler armed so it stops, and close the connection instead of reusing it
+            drop(conn.detach());
+        } else {
+            // the handler stays installed until the next acquire, a later cancel must not
+            // interrupt whoever gets this connection from the pool
+            self.armed.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Latest query per request key (e.g. "search-km"). Starting a query cancels the older one with the same key
+#[derive(Default)]
+pub struct InflightQueries {
+    current: Mutex<HashMap<String, (u64, CancelToken)>>,
+    next_id: AtomicU64,
+}
+
+impl InflightQueries {
+    fn begin(&self, key: &str) -> (u64, CancelToken) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let token = CancelToken::default();
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, older)) = current.insert(key.to_string(), (id, token.clone())) {
+            older.cancel();
+        }
+        (id, token)
+    }
+
+    /// true if the query is still the latest for its key, i.e. its result may be delivered
+    fn finish(&self, key: &str, id: u64) -> bool {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        match current.get(key) {
+            Some((current_id, _)) if *current_id == id => {
+                current.remove(key);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn cancel(&self, key: &str) {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, token)) = current.remove(key) {
+            token.cancel();
+        }
+    }
+
+    /// Runs `query`; without a key it can't be cancelled. A cancelled query is dropped at its next
+    /// await and its SQLite statements are interrupted, it returns `CANCELLED` and never its result
+    pub async fn run<T, F, Fut>(&self, key: Option<&str>, query: F) -> Result<T, String>
+    where
+        F: FnOnce(QueryScope) -> Fut,
+        Fut: Future<Output = Result<T, String>>,
+    {
+        let Some(key) = key else {
+            return query(QueryScope { token: CancelToken::default() }).await;
+        };
+
+        let (id, token) = self.begin(key);
+        let result = tokio::select! {
+            biased;
+            _ = token.cancelled() => Err(CANCELLED.to_string()),
+            result = query(QueryScope { token: token.clone() }) => result,
+        };
+
+        // a newer query may have started right after this one finished, its result wins
+        if !self.finish(key, id) {
+            return Err(CANCELLED.to_string());
+        }
+        result
+    }
+}
+
+/// Cancels the in-flight query started with `request_key`, e.g. when its view is closed
+#[command]
+pub async fn cancel_query(state: State<'_, AppState>, request_key: String) -> Result<(), String> {
+    state.inflight.cancel(&request_key);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CANCELLED, CancelToken, InflightQueries};
+    use sqlx::SqlitePool;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    // never finishes on its own
+    const ENDLESS_SQL: &str = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) SELECT COUNT(*) FROM c";
+    // far more VM instructions than PROGRESS_HANDLER_OPS
+    const LONG_SQL: &str = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 100000) SELECT COUNT(*) FROM c";
+
+    async fn pool(max_connections: u32) -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    async fn select_value(queries: &InflightQueries, pool: &SqlitePool, key: &str, value: i64) -> Result<i64, String> {
+        queries
+            .run(Some(key), |scope| async move {
+                let mut conn = scope.acquire(pool).await?;
+                sqlx::query_scalar::<_, i64>("SELECT ?")
+                    .bind(value)
+                    .fetch_one(&mut *conn)
+                    .await
+                    .map_err(|e| e.to_string())
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn newer_query_cancels_older_one_with_same_key() {
+        let queries = Arc::new(InflightQueries::default());
+        let (started_tx, started_rx) = oneshot::channel();
+
+        let older = tokio::spawn({
+            let queries = queries.clone();
+            async move {
+                queries
+                    .run(Some("search"), |_| async move {
+                        started_tx.send(()).unwrap();
+                        std::future::pending::<()>().await;
+                        Ok("stale")
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        let newer = queries.run(Some("search"), |_| async { Ok("fresh") }).await;
+
+        assert_eq!(newer, Ok("fresh"));
+        assert_eq!(older.await.unwrap(), Err(CANCELLED.to_string()));
+    }
+
+    #[tokio::test]
+    async fn result_finished_after_newer_start_is_not_delivered() {
+        let queries = Arc::new(InflightQueries::default());
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+        let (started_tx, started_rx) = oneshot::channel();
+
+        // the older query ignores cancellation until it has its result, like a statement
+        // that completes in the same instant the newer query starts
+        let older = tokio::spawn({
+            let queries = queries.clone();
+            async move {
+                let (id, _) = queries.begin("search");
+                started_tx.send(()).unwrap();
+                finish_rx.await.unwrap();
+                queries.finish("search", id)
+            }
+        });
+        started_rx.await.unwrap();
+
+        let (newer_id, _) = queries.begin("search");
+        finish_tx.send(()).unwrap();
+
+        assert!(!older.await.unwrap());
+        assert!(queries.finish("search", newer_id));
+    }
+
+    #[tokio::test]
+    async fn different_keys_do_not_cancel_each_other() {
+        let queries = Arc::new(InflightQueries::default());
+        let (started_tx, started_rx) = oneshot::channel();
+        let (release_tx, release_rx) = oneshot::channel::<()>();
+
+        let km = tokio::spawn({
+            let queries = queries.clone();
+            async move {
+                queries
+                    .run(Some("search-km"), |_| async move {
+                        started_tx.send(()).unwrap();
+                        release_rx.await.unwrap();
+                        Ok("km")
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        assert_eq!(queries.run(Some("search-en"), |_| async { Ok("en") }).await, Ok("en"));
+        release_tx.send(()).unwrap();
+        assert_eq!(km.await.unwrap(), Ok("km"));
+    }
+
+    #[tokio::test]
+    async fn query_without_key_is_never_cancelled() {
+        let queries = InflightQueries::default();
+        let (a, b) = tokio::join!(
+            queries.run(None, |_| async { Ok(1) }),
+            queries.run(None, |_| async { Ok(2) }),
+        );
+        assert_eq!((a, b), (Ok(1), Ok(2)));
+    }
+
+    #[tokio::test]
+    async fn explicit_cancel_stops_query() {
+        let queries = Arc::new(InflightQueries::default());
+        let (started_tx, started_rx) = oneshot::channel();
+
+        let query = tokio::spawn({
+            let queries = queries.clone();
+            async move {
+                queries
+                    .run(Some("search"), |_| async move {
+                        started_tx.send(()).unwrap();
+                        std::future::pending::<()>().await;
+                        Ok(())
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+        queries.cancel("search");
+
+        assert_eq!(query.await.unwrap(), Err(CANCELLED.to_string()));
+    }
+
+    #[tokio::test]
+    async fn cancel_interrupts_running_sqlite_statement() {
+        let pool = pool(1).await;
+        let token = CancelToken::default();
+        let scope = super::QueryScope { token: token.clone() };
+        let mut conn = scope.acquire(&pool).await.unwrap();
+
+        let statement = sqlx::query_scalar::<_, i64>(ENDLESS_SQL).fetch_one(&mut *conn);
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        };
+        let (result, ()) = tokio::time::timeout(Duration::from_secs(10), async { tokio::join!(statement, cancel) })
+            .await
+            .expect("statement was not interrupted");
+
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("interrupt"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn cancelled_endless_query_frees_pool_for_newer_one() {
+        let pool = pool(1).await;
+        let queries = Arc::new(InflightQueries::default());
+        let (started_tx, started_rx) = oneshot::channel();
+
+        let older = tokio::spawn({
+            let queries = queries.clone();
+            let pool = pool.clone();
+            async move {
+                queries
+                    .run(Some("search"), |scope| async move {
+                        let mut conn = scope.acquire(&pool).await?;
+                        started_tx.send(()).unwrap();
+                        sqlx::query_scalar::<_, i64>(ENDLESS_SQL)
+                            .fetch_one(&mut *conn)
+                            .await
+                            .map_err(|e| e.to_string())
+                    })
+                    .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        let newer = tokio::time::timeout(Duration::from_secs(10), select_value(&queries, &pool, "search", 42))
+            .await
+            .expect("newer query waited for the cancelled one");
+
+        assert_eq!(newer, Ok(42));
+        assert_eq!(older.await.unwrap(), Err(CANCELLED.to_string()));
+    }
+
+    #[tokio::test]
+    async fn connection_of_finished_query_is_not_interrupted_by_later_cancel() {
+        let pool = pool(1).await;
+        let scope = super::QueryScope { token: Default::default() };
+
+        let mut conn = scope.acquire(&pool).await.unwrap();
+        sqlx::query("SELECT 1").execute(&mut *conn).await.unwrap();
+        drop(conn);
+        // cancelled after its statements finished but before its result was checked
+        scope.token.cancel();
+
+        // the only connection of the pool, with the old progress handler still installed
+        let count: i64 = sqlx::query_scalar(LONG_SQL).fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 100_000);
+    }
+}
@@ -1,28 +1,40 @@
 use crate::app_state::AppState;
+use crate::khmer::collation::sort_key_hex;
+use crate::khmer::transliterate::{KhmerTransliteration, transliterate_all};
 use serde::Serialize;
-use std::collections::HashMap;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use tauri::ipc::Channel;
 use tauri::{State, command};
-use super::common::{WordRow, ShortDefinitionKm, KmShortDefinitionSource, parse_json_opt, validate_words_not_empty, get_placeholders, to_strict_map, to_optional_map, to_optional_map_wrap, fetch_many};
-
-const KM_SHORT_DESC_COALESCE: &str = "COALESCE(from_csv_rawHtml, en_km_com, Desc, from_chuon_nath_translated, wiktionary, from_russian_wiki, gorgoniev)";
-const KM_SHORT_DESC_SOURCE: &str = "(CASE
-    WHEN from_csv_rawHtml IS NOT NULL THEN 1
-    WHEN en_km_com IS NOT NULL THEN 2
-    WHEN Desc IS NOT NULL THEN 3
-    WHEN from_chuon_nath_translated IS NOT NULL THEN 4
-    WHEN wiktionary IS NOT NULL THEN 5
-    WHEN from_russian_wiki IS NOT NULL THEN 6
-    WHEN gorgoniev IS NOT NULL THEN 7
-    ELSE 0 END)";
-
-#[derive(Serialize, sqlx::FromRow)]
+use super::km_norm::{km_headword_by_norm, resolve_km_headwords, rekey_to_requested, stored_headwords};
+use super::content_search::{ContentSearchHit, ContentSearchTable, build_hit, compile_content_query, fetch_content_search_page};
+use super::search_query::CompiledQuery;
+use super::stream::{BatchSender, StreamEvent, StreamedDetail, for_each_row};
+use super::pagination::{Page, PageParams};
+use super::source_priority::{SourcePreferences, short_definition_sql};
+use super::common::{ShortDefinitionKm, KmShortDefinitionSource, parse_json_opt, validate_words_not_empty, get_placeholders, to_strict_map, to_optional_map, to_optional_map_wrap, fetch_many};
+
+pub const KM_IS_VERIFIED: &str = "(
+    Wiktionary IS NOT NULL
+    OR from_csv_variants IS NOT NULL
+    OR from_csv_nounForms IS NOT NULL
+    OR from_csv_pronunciations IS NOT NULL
+    OR from_csv_rawHtml IS NOT NULL
+    OR from_chuon_nath IS NOT NULL
+    OR from_chuon_nath_translated IS NOT NULL
+    OR from_russian_wiki IS NOT NULL
+    OR gorgoniev IS NOT NULL
+    OR en_km_com IS NOT NULL
+)";
+
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct KmWord {
     #[sqlx(rename = "Word")]
     pub word: String,
     pub is_verified: bool,
 }
 
-#[derive(Serialize, sqlx::FromRow, Debug)]
+#[derive(Serialize, sqlx::FromRow, Debug, Clone)]
 pub struct WordDetailKmRaw {
     #[sqlx(rename = "Word")]
     pub word: String,
@@ -52,7 +64,7 @@ pub struct WordDetailKmRaw {
     pub en_km_com: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 pub struct WordDetailKm {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub desc: Option<String>,
@@ -78,47 +90,66 @@ pub struct WordDetailKm {
     pub gorgoniev: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub en_km_com: Option<String>,
+    // generated from the spelling, only for words with neither Phonetic nor from_csv_pronunciations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_pronunciation: Option<KhmerTransliteration>,
+}
+
+impl WordDetailKm {
+    /// Drops the sources the user hid. The km original of Chuon Nath stays, only the machine translation is a source
+    pub fn without_excluded(mut self, prefs: Option<&SourcePreferences<KmShortDefinitionSource>>) -> Self {
+        let Some(prefs) = prefs else { return self };
+        for source in &prefs.excluded {
+            match source {
+                KmShortDefinitionSource::FromCsvRawHtml => self.from_csv_raw_html = None,
+                KmShortDefinitionSource::EnKmCom => self.en_km_com = None,
+                KmShortDefinitionSource::Desc => self.desc = None,
+                KmShortDefinitionSource::FromChuonNathTranslated => self.from_chuon_nath_translated = None,
+                KmShortDefinitionSource::Wiktionary => self.wiktionary = None,
+                KmShortDefinitionSource::FromRussianWiki => self.from_russian_wiki = None,
+                KmShortDefinitionSource::Gorgoniev => self.gorgoniev = None,
+            }
+        }
+        self
+    }
 }
 
 impl From<WordDetailKmRaw> for WordDetailKm {
     fn from(raw: WordDetailKmRaw) -> Self {
+        let from_csv_pronunciations: Option<Vec<String>> = parse_json_opt(raw.from_csv_pronunciations_raw);
+        let has_pronunciation = raw.phonetic.is_some() || from_csv_pronunciations.as_ref().is_some_and(|p| !p.is_empty());
+        let generated_pronunciation = (!has_pronunciation)
+            .then(|| transliterate_all(&raw.word))
+            .filter(|t| [&t.ungegn, &t.ala_lc, &t.ipa].iter().all(|s| !s.trim().is_empty()));
         Self {
             desc: raw.desc,
             phonetic: raw.phonetic,
             wiktionary: raw.wiktionary,
             from_csv_variants: parse_json_opt(raw.from_csv_variants_raw),
             from_csv_noun_forms: parse_json_opt(raw.from_csv_noun_forms_raw),
-            from_csv_pronunciations: parse_json_opt(raw.from_csv_pronunciations_raw),
+            from_csv_pronunciations,
             from_csv_raw_html: raw.from_csv_raw_html,
             from_chuon_nath: raw.from_chuon_nath,
             from_chuon_nath_translated: raw.from_chuon_nath_translated,
             from_russian_wiki: raw.from_russian_wiki,
             gorgoniev: raw.gorgoniev,
             en_km_com: raw.en_km_com,
+            generated_pronunciation,
         }
     }
 }
 
+// Joined to list headwords in dictionary order (`ORDER BY Word COLLATE khmer`) through the km_Dict_sort index
+const KM_DICT_SORTED_SQL: &str = "km_Dict_sort s JOIN km_Dict d ON d.Word = s.Word";
+
 #[command]
 pub async fn get_km_words(state: State<'_, AppState>) -> Result<Vec<KmWord>, String> {
     let pool = state.get_pool().await?;
 
-    let sql = "SELECT
-            Word,
-            (
-                Wiktionary IS NOT NULL
-                OR from_csv_variants IS NOT NULL
-                OR from_csv_nounForms IS NOT NULL
-                OR from_csv_pronunciations IS NOT NULL
-                OR from_csv_rawHtml IS NOT NULL
-                OR from_chuon_nath IS NOT NULL
-                OR from_chuon_nath_translated IS NOT NULL
-                OR from_russian_wiki IS NOT NULL
-                OR gorgoniev IS NOT NULL
-                OR en_km_com IS NOT NULL
-            ) AS is_verified
-         FROM km_Dict
-         ORDER BY Word ASC";
+    let sql = format!(
+        "SELECT d.Word, {} AS is_verified FROM {} ORDER BY s.sort_key ASC, s.Word ASC",
+        KM_IS_VERIFIED, KM_DICT_SORTED_SQL
+    );
 
     let rows = sqlx::query_as::<_, KmWord>(&sql)
         .fetch_all(&pool)
@@ -128,102 +159,289 @@ pub async fn get_km_words(state: State<'_, AppState>) -> Result<Vec<KmWord>, Str
     Ok(rows)
 }
 
+#[command]
+pub async fn stream_km_words(
+    state: State<'_, AppState>,
+    on_event: Channel<StreamEvent<KmWord>>,
+) -> Result<(), String> {
+    let pool = state.get_pool().await?;
+
+    let sql = format!(
+        "SELECT d.Word, {} AS is_verified FROM {} ORDER BY s.sort_key ASC, s.Word ASC",
+        KM_IS_VERIFIED, KM_DICT_SORTED_SQL
+    );
+
+    let mut sender = BatchSender::new(&on_event);
+    for_each_row::<KmWord>(&pool, &sql, &[], |row| sender.push(row)).await?;
+    sender.finish()
+}
+
+#[command]
+pub async fn get_km_words_page(
+    state: State<'_, AppState>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+) -> Result<Page<KmWord>, String> {
+    let params = PageParams::new(cursor, page_size)?;
+    let pool = state.get_pool().await?;
+
+    let sql = format!(
+        "SELECT d.Word, {} AS is_verified FROM {}
+        WHERE (s.sort_key, s.Word) > (?, ?) ORDER BY s.sort_key ASC, s.Word ASC LIMIT ?",
+        KM_IS_VERIFIED, KM_DICT_SORTED_SQL
+    );
+    let after_word = params.after_word();
+    let rows = sqlx::query_as::<_, KmWord>(&sql)
+        .bind(sort_key_hex(after_word))
+        .bind(after_word)
+        .bind(params.fetch_limit())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total = match params.total_estimate() {
+        Some(total) => total,
+        None => sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM km_Dict")
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+
+    Ok(params.keyset_page(rows, total, |r| r.word.clone(), Some))
+}
+
+async fn fetch_km_detail_row(pool: &SqlitePool, word: &str) -> Result<Option<WordDetailKmRaw>, String> {
+    sqlx::query_as::<_, WordDetailKmRaw>("SELECT * FROM km_Dict WHERE Word = ?")
+        .bind(word)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
 #[command]
 pub async fn get_word_detail_km(
     state: State<'_, AppState>,
     word: String,
+    sources: Option<SourcePreferences<KmShortDefinitionSource>>,
 ) -> Result<Option<WordDetailKm>, String> {
     let pool = state.get_pool().await?;
-    let sql = "SELECT * FROM km_Dict WHERE Word = ?";
-    let row = sqlx::query_as::<_, WordDetailKmRaw>(&sql)
-        .bind(word)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| e.to_string())?;
+    // exact headword first, otherwise one with the same normalized form, otherwise that of the repaired input
+    let mut row = fetch_km_detail_row(&pool, &word).await?;
+    if row.is_none()
+        && let Some(headword) = km_headword_by_norm(&pool, &word).await?
+    {
+        row = fetch_km_detail_row(&pool, &headword).await?;
+    }
 
-    Ok(row.map(WordDetailKm::from))
+    Ok(row.map(|r| WordDetailKm::from(r).without_excluded(sources.as_ref())))
+}
+
+/// HTML-free text of the definitions (see plain_text_index.rs), for TTS and copying. Same keys as WordDetailKm
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct WordPlainTextKm {
+    #[sqlx(rename = "Desc")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+    #[sqlx(rename = "Wiktionary")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wiktionary: Option<String>,
+    #[sqlx(rename = "from_csv_rawHtml")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_csv_raw_html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_chuon_nath: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_chuon_nath_translated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from_russian_wiki: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gorgoniev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub en_km_com: Option<String>,
+}
+
+async fn fetch_km_plain_text_row(pool: &SqlitePool, word: &str) -> Result<Option<WordPlainTextKm>, String> {
+    sqlx::query_as::<_, WordPlainTextKm>(
+        "SELECT x.* FROM km_Dict d JOIN km_Dict_text x ON x.dict_rowid = d.rowid WHERE d.Word = ?",
+    )
+    .bind(word)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn get_word_plain_text_km(state: State<'_, AppState>, word: String) -> Result<Option<WordPlainTextKm>, String> {
+    let pool = state.get_pool().await?;
+    // same headword resolution as get_word_detail_km
+    let mut row = fetch_km_plain_text_row(&pool, &word).await?;
+    if row.is_none()
+        && let Some(headword) = km_headword_by_norm(&pool, &word).await?
+    {
+        row = fetch_km_plain_text_row(&pool, &headword).await?;
+    }
+    Ok(row)
+}
+
+const KM_CONTENT_SEARCH: ContentSearchTable = ContentSearchTable {
+    table: "km_Dict",
+    columns: "d.Word, x.from_csv_rawHtml, x.en_km_com, x.Desc, x.from_chuon_nath, x.from_chuon_nath_translated, x.Wiktionary, x.from_russian_wiki, x.gorgoniev",
+};
+
+#[derive(sqlx::FromRow)]
+pub struct KmContentSearchRow {
+    #[sqlx(rename = "Word")]
+    pub word: String,
+    pub score: f64,
+    #[sqlx(rename = "from_csv_rawHtml")]
+    pub from_csv_raw_html: Option<String>,
+    pub en_km_com: Option<String>,
+    #[sqlx(rename = "Desc")]
+    pub desc: Option<String>,
+    pub from_chuon_nath: Option<String>,
+    pub from_chuon_nath_translated: Option<String>,
+    #[sqlx(rename = "Wiktionary")]
+    pub wiktionary: Option<String>,
+    pub from_russian_wiki: Option<String>,
+    pub gorgoniev: Option<String>,
+}
+
+impl KmContentSearchRow {
+    fn into_hit(self, query: &CompiledQuery<KmShortDefinitionSource>) -> Option<ContentSearchHit<KmShortDefinitionSource>> {
+        build_hit(
+            self.word,
+            self.score,
+            query,
+            vec![
+                (KmShortDefinitionSource::FromCsvRawHtml, self.from_csv_raw_html),
+                (KmShortDefinitionSource::EnKmCom, self.en_km_com),
+                (KmShortDefinitionSource::Desc, self.desc),
+                (KmShortDefinitionSource::FromChuonNathTranslated, self.from_chuon_nath_translated),
+                // the km original is indexed together with its translation
+                (KmShortDefinitionSource::FromChuonNathTranslated, self.from_chuon_nath),
+                (KmShortDefinitionSource::Wiktionary, self.wiktionary),
+                (KmShortDefinitionSource::FromRussianWiki, self.from_russian_wiki),
+                (KmShortDefinitionSource::Gorgoniev, self.gorgoniev),
+            ],
+        )
+    }
+}
+
+/// One page of `search_km_content`, also used by the global search
+pub async fn find_km_content(
+    conn: &mut SqliteConnection,
+    query: &str,
+    sources: Option<&[KmShortDefinitionSource]>,
+    params: &PageParams,
+) -> Result<Page<ContentSearchHit<KmShortDefinitionSource>>, String> {
+    let compiled = compile_content_query(&KM_CONTENT_SEARCH, query, sources)?;
+
+    let (rows, total) = fetch_content_search_page::<KmContentSearchRow, _>(conn, &KM_CONTENT_SEARCH, &compiled, params).await?;
+
+    Ok(params.offset_page(rows, total, |r| r.into_hit(&compiled)))
 }
 
 #[command]
 pub async fn search_km_content(
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<String>, String> {
+    sources: Option<Vec<KmShortDefinitionSource>>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+    request_key: Option<String>,
+) -> Result<Page<ContentSearchHit<KmShortDefinitionSource>>, String> {
+    let params = PageParams::new(cursor, page_size)?;
     let pool = state.get_pool().await?;
-    let pattern = format!("%{}%", query);
-    let sql = "SELECT Word FROM km_Dict
-         WHERE Desc LIKE ?
-            OR Wiktionary LIKE ?
-            OR from_chuon_nath LIKE ?
-            OR from_russian_wiki LIKE ?
-            OR gorgoniev LIKE ?
-            OR from_csv_rawHtml LIKE ?
-            OR en_km_com LIKE ?
-         ORDER BY LENGTH(Word) ASC LIMIT 50";
-    let rows = sqlx::query_as::<_, WordRow>(&sql)
-        .bind(&pattern)
-        .bind(&pattern)
-        .bind(&pattern)
-        .bind(&pattern)
-        .bind(&pattern)
-        .bind(&pattern)
-        .fetch_all(&pool)
+
+    state
+        .inflight
+        .run(request_key.as_deref(), |scope| async move {
+            let mut conn = scope.acquire(&pool).await?;
+            find_km_content(&mut conn, &query, sources.as_deref(), &params).await
+        })
         .await
-        .map_err(|e| e.to_string())?;
-    Ok(rows.into_iter().map(|r| r.word).collect())
 }
 
-#[derive(Serialize, sqlx::FromRow)]
+#[derive(Serialize, sqlx::FromRow, Clone)]
 pub struct WordKmWordsDetailShortRow {
     #[sqlx(rename = "Word")]
     pub word: String,
-    pub definition: String,
-    pub source: KmShortDefinitionSource,
+    // NULL if none of the allowed sources has a definition
+    pub definition: Option<String>,
+    pub source: Option<KmShortDefinitionSource>,
+}
+
+impl WordKmWordsDetailShortRow {
+    pub fn into_short(self) -> Option<ShortDefinitionKm> {
+        Some(ShortDefinitionKm { definition: self.definition?, source: self.source? })
+    }
+}
+
+/// Looks the words up by normalized form (see km_norm), rows are paired with the word as it was requested
+async fn fetch_many_normalized<T>(
+    pool: &SqlitePool,
+    words: &[String],
+    select: &str,
+    get_word: impl Fn(&T) -> String,
+) -> Result<Vec<(String, T)>, String>
+where
+    for<'r> T: sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin + Clone,
+{
+    let resolved = resolve_km_headwords(pool, words).await?;
+    let stored = stored_headwords(&resolved);
+    if stored.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let sql = format!("{} WHERE Word IN ({})", select, get_placeholders(stored.len()));
+    let rows: Vec<T> = fetch_many(pool, &stored, sql).await?;
+
+    Ok(rekey_to_requested(&resolved, rows, get_word))
 }
 
 #[command]
 pub async fn km_for_many_short_description_none_if_word_not_found(
     state: State<'_, AppState>,
     words: Vec<String>,
+    sources: Option<SourcePreferences<KmShortDefinitionSource>>,
 ) -> Result<HashMap<String, Option<ShortDefinitionKm>>, String> { // for analyzer page
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
-    let sql = format!(
-        "SELECT Word, {} as definition, {} as source FROM km_Dict WHERE Word IN ({})",
-        KM_SHORT_DESC_COALESCE,
-        KM_SHORT_DESC_SOURCE,
-        get_placeholders(words.len())
+    let short = short_definition_sql(sources.as_ref());
+    let select = format!(
+        "SELECT Word, {} as definition, {} as source FROM km_Dict",
+        short.definition,
+        short.source
     );
 
-    let rows: Vec<WordKmWordsDetailShortRow> = fetch_many(&pool, &words, sql).await?;
+    let rows = fetch_many_normalized::<WordKmWordsDetailShortRow>(&pool, &words, &select, |r| r.word.clone()).await?;
 
-    Ok(to_optional_map(words, rows, |r| r.word.clone(), |r| Some(ShortDefinitionKm { definition: r.definition, source: r.source })))
+    Ok(to_optional_map(words, rows, |(w, _)| w.clone(), |(_, r)| r.into_short()))
 }
 
 #[command]
 pub async fn km_for_many_short_description_throws_if_word_not_found(
     state: State<'_, AppState>,
     words: Vec<String>,
+    sources: Option<SourcePreferences<KmShortDefinitionSource>>,
 ) -> Result<HashMap<String, ShortDefinitionKm>, String> {
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
-    let sql = format!(
-        "SELECT Word, {} as definition, {} as source FROM km_Dict WHERE Word IN ({})",
-        KM_SHORT_DESC_COALESCE,
-        KM_SHORT_DESC_SOURCE,
-        get_placeholders(words.len())
+    let short = short_definition_sql(sources.as_ref());
+    let select = format!(
+        "SELECT Word, {} as definition, {} as source FROM km_Dict",
+        short.definition,
+        short.source
     );
 
-    let rows: Vec<WordKmWordsDetailShortRow> = fetch_many(&pool, &words, sql).await?;
+    let rows = fetch_many_normalized::<WordKmWordsDetailShortRow>(&pool, &words, &select, |r| r.word.clone()).await?;
 
-    to_strict_map(
-        words,
-        rows,
-        |r| r.word.clone(),
-        |r| ShortDefinitionKm { definition: r.definition, source: r.source }
-    )
+    // a word with no definition from the allowed sources counts as not found
+    let rows: Vec<(String, ShortDefinitionKm)> = rows.into_iter().filter_map(|(w, r)| r.into_short().map(|d| (w, d))).collect();
+
+    to_strict_map(words, rows, |(w, _)| w.clone(), |(_, d)| d)
 }
 
 #[command]
@@ -234,11 +452,9 @@ pub async fn km_for_many_full_details_none_if_word_not_found(
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
-    let sql = format!("SELECT * FROM km_Dict WHERE Word IN ({})", get_placeholders(words.len()));
+    let rows = fetch_many_normalized::<WordDetailKmRaw>(&pool, &words, "SELECT * FROM km_Dict", |r| r.word.clone()).await?;
 
-    let rows: Vec<WordDetailKmRaw> = fetch_many(&pool, &words, sql).await?;
-
-    Ok(to_optional_map_wrap(words, rows, |r| r.word.clone(), WordDetailKm::from))
+    Ok(to_optional_map_wrap(words, rows, |(w, _)| w.clone(), |(_, r)| WordDetailKm::from(r)))
 }
 
 #[command]
@@ -249,9 +465,47 @@ pub async fn km_for_many_full_details_throws_if_word_not_found(
     validate_words_not_empty(&words)?;
 
     let pool = state.get_pool().await?;
-    let sql = format!("SELECT * FROM km_Dict WHERE Word IN ({})", get_placeholders(words.len()));
+    let rows = fetch_many_normalized::<WordDetailKmRaw>(&pool, &words, "SELECT * FROM km_Dict", |r| r.word.clone()).await?;
+
+    to_strict_map(words, rows, |(w, _)| w.clone(), |(_, r)| WordDetailKm::from(r))
+}
 
-    let rows: Vec<WordDetailKmRaw> = fetch_many(&pool, &words, sql).await?;
+#[command]
+pub async fn stream_km_full_details(
+    state: State<'_, AppState>,
+    words: Vec<String>,
+    on_event: Channel<StreamEvent<StreamedDetail<WordDetailKm>>>,
+) -> Result<(), String> {
+    validate_words_not_empty(&words)?;
 
-    to_strict_map(words, rows, |r| r.word.clone(), WordDetailKm::from)
-}
\ No newline at end of file
+    let pool = state.get_pool().await?;
+    let resolved = resolve_km_headwords(&pool, &words).await?;
+    let stored = stored_headwords(&resolved);
+
+    // several requested spellings can resolve to the same stored headword
+    let mut requested_by_stored: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (requested, stored) in &resolved {
+        requested_by_stored.entry(stored.as_str()).or_default().push(requested.as_str());
+    }
+
+    let mut sender = BatchSender::new(&on_event);
+    let mut sent: HashSet<&str> = HashSet::new();
+
+    if !stored.is_empty() {
+        let sql = format!("SELECT * FROM km_Dict WHERE Word IN ({})", get_placeholders(stored.len()));
+        for_each_row::<WordDetailKmRaw>(&pool, &sql, &stored, |raw| {
+            let Some(requested) = requested_by_stored.get(raw.word.as_str()) else {
+                return Ok(());
+            };
+            let detail = WordDetailKm::from(raw);
+            for &word in requested {
+                sent.insert(word);
+                sender.push(StreamedDetail { word: word.to_string(), detail: Some(detail.clone()) })?;
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    sender.finish_details(&words, &sent)
+}
@@ -0,0 +1,167 @@
+use crate::app_state::AppState;
+use crate::khmer::pronunciation::split_pronunciations;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tauri::{State, command};
+use super::common::{KmShortDefinitionSource, ShortDefinitionKm, get_placeholders, parse_json_opt};
+use super::km::WordKmWordsDetailShortRow;
+use super::km_norm::resolve_km_headwords;
+use super::source_priority::{SourcePreferences, short_definition_sql};
+use super::fts::{index_version, set_index_version};
+
+// Bump when split_pronunciations changes
+const KM_PRON_VERSION: &str = "1";
+
+// km_Dict.Word -> each of its normalized pronunciations (from Phonetic and from_csv_pronunciations),
+// with the pronunciation as the source wrote it
+const CREATE_KM_PRON_TABLE_SQL: &str = "
+    DROP TABLE IF EXISTS km_Dict_pron;
+    CREATE TABLE km_Dict_pron (
+        Word TEXT NOT NULL,
+        pron TEXT NOT NULL,
+        written TEXT NOT NULL,
+        PRIMARY KEY (Word, pron)
+    ) WITHOUT ROWID;
+";
+
+const CREATE_KM_PRON_INDEX_SQL: &str = "CREATE INDEX km_Dict_pron_pron ON km_Dict_pron (pron)";
+
+#[derive(sqlx::FromRow)]
+struct KmPronunciationRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    #[sqlx(rename = "Phonetic")]
+    phonetic: Option<String>,
+    from_csv_pronunciations: Option<String>,
+}
+
+/// Fills km_Dict_pron with the normalized pronunciations of the headwords
+pub async fn ensure_km_pron_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "km_pron_version").await?.as_deref() == Some(KM_PRON_VERSION) {
+        println!("✅ Khmer pronunciation keys are up to date (v{}).", KM_PRON_VERSION);
+        return Ok(());
+    }
+
+    println!("🗣️ Building Khmer pronunciation keys...");
+
+    let rows = sqlx::query_as::<_, KmPronunciationRow>(
+        "SELECT Word, Phonetic, from_csv_pronunciations FROM km_Dict
+            WHERE Phonetic IS NOT NULL OR from_csv_pronunciations IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_KM_PRON_TABLE_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create km_Dict_pron: {}", e))?;
+
+    let mut filled = 0;
+    for row in rows {
+        let cells = row.phonetic.into_iter().chain(parse_json_opt(row.from_csv_pronunciations).unwrap_or_default());
+        for cell in cells {
+            for pron in split_pronunciations(&cell) {
+                let result = sqlx::query("INSERT OR IGNORE INTO km_Dict_pron (Word, pron, written) VALUES (?, ?, ?)")
+                    .bind(&row.word)
+                    .bind(&pron.normalized)
+                    .bind(pron.written)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to fill km_Dict_pron: {}", e))?;
+                filled += result.rows_affected();
+            }
+        }
+    }
+
+    sqlx::query(CREATE_KM_PRON_INDEX_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    set_index_version(&mut tx, "km_pron_version", KM_PRON_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Khmer pronunciation keys built, {} pronunciations (v{}).", filled, KM_PRON_VERSION);
+
+    Ok(())
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KmHomophone {
+    pub word: String,
+    // the shared pronunciation, as the other word's source writes it
+    pub pronunciation: String,
+    // None if none of the allowed sources has a definition
+    pub short: Option<ShortDefinitionKm>,
+}
+
+#[derive(sqlx::FromRow)]
+struct HomophoneRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    written: String,
+}
+
+/// Other headwords pronounced like `word` (sharing any normalized pronunciation), for the "sounds like" section.
+/// [] if the word is unknown or has no pronunciation
+#[command]
+pub async fn get_km_homophones(
+    state: State<'_, AppState>,
+    word: String,
+    sources: Option<SourcePreferences<KmShortDefinitionSource>>,
+) -> Result<Vec<KmHomophone>, String> {
+    let pool = state.get_pool().await?;
+
+    let Some(stored) = resolve_km_headwords(&pool, std::slice::from_ref(&word)).await?.remove(&word) else {
+        return Ok(vec![]);
+    };
+
+    let rows = sqlx::query_as::<_, HomophoneRow>(
+        "SELECT other.Word, MIN(other.written) AS written
+            FROM km_Dict_pron this JOIN km_Dict_pron other ON other.pron = this.pron
+            WHERE this.Word = ? AND other.Word != this.Word
+            GROUP BY other.Word
+            ORDER BY other.Word COLLATE khmer ASC",
+    )
+    .bind(&stored)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if rows.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let short = short_definition_sql(sources.as_ref());
+    let sql = format!(
+        "SELECT Word, {} as definition, {} as source FROM km_Dict WHERE Word IN ({})",
+        short.definition,
+        short.source,
+        get_placeholders(rows.len())
+    );
+    let mut query = sqlx::query_as::<_, WordKmWordsDetailShortRow>(&sql);
+    for row in &rows {
+        query = query.bind(&row.word);
+    }
+    let mut shorts: HashMap<String, Option<ShortDefinitionKm>> = query
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| (r.word.clone(), r.into_short()))
+        .collect();
+
+    Ok(rows
+        .into_iter()
+        .map(|row| KmHomophone {
+            short: shorts.remove(&row.word).flatten(),
+            word: row.word,
+            pronunciation: row.written,
+        })
+        .collect())
+}
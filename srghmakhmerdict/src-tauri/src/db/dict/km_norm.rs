@@ -0,0 +1,150 @@
+use crate::khmer::normalize::normalize_khmer;
+use crate::khmer::orthography::repair_orthography;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use super::common::{WordRow, get_placeholders};
+use super::fts::{index_version, set_index_version};
+
+// Bump when normalize_khmer changes
+const KM_NORM_VERSION: &str = "1";
+
+// km_Dict.Word -> normalize_khmer(Word). Computed in Rust, SQLite can't do it
+const CREATE_KM_NORM_TABLE_SQL: &str = "
+    DROP TABLE IF EXISTS km_Dict_norm;
+    CREATE TABLE km_Dict_norm (Word TEXT PRIMARY KEY, norm TEXT NOT NULL);
+";
+
+const CREATE_KM_NORM_INDEX_SQL: &str = "CREATE INDEX km_Dict_norm_norm ON km_Dict_norm (norm)";
+
+/// Fills km_Dict_norm, the normalized key of every headword
+pub async fn ensure_km_norm_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "km_norm_version").await?.as_deref() == Some(KM_NORM_VERSION) {
+        println!("✅ Khmer normalized keys are up to date (v{}).", KM_NORM_VERSION);
+        return Ok(());
+    }
+
+    println!("🔤 Building Khmer normalized keys...");
+
+    let words = sqlx::query_as::<_, WordRow>("SELECT Word FROM km_Dict")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_KM_NORM_TABLE_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create km_Dict_norm: {}", e))?;
+
+    for row in &words {
+        sqlx::query("INSERT OR IGNORE INTO km_Dict_norm (Word, norm) VALUES (?, ?)")
+            .bind(&row.word)
+            .bind(normalize_khmer(&row.word))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to fill km_Dict_norm: {}", e))?;
+    }
+
+    sqlx::query(CREATE_KM_NORM_INDEX_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    set_index_version(&mut tx, "km_norm_version", KM_NORM_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Khmer normalized keys built for {} words (v{}).", words.len(), KM_NORM_VERSION);
+
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct KmNormRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    norm: String,
+}
+
+/// Maps every requested word to the stored headword: the word itself if it is stored as is,
+/// otherwise a headword with the same normalized form. Words with neither are left out
+pub async fn resolve_km_headwords(pool: &SqlitePool, words: &[String]) -> Result<HashMap<String, String>, String> {
+    let normalized: Vec<String> = words.iter().map(|w| normalize_khmer(w)).collect();
+
+    let sql = format!(
+        "SELECT Word, norm FROM km_Dict_norm WHERE Word IN ({0}) OR norm IN ({0}) ORDER BY Word ASC",
+        get_placeholders(words.len())
+    );
+    let mut query = sqlx::query_as::<_, KmNormRow>(&sql);
+    for word in words {
+        query = query.bind(word);
+    }
+    for norm in &normalized {
+        query = query.bind(norm);
+    }
+    let rows = query.fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    let stored: HashSet<&str> = rows.iter().map(|r| r.word.as_str()).collect();
+    // rows are sorted, so of several headwords with the same key the first one wins
+    let mut by_norm: HashMap<&str, &str> = HashMap::with_capacity(rows.len());
+    for row in &rows {
+        by_norm.entry(row.norm.as_str()).or_insert(row.word.as_str());
+    }
+
+    let mut resolved = HashMap::with_capacity(words.len());
+    for (word, norm) in words.iter().zip(&normalized) {
+        let found = if stored.contains(word.as_str()) {
+            Some(word.as_str())
+        } else {
+            by_norm.get(norm.as_str()).copied()
+        };
+        if let Some(found) = found {
+            resolved.insert(word.clone(), found.to_string());
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Headword for a word not stored as typed: the first (by Word) with the same normalized form, otherwise
+/// with the normalized form of its repaired spelling. One indexed lookup per form
+pub async fn km_headword_by_norm(pool: &SqlitePool, word: &str) -> Result<Option<String>, String> {
+    let normalized = normalize_khmer(word);
+    let repaired = normalize_khmer(&repair_orthography(word));
+    let forms = if repaired == normalized { vec![normalized] } else { vec![normalized, repaired] };
+
+    for norm in forms {
+        let found =
+            sqlx::query_scalar::<_, String>("SELECT Word FROM km_Dict_norm WHERE norm = ? ORDER BY Word ASC LIMIT 1")
+                .bind(&norm)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        if found.is_some() {
+            return Ok(found);
+        }
+    }
+    Ok(None)
+}
+
+/// Distinct stored headwords to fetch for a `resolve_km_headwords` result
+pub fn stored_headwords(resolved: &HashMap<String, String>) -> Vec<String> {
+    let mut stored: Vec<String> = resolved.values().cloned().collect();
+    stored.sort();
+    stored.dedup();
+    stored
+}
+
+/// Pairs each row (fetched by stored headword) with every requested word that resolved to it
+pub fn rekey_to_requested<T: Clone>(
+    resolved: &HashMap<String, String>,
+    rows: Vec<T>,
+    get_word: impl Fn(&T) -> String,
+) -> Vec<(String, T)> {
+    let by_stored: HashMap<String, T> = rows.into_iter().map(|r| (get_word(&r), r)).collect();
+    resolved
+        .iter()
+        .filter_map(|(requested, stored)| by_stored.get(stored).map(|row| (requested.clone(), row.clone())))
+        .collect()
+}
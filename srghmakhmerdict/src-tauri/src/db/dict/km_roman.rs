@@ -0,0 +1,244 @@
+use crate::app_state::AppState;
+use crate::edit_distance::osa_distance;
+use crate::khmer::chars::contains_khmer;
+use crate::khmer::pronunciation::split_pronunciations;
+use crate::khmer::romanize::{fold_romanization, romanization_key, romanize};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use tauri::{State, command};
+use super::common::parse_json_opt;
+use super::fts::{index_version, set_index_version};
+
+// Bump when romanize / fold_romanization / split_pronunciations change
+const KM_ROMAN_VERSION: &str = "3";
+
+const DEFAULT_ROMAN_SEARCH_LIMIT: usize = 20;
+
+// Matches found only because the skeleton starts with the query's (the user is still typing) rank after full ones
+const PREFIX_MATCH_PENALTY: f64 = 1.0;
+
+// km_Dict.Word -> every Latin spelling we know for it: folded (fold_romanization) and its consonant key.
+// A word has a row per distinct folded spelling, from the best origin that gave it
+const CREATE_KM_ROMAN_TABLE_SQL: &str = "
+    DROP TABLE IF EXISTS km_Dict_roman;
+    CREATE TABLE km_Dict_roman (
+        Word TEXT NOT NULL,
+        folded TEXT NOT NULL,
+        key TEXT NOT NULL,
+        romanization TEXT NOT NULL,
+        origin INTEGER NOT NULL,
+        PRIMARY KEY (Word, folded)
+    ) WITHOUT ROWID;
+";
+
+const CREATE_KM_ROMAN_INDEX_SQL: &str = "CREATE INDEX km_Dict_roman_key ON km_Dict_roman (key)";
+
+/// Where a spelling comes from, in order of trust
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, sqlx::Type)]
+#[repr(i64)]
+pub enum RomanizationOrigin {
+    // km_Dict.Phonetic
+    Phonetic = 1,
+    // km_Dict.from_csv_pronunciations
+    CsvPronunciation = 2,
+    // khmer::romanize of the headword
+    Transliterated = 3,
+}
+
+#[derive(sqlx::FromRow)]
+struct KmPronunciationRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    #[sqlx(rename = "Phonetic")]
+    phonetic: Option<String>,
+    from_csv_pronunciations: Option<String>,
+}
+
+/// Every spelling of a word with its origin, best origin first.
+/// A pronunciation cell can list alternatives ("kaa, kaː"), each is a spelling of its own
+fn spellings(row: KmPronunciationRow) -> Vec<(String, RomanizationOrigin)> {
+    let cells = row
+        .phonetic
+        .into_iter()
+        .map(|cell| (cell, RomanizationOrigin::Phonetic))
+        .chain(
+            parse_json_opt(row.from_csv_pronunciations)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|cell| (cell, RomanizationOrigin::CsvPronunciation)),
+        );
+
+    let mut spellings = Vec::new();
+    for (cell, origin) in cells {
+        for pronunciation in split_pronunciations(&cell) {
+            spellings.push((pronunciation.written.to_string(), origin));
+        }
+    }
+    spellings.push((romanize(&row.word), RomanizationOrigin::Transliterated));
+    spellings
+}
+
+/// Fills km_Dict_roman with the Latin spellings of the headwords
+pub async fn ensure_km_roman_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "km_roman_version").await?.as_deref() == Some(KM_ROMAN_VERSION) {
+        println!("✅ Khmer romanization index is up to date (v{}).", KM_ROMAN_VERSION);
+        return Ok(());
+    }
+
+    println!("🔡 Building Khmer romanization index...");
+
+    let rows = sqlx::query_as::<_, KmPronunciationRow>("SELECT Word, Phonetic, from_csv_pronunciations FROM km_Dict")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_KM_ROMAN_TABLE_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create km_Dict_roman: {}", e))?;
+
+    let mut filled = 0;
+    for row in rows {
+        let word = row.word.clone();
+        for (romanization, origin) in spellings(row) {
+            let folded = fold_romanization(&romanization);
+            if folded.is_empty() {
+                continue;
+            }
+            // spellings come best origin first, so OR IGNORE keeps the most trusted one
+            let result = sqlx::query(
+                "INSERT OR IGNORE INTO km_Dict_roman (Word, folded, key, romanization, origin) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&word)
+            .bind(&folded)
+            .bind(romanization_key(&folded))
+            .bind(romanization.trim())
+            .bind(origin)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to fill km_Dict_roman: {}", e))?;
+            filled += result.rows_affected();
+        }
+    }
+
+    sqlx::query(CREATE_KM_ROMAN_INDEX_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    set_index_version(&mut tx, "km_roman_version", KM_ROMAN_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Khmer romanization index built, {} spellings (v{}).", filled, KM_ROMAN_VERSION);
+
+    Ok(())
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RomanizedMatch {
+    pub word: String,
+    // the spelling of the word that matched the query best, as written in its origin
+    pub romanization: String,
+    pub origin: RomanizationOrigin,
+    // lower is better, 0 if the query is that spelling up to variants
+    pub distance: f64,
+}
+
+#[derive(sqlx::FromRow)]
+struct KmRomanRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    folded: String,
+    key: String,
+    romanization: String,
+    origin: RomanizationOrigin,
+}
+
+fn max_distance(chars: usize) -> f64 {
+    match chars {
+        0..=3 => 1.0,
+        4..=7 => 2.0,
+        _ => 3.0,
+    }
+}
+
+/// Edit distance between the query and the row's spelling, and whether the row only matched as a longer word
+fn score(query_folded: &[char], query_key: &str, row: &KmRomanRow) -> (f64, bool) {
+    let folded: Vec<char> = row.folded.chars().collect();
+    if row.key == query_key {
+        (osa_distance(query_folded, &folded), false)
+    } else {
+        // the query is the start of the word, compare with as much of the spelling
+        let prefix = &folded[..folded.len().min(query_folded.len())];
+        (osa_distance(query_folded, prefix), true)
+    }
+}
+
+/// Khmer headwords pronounced like `query`, a Latin spelling (UNGEGN, the dictionaries' phonetics,
+/// or chat spelling like "chhmuos"). Best matches first: closest spelling, then most trusted origin, then shortest word
+#[command]
+pub async fn search_km_by_romanization(
+    state: State<'_, AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<RomanizedMatch>, String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Query should not be empty".to_string());
+    }
+    if contains_khmer(query) {
+        return Err("Query should be written in Latin letters".to_string());
+    }
+
+    let query_folded = fold_romanization(query);
+    if query_folded.is_empty() {
+        return Ok(vec![]);
+    }
+    let query_key = romanization_key(&query_folded);
+    let query_chars: Vec<char> = query_folded.chars().collect();
+    let max = max_distance(query_chars.len());
+
+    let pool = state.get_pool().await?;
+    // keys starting with the query's: the exact key, and longer words while the user is still typing
+    let rows = sqlx::query_as::<_, KmRomanRow>(
+        "SELECT Word, folded, key, romanization, origin FROM km_Dict_roman WHERE key BETWEEN ? AND ?",
+    )
+    .bind(&query_key)
+    .bind(format!("{}{}", query_key, char::MAX))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // one match per word, its best spelling
+    let mut best: HashMap<String, RomanizedMatch> = HashMap::new();
+    for row in rows {
+        let (distance, is_prefix) = score(&query_chars, &query_key, &row);
+        if distance > max {
+            continue;
+        }
+        let distance = if is_prefix { distance + PREFIX_MATCH_PENALTY } else { distance };
+        let candidate = RomanizedMatch { word: row.word, romanization: row.romanization, origin: row.origin, distance };
+        match best.get(&candidate.word) {
+            Some(current) if (current.distance, current.origin) <= (candidate.distance, candidate.origin) => {}
+            _ => {
+                best.insert(candidate.word.clone(), candidate);
+            }
+        }
+    }
+
+    let mut matches: Vec<RomanizedMatch> = best.into_values().collect();
+    matches.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then_with(|| a.origin.cmp(&b.origin))
+            .then_with(|| a.word.chars().count().cmp(&b.word.chars().count()))
+            .then_with(|| a.word.cmp(&b.word))
+    });
+    matches.truncate(limit.unwrap_or(DEFAULT_ROMAN_SEARCH_LIMIT));
+
+    Ok(matches)
+}
@@ -0,0 +1,459 @@
+use crate::app_state::AppState;
+use crate::khmer::normalize::normalize_khmer;
+use crate::khmer::syllables::{SpanKind, TextSpan, segment_syllables};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tauri::{State, command};
+use super::autocomplete::PrefixIndex;
+use super::common::{KmShortDefinitionSource, ShortDefinitionKm, get_placeholders};
+use super::km::WordKmWordsDetailShortRow;
+use super::source_priority::{SourcePreferences, short_definition_sql};
+
+// Longer pasted text should be split into sentences by the caller
+const MAX_SEGMENT_TEXT_CHARS: usize = 20_000;
+
+// Cost of a path is the sum of its edges, the cheapest one wins: as few words as possible (maximal matching),
+// preferring verified headwords. There is no frequency data for Khmer yet, `word_cost` is where it would go
+const VERIFIED_WORD_COST: f64 = 1.0;
+const UNVERIFIED_WORD_COST: f64 = 1.25;
+// a syllable no headword covers; more than two words, so splitting into known words always wins
+pub(super) const UNKNOWN_SYLLABLE_COST: f64 = 2.5;
+
+// Paths kept per syllable boundary, the alternatives come from them
+const PATHS_PER_BOUNDARY: usize = 8;
+// An alternative is offered if it costs at most this much more than the chosen segmentation: one more word
+const ALTERNATIVE_COST_SLACK: f64 = 1.5;
+const MAX_ALTERNATIVES_PER_SPAN: usize = 4;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KmSegmentKind {
+    // a km_Dict headword
+    Word,
+    // Khmer syllables no headword covers
+    Unknown,
+    Space,
+    Digits,
+    Punctuation,
+    StrayMarks,
+    Other,
+}
+
+impl From<SpanKind> for KmSegmentKind {
+    fn from(kind: SpanKind) -> Self {
+        match kind {
+            // not reached, syllables are segmented into words
+            SpanKind::Syllable => KmSegmentKind::Unknown,
+            SpanKind::Space => KmSegmentKind::Space,
+            SpanKind::Digits => KmSegmentKind::Digits,
+            SpanKind::Punctuation => KmSegmentKind::Punctuation,
+            SpanKind::StrayMarks => KmSegmentKind::StrayMarks,
+            SpanKind::Other => KmSegmentKind::Other,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KmSegment {
+    pub text: String,
+    // offsets into the input, end exclusive, as in segment_khmer_syllables
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub kind: KmSegmentKind,
+    // the stored headword (the text can differ from it in normalization), Some for words only
+    pub headword: Option<String>,
+    // None if not a word or none of the allowed sources has a definition
+    pub short: Option<ShortDefinitionKm>,
+}
+
+/// A span the dictionary splits in more than one plausible way. The chosen split is in `segments`
+#[derive(Serialize, Clone, Debug)]
+pub struct KmAmbiguity {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    // other splits of the span into headwords, best first
+    pub alternatives: Vec<Vec<KmSegment>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KmSegmentation {
+    pub segments: Vec<KmSegment>,
+    pub ambiguities: Vec<KmAmbiguity>,
+}
+
+/// Syllables `start..end` of a run, as one headword or one unknown syllable
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Edge {
+    pub(super) start: usize,
+    pub(super) end: usize,
+    pub(super) headword: Option<String>,
+    pub(super) cost: f64,
+}
+
+fn word_cost(is_verified: bool) -> f64 {
+    if is_verified { VERIFIED_WORD_COST } else { UNVERIFIED_WORD_COST }
+}
+
+/// Every way to start a word at each syllable of a run
+fn edges_from(index: &PrefixIndex<KmShortDefinitionSource>, keys: &[String]) -> Vec<Vec<Edge>> {
+    (0..keys.len())
+        .map(|start| {
+            let mut edges: Vec<Edge> = index
+                .keys_along(&keys[start..])
+                .into_iter()
+                .map(|m| Edge {
+                    start,
+                    end: start + m.len,
+                    headword: Some(m.word.to_string()),
+                    cost: word_cost(m.is_verified),
+                })
+                .collect();
+            edges.push(Edge { start, end: start + 1, headword: None, cost: UNKNOWN_SYLLABLE_COST });
+            edges
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy)]
+struct PathEnd {
+    cost: f64,
+    // the edge ending here (start boundary, index in edges_from) and which of the paths at its start it extends
+    edge: Option<(usize, usize)>,
+    prev_rank: usize,
+}
+
+/// Up to PATHS_PER_BOUNDARY cheapest segmentations of a run of `n` syllables (k-best Viterbi), cheapest first
+pub(super) fn best_paths(n: usize, edges: &[Vec<Edge>]) -> Vec<(f64, Vec<Edge>)> {
+    let mut ends: Vec<Vec<PathEnd>> = vec![Vec::new(); n + 1];
+    ends[0].push(PathEnd { cost: 0.0, edge: None, prev_rank: 0 });
+
+    for start in 0..n {
+        // everything ending at `start` is in, keep the best of them
+        ends[start].sort_by(|a, b| a.cost.total_cmp(&b.cost));
+        ends[start].truncate(PATHS_PER_BOUNDARY);
+
+        for (edge_index, edge) in edges[start].iter().enumerate() {
+            for rank in 0..ends[start].len() {
+                let cost = ends[start][rank].cost + edge.cost;
+                ends[edge.end].push(PathEnd { cost, edge: Some((start, edge_index)), prev_rank: rank });
+            }
+        }
+    }
+    // the sort is stable and paths are pushed by where their last edge starts, so longer last words win ties
+    ends[n].sort_by(|a, b| a.cost.total_cmp(&b.cost));
+    ends[n].truncate(PATHS_PER_BOUNDARY);
+
+    (0..ends[n].len())
+        .map(|rank| {
+            let mut path = Vec::new();
+            let cost = ends[n][rank].cost;
+            let (mut boundary, mut rank) = (n, rank);
+            while let Some((start, edge_index)) = ends[boundary][rank].edge {
+                path.push(edges[start][edge_index].clone());
+                rank = ends[boundary][rank].prev_rank;
+                boundary = start;
+            }
+            path.reverse();
+            (cost, path)
+        })
+        .collect()
+}
+
+/// Runs of edges where `alt` splits differently from `best`: (start, end) syllable boundaries they both have,
+/// with alt's edges in between
+fn differing_spans<'a>(best: &[Edge], alt: &'a [Edge]) -> Vec<((usize, usize), &'a [Edge])> {
+    let mut spans = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < best.len() && j < alt.len() {
+        if best[i] == alt[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // both are at the same boundary here, walk until they meet at a boundary again
+        let start = best[i].start;
+        let alt_from = j;
+        let (mut best_end, mut alt_end) = (best[i].end, alt[j].end);
+        while best_end != alt_end {
+            if best_end < alt_end {
+                i += 1;
+                best_end = best[i].end;
+            } else {
+                j += 1;
+                alt_end = alt[j].end;
+            }
+        }
+        i += 1;
+        j += 1;
+        spans.push(((start, best_end), &alt[alt_from..j]));
+    }
+    spans
+}
+
+/// What the segmentation of one syllable run becomes: the chosen edges, and alternatives per span
+struct RunSegmentation {
+    chosen: Vec<Edge>,
+    alternatives: BTreeMap<(usize, usize), Vec<Vec<Edge>>>,
+}
+
+fn segment_run(index: &PrefixIndex<KmShortDefinitionSource>, syllables: &[&TextSpan]) -> RunSegmentation {
+    let keys: Vec<String> = syllables.iter().map(|s| normalize_khmer(&s.text)).collect();
+    let edges = edges_from(index, &keys);
+    let mut paths = best_paths(keys.len(), &edges).into_iter();
+    let (best_cost, chosen) = paths.next().unwrap_or_default();
+
+    let mut alternatives: BTreeMap<(usize, usize), Vec<Vec<Edge>>> = BTreeMap::new();
+    for (cost, path) in paths {
+        if cost > best_cost + ALTERNATIVE_COST_SLACK {
+            break;
+        }
+        for (span, alt) in differing_spans(&chosen, &path) {
+            // splitting a span into unknown syllables is never a reading worth showing
+            if alt.iter().any(|e| e.headword.is_none()) {
+                continue;
+            }
+            let known = alternatives.entry(span).or_default();
+            if known.len() < MAX_ALTERNATIVES_PER_SPAN && !known.iter().any(|k| k.as_slice() == alt) {
+                known.push(alt.to_vec());
+            }
+        }
+    }
+
+    RunSegmentation { chosen, alternatives }
+}
+
+/// Segments for edges of a run, consecutive unknown syllables joined into one segment
+fn edges_to_segments(text: &str, syllables: &[&TextSpan], edges: &[Edge]) -> Vec<KmSegment> {
+    let mut segments: Vec<KmSegment> = Vec::new();
+    for edge in edges {
+        let (first, last) = (syllables[edge.start], syllables[edge.end - 1]);
+        let kind = if edge.headword.is_some() { KmSegmentKind::Word } else { KmSegmentKind::Unknown };
+        if let Some(prev) = segments.last_mut()
+            && kind == KmSegmentKind::Unknown
+            && prev.kind == KmSegmentKind::Unknown
+        {
+            prev.byte_end = last.byte_end;
+            prev.char_end = last.char_end;
+            prev.text = text[prev.byte_start..prev.byte_end].to_string();
+            continue;
+        }
+        segments.push(KmSegment {
+            text: text[first.byte_start..last.byte_end].to_string(),
+            byte_start: first.byte_start,
+            byte_end: last.byte_end,
+            char_start: first.char_start,
+            char_end: last.char_end,
+            kind,
+            headword: edge.headword.clone(),
+            short: None,
+        });
+    }
+    segments
+}
+
+fn segment_text(index: &PrefixIndex<KmShortDefinitionSource>, text: &str) -> KmSegmentation {
+    let spans = segment_syllables(text);
+    let mut segments = Vec::new();
+    let mut ambiguities = Vec::new();
+
+    for run in spans.chunk_by(|a, b| a.kind == SpanKind::Syllable && b.kind == SpanKind::Syllable) {
+        if run[0].kind != SpanKind::Syllable {
+            let span = &run[0];
+            segments.push(KmSegment {
+                text: span.text.clone(),
+                byte_start: span.byte_start,
+                byte_end: span.byte_end,
+                char_start: span.char_start,
+                char_end: span.char_end,
+                kind: span.kind.into(),
+                headword: None,
+                short: None,
+            });
+            continue;
+        }
+
+        let syllables: Vec<&TextSpan> = run.iter().collect();
+        let result = segment_run(index, &syllables);
+        segments.extend(edges_to_segments(text, &syllables, &result.chosen));
+
+        for ((start, end), alternatives) in result.alternatives {
+            let (first, last) = (syllables[start], syllables[end - 1]);
+            ambiguities.push(KmAmbiguity {
+                byte_start: first.byte_start,
+                byte_end: last.byte_end,
+                char_start: first.char_start,
+                char_end: last.char_end,
+                alternatives: alternatives.iter().map(|alt| edges_to_segments(text, &syllables, alt)).collect(),
+            });
+        }
+    }
+
+    KmSegmentation { segments, ambiguities }
+}
+
+/// Splits unspaced Khmer text into km_Dict headwords, with the short definition of each.
+/// Spans the dictionary splits in several plausible ways are listed in `ambiguities` with the other splits
+#[command]
+pub async fn segment_km_text(
+    state: State<'_, AppState>,
+    text: String,
+    sources: Option<SourcePreferences<KmShortDefinitionSource>>,
+) -> Result<KmSegmentation, String> {
+    if text.chars().count() > MAX_SEGMENT_TEXT_CHARS {
+        return Err(format!("Text should be at most {} chars", MAX_SEGMENT_TEXT_CHARS));
+    }
+
+    let pool = state.get_pool().await?;
+    let index = Arc::clone(state.autocomplete.km_index(&pool).await?);
+    // the k-best search is CPU bound, keep it off the async workers
+    let mut segmentation = tokio::task::spawn_blocking(move || segment_text(&index, &text))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let all_segments = || {
+        segmentation
+            .segments
+            .iter()
+            .chain(segmentation.ambiguities.iter().flat_map(|a| a.alternatives.iter().flatten()))
+    };
+    let mut headwords: Vec<String> = all_segments().filter_map(|s| s.headword.clone()).collect();
+    headwords.sort();
+    headwords.dedup();
+    if headwords.is_empty() {
+        return Ok(segmentation);
+    }
+
+    let short = short_definition_sql(sources.as_ref());
+    let sql = format!(
+        "SELECT Word, {} as definition, {} as source FROM km_Dict WHERE Word IN ({})",
+        short.definition,
+        short.source,
+        get_placeholders(headwords.len())
+    );
+    let mut query = sqlx::query_as::<_, WordKmWordsDetailShortRow>(&sql);
+    for word in &headwords {
+        query = query.bind(word);
+    }
+    let shorts: HashMap<String, ShortDefinitionKm> = query
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter_map(|r| {
+            let word = r.word.clone();
+            r.into_short().map(|short| (word, short))
+        })
+        .collect();
+
+    let fill = |segment: &mut KmSegment| {
+        segment.short = segment.headword.as_ref().and_then(|w| shorts.get(w).cloned());
+    };
+    segmentation.segments.iter_mut().for_each(fill);
+    segmentation
+        .ambiguities
+        .iter_mut()
+        .flat_map(|a| a.alternatives.iter_mut().flatten())
+        .for_each(fill);
+
+    Ok(segmentation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(start: usize, end: usize, headword: &str) -> Edge {
+        Edge { start, end, headword: Some(headword.to_string()), cost: VERIFIED_WORD_COST }
+    }
+
+    fn unknown(start: usize) -> Edge {
+        Edge { start, end: start + 1, headword: None, cost: UNKNOWN_SYLLABLE_COST }
+    }
+
+    // edges_from for syllables `n` long with `words` known, every syllable can also be unknown
+    fn edges(n: usize, words: &[Edge]) -> Vec<Vec<Edge>> {
+        (0..n)
+            .map(|start| {
+                let mut from: Vec<Edge> = words.iter().filter(|w| w.start == start).cloned().collect();
+                from.push(unknown(start));
+                from
+            })
+            .collect()
+    }
+
+    fn headwords(path: &[Edge]) -> Vec<Option<&str>> {
+        path.iter().map(|e| e.headword.as_deref()).collect()
+    }
+
+    #[test]
+    fn fewest_words_win() {
+        let words = [word(0, 1, "a"), word(1, 2, "b"), word(2, 3, "c"), word(0, 3, "abc")];
+        let paths = best_paths(3, &edges(3, &words));
+        assert_eq!(headwords(&paths[0].1), vec![Some("abc")]);
+        assert_eq!(paths[0].0, 1.0);
+        assert_eq!(headwords(&paths[1].1), vec![Some("a"), Some("b"), Some("c")]);
+        assert!(paths.windows(2).all(|w| w[0].0 <= w[1].0));
+    }
+
+    #[test]
+    fn known_words_beat_unknown_syllables() {
+        // two known words cost less than one unknown syllable
+        let words = [word(0, 1, "a"), word(1, 2, "b")];
+        let paths = best_paths(3, &edges(3, &words));
+        assert_eq!(headwords(&paths[0].1), vec![Some("a"), Some("b"), None]);
+        assert_eq!(paths[0].0, 2.0 + UNKNOWN_SYLLABLE_COST);
+
+        assert_eq!(headwords(&best_paths(2, &edges(2, &[]))[0].1), vec![None, None]);
+        assert!(best_paths(0, &[]).iter().all(|(cost, path)| *cost == 0.0 && path.is_empty()));
+    }
+
+    #[test]
+    fn ties_go_to_the_longer_last_word() {
+        // ab|c and a|bc cost the same
+        let words = [word(0, 1, "a"), word(0, 2, "ab"), word(1, 3, "bc"), word(2, 3, "c")];
+        let paths = best_paths(3, &edges(3, &words));
+        assert_eq!(headwords(&paths[0].1), vec![Some("a"), Some("bc")]);
+        assert_eq!(headwords(&paths[1].1), vec![Some("ab"), Some("c")]);
+        assert_eq!(paths[0].0, paths[1].0);
+    }
+
+    #[test]
+    fn differing_spans_between_shared_boundaries() {
+        let best = [word(0, 2, "ab"), word(2, 3, "c"), word(3, 4, "d"), word(4, 6, "ef")];
+        let alt = [word(0, 1, "a"), word(1, 3, "bc"), word(3, 4, "d"), word(4, 5, "e"), word(5, 6, "f")];
+        let spans = differing_spans(&best, &alt);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0], ((0, 3), &alt[0..2]));
+        assert_eq!(spans[1], ((4, 6), &alt[3..5]));
+
+        assert!(differing_spans(&best, &best).is_empty());
+    }
+
+    #[test]
+    fn ambiguous_span_from_best_paths() {
+        let words = [word(0, 1, "x"), word(1, 2, "a"), word(1, 3, "ab"), word(2, 4, "bc"), word(3, 4, "c")];
+        let paths = best_paths(4, &edges(4, &words));
+        let (best, alt) = (&paths[0].1, &paths[1].1);
+        assert_eq!(headwords(best), vec![Some("x"), Some("a"), Some("bc")]);
+        assert_eq!(differing_spans(best, alt), vec![((1, 4), &alt[1..])]);
+        assert_eq!(headwords(&alt[1..]), vec![Some("ab"), Some("c")]);
+    }
+
+    #[test]
+    fn unknown_syllables_are_one_segment() {
+        let text = "ឆ្មាឆ្កែក";
+        let spans = segment_syllables(text);
+        let syllables: Vec<&TextSpan> = spans.iter().collect();
+        assert_eq!(syllables.len(), 3);
+
+        let segments = edges_to_segments(text, &syllables, &[unknown(0), unknown(1), word(2, 3, "ក")]);
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[0].text.as_str(), segments[0].kind), ("ឆ្មាឆ្កែ", KmSegmentKind::Unknown));
+        assert_eq!((segments[0].char_start, segments[0].char_end), (0, 8));
+        assert_eq!((segments[1].text.as_str(), segments[1].kind), ("ក", KmSegmentKind::Word));
+    }
+}
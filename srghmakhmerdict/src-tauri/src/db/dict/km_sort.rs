@@ -0,0 +1,57 @@
+use crate::khmer::collation::sort_key_hex;
+use sqlx::SqlitePool;
+use super::common::WordRow;
+use super::fts::{index_version, set_index_version};
+
+// Bump when collation::sort_key changes
+const KM_SORT_VERSION: &str = "1";
+
+// km_Dict.Word -> collation::sort_key_hex(Word). Ordering by (sort_key, Word) is `ORDER BY Word COLLATE khmer`
+// (the collation breaks ties by code point too), but walks the primary key instead of sorting every row
+const CREATE_KM_SORT_TABLE_SQL: &str = "
+    DROP TABLE IF EXISTS km_Dict_sort;
+    CREATE TABLE km_Dict_sort (
+        sort_key TEXT NOT NULL,
+        Word TEXT NOT NULL,
+        PRIMARY KEY (sort_key, Word)
+    ) WITHOUT ROWID;
+";
+
+/// Fills km_Dict_sort with the dictionary order sort key of every headword
+pub async fn ensure_km_sort_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "km_sort_version").await?.as_deref() == Some(KM_SORT_VERSION) {
+        println!("✅ Khmer sort keys are up to date (v{}).", KM_SORT_VERSION);
+        return Ok(());
+    }
+
+    println!("🔤 Building Khmer sort keys...");
+
+    let words = sqlx::query_as::<_, WordRow>("SELECT Word FROM km_Dict")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_KM_SORT_TABLE_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create km_Dict_sort: {}", e))?;
+
+    for row in &words {
+        sqlx::query("INSERT OR IGNORE INTO km_Dict_sort (sort_key, Word) VALUES (?, ?)")
+            .bind(sort_key_hex(&row.word))
+            .bind(&row.word)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to fill km_Dict_sort: {}", e))?;
+    }
+
+    set_index_version(&mut tx, "km_sort_version", KM_SORT_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Khmer sort keys built for {} words (v{}).", words.len(), KM_SORT_VERSION);
+
+    Ok(())
+}
@@ -0,0 +1,388 @@
+use crate::app_state::AppState;
+use crate::edit_distance::osa_distance;
+use crate::khmer::collation;
+use crate::khmer::normalize::normalize_khmer;
+use crate::khmer::syllables::{SpanKind, TextSpan, segment_syllables};
+use crate::khmer::units::split_edit_units;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::path::BaseDirectory;
+use tauri::{AppHandle, Manager, State, command};
+use tauri_plugin_fs::FsExt;
+use super::common::WordRow;
+use super::km_segment::{Edge, UNKNOWN_SYLLABLE_COST, best_paths};
+use super::suggest::max_distance;
+
+// Spellcheck of Khmer text against the word lists of khmer-spellchecker and the km_Dict headwords.
+// The text is split into words as in km_segment.rs, syllables no word covers are the misspellings.
+// There is no frequency data for Khmer, so the number of lists a word is in stands for it:
+// a word every list has is common, one a single scraped list has is rare or a typo itself
+
+// Bundled from khmer-spellchecker/ (bundle.resources in tauri.conf.json). Its dictionary.txt is the union of these
+const WORD_LISTS: &[&str] = &[
+    "spellchecker/dictionary1.txt",
+    "spellchecker/dictionary2.txt",
+    "spellchecker/dictionary3.txt",
+    "spellchecker/dictionary4.txt",
+    "spellchecker/dictionary5.txt",
+    "spellchecker/dictionary6.txt",
+    "spellchecker/dictionary7.txt",
+    "spellchecker/dictionary8.txt",
+    "spellchecker/dictionary-my.txt",
+];
+
+// Longer pasted text should be split into sentences by the caller
+const MAX_SPELLCHECK_TEXT_CHARS: usize = 20_000;
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 5;
+
+// Longest word tried when splitting, in syllables. The lists also have whole phrases, text splits fine without them
+const MAX_WORD_SYLLABLES: usize = 12;
+
+// Syllables on each side of a misspelling that its corrections may also replace
+const CONTEXT_SYLLABLES: usize = 2;
+
+// A word only one list has costs a bit more, so a split into well attested words wins over it
+const ATTESTED_WORD_COST: f64 = 1.0;
+const RARE_WORD_COST: f64 = 1.25;
+
+struct LexiconWord {
+    // as the first list that has it writes it, km_Dict goes first
+    word: String,
+    // how many lists have it, km_Dict counts as one
+    list_count: u8,
+    // the last list counted, so duplicates inside one list count once
+    last_list: usize,
+}
+
+// Id of an edit unit no known word has, in the text being checked
+const UNKNOWN_UNIT: u32 = u32::MAX;
+
+/// Every known spelling, by normalized form, and the forms by length in edit units for suggestions.
+/// The forms are kept split into edit units, numbered, so a suggestion search only compares numbers
+pub struct KmSpellLexicon {
+    words: HashMap<String, LexiconWord>,
+    unit_ids: HashMap<String, u32>,
+    by_units: Vec<Vec<(String, Vec<u32>)>>,
+}
+
+impl KmSpellLexicon {
+    fn build<'a>(headwords: impl IntoIterator<Item = String>, lists: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut words: HashMap<String, LexiconWord> = HashMap::new();
+        let mut add = |word: &str, list: usize| {
+            let key = normalize_khmer(word);
+            if key.is_empty() {
+                return;
+            }
+            let known = words.entry(key).or_insert_with(|| LexiconWord {
+                word: word.to_string(),
+                list_count: 0,
+                last_list: usize::MAX,
+            });
+            if known.last_list != list {
+                known.last_list = list;
+                known.list_count = known.list_count.saturating_add(1);
+            }
+        };
+
+        for headword in headwords {
+            add(headword.trim(), 0);
+        }
+        for (i, list) in lists.into_iter().enumerate() {
+            for line in list.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                add(line, i + 1);
+            }
+        }
+
+        let mut unit_ids: HashMap<String, u32> = HashMap::new();
+        let mut by_units: Vec<Vec<(String, Vec<u32>)>> = Vec::new();
+        for key in words.keys() {
+            let units: Vec<u32> = split_edit_units(key)
+                .into_iter()
+                .map(|unit| match unit_ids.get(unit) {
+                    Some(&id) => id,
+                    None => {
+                        let id = unit_ids.len() as u32;
+                        unit_ids.insert(unit.to_string(), id);
+                        id
+                    }
+                })
+                .collect();
+            if by_units.len() <= units.len() {
+                by_units.resize_with(units.len() + 1, Vec::new);
+            }
+            by_units[units.len()].push((key.clone(), units));
+        }
+
+        Self { words, unit_ids, by_units }
+    }
+
+    fn get(&self, key: &str) -> Option<&LexiconWord> {
+        self.words.get(key)
+    }
+
+    /// Known words within `max_distance` edits of `key`, with their distance
+    fn closest(&self, key: &str) -> Vec<(&LexiconWord, f64)> {
+        // units no known word has never match, whatever id they get
+        let target: Vec<u32> = split_edit_units(key)
+            .into_iter()
+            .map(|unit| self.unit_ids.get(unit).copied().unwrap_or(UNKNOWN_UNIT))
+            .collect();
+        let max = max_distance(target.len());
+        let slack = max as usize;
+
+        (target.len().saturating_sub(slack)..=target.len() + slack)
+            .filter_map(|len| self.by_units.get(len))
+            .flatten()
+            .filter(|(candidate, _)| candidate.as_str() != key)
+            .filter_map(|(candidate, units)| {
+                let d = osa_distance(&target, units);
+                (d <= max).then(|| (&self.words[candidate], d))
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct KmSpellingSuggestion {
+    pub word: String,
+    pub distance: f64,
+    // how many word lists have it (km_Dict counts as one), stands in for frequency
+    pub list_count: u8,
+    // what it replaces: the misspelled span, or that span with up to two syllables on each side
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Syllables no known word covers, offsets into the input as in segment_khmer_syllables
+#[derive(Serialize, Clone, Debug)]
+pub struct KmSpellingIssue {
+    pub text: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    // best first: fewest edits for their length, then in more lists
+    pub suggestions: Vec<KmSpellingSuggestion>,
+}
+
+fn word_cost(list_count: u8) -> f64 {
+    if list_count > 1 { ATTESTED_WORD_COST } else { RARE_WORD_COST }
+}
+
+/// Cheapest split of a run of syllables (their normalized forms) into known words and unknown syllables
+fn split_run(lexicon: &KmSpellLexicon, keys: &[String]) -> Vec<Edge> {
+    let edges: Vec<Vec<Edge>> = (0..keys.len())
+        .map(|start| {
+            let mut edges = vec![Edge { start, end: start + 1, headword: None, cost: UNKNOWN_SYLLABLE_COST }];
+            let mut key = String::new();
+            for end in start + 1..=keys.len().min(start + MAX_WORD_SYLLABLES) {
+                key.push_str(&keys[end - 1]);
+                if let Some(known) = lexicon.get(&key) {
+                    edges.push(Edge { start, end, headword: Some(known.word.clone()), cost: word_cost(known.list_count) });
+                }
+            }
+            edges
+        })
+        .collect();
+
+    best_paths(keys.len(), &edges).into_iter().next().map(|(_, path)| path).unwrap_or_default()
+}
+
+/// Corrections for the unknown syllables `start..end`, of them alone and together with up to CONTEXT_SYLLABLES
+/// syllables around them: a typo often leaves part of the word looking like a shorter word (ស្រ|ឡា|ញ).
+/// Ranked by edits per edit unit of what they replace, so one edit in a long word beats one in a syllable
+fn suggestions_for(
+    lexicon: &KmSpellLexicon,
+    keys: &[String],
+    syllables: &[&TextSpan],
+    (start, end): (usize, usize),
+    limit: usize,
+) -> Vec<KmSpellingSuggestion> {
+    // by word: the suggestion and its edits per unit
+    let mut found: HashMap<&str, (KmSpellingSuggestion, f64)> = HashMap::new();
+    for from in start.saturating_sub(CONTEXT_SYLLABLES)..=start {
+        for to in end..=(end + CONTEXT_SYLLABLES).min(keys.len()) {
+            let key: String = keys[from..to].concat();
+            let units = split_edit_units(&key).len().max(1) as f64;
+            for (known, distance) in lexicon.closest(&key) {
+                let relative = distance / units;
+                if found.get(known.word.as_str()).is_some_and(|(_, r)| *r <= relative) {
+                    continue;
+                }
+                let suggestion = KmSpellingSuggestion {
+                    word: known.word.clone(),
+                    distance,
+                    list_count: known.list_count,
+                    byte_start: syllables[from].byte_start,
+                    byte_end: syllables[to - 1].byte_end,
+                    char_start: syllables[from].char_start,
+                    char_end: syllables[to - 1].char_end,
+                };
+                found.insert(&known.word, (suggestion, relative));
+            }
+        }
+    }
+
+    let mut ranked: Vec<(KmSpellingSuggestion, f64)> = found.into_values().collect();
+    ranked.sort_by(|(a, a_relative), (b, b_relative)| {
+        a_relative
+            .total_cmp(b_relative)
+            .then_with(|| a.distance.total_cmp(&b.distance))
+            .then_with(|| b.list_count.cmp(&a.list_count))
+            .then_with(|| collation::compare(&a.word, &b.word))
+    });
+    ranked.into_iter().take(limit).map(|(suggestion, _)| suggestion).collect()
+}
+
+fn check_text(lexicon: &KmSpellLexicon, text: &str, limit: usize) -> Vec<KmSpellingIssue> {
+    let spans = segment_syllables(text);
+    let mut issues = Vec::new();
+
+    for run in spans.chunk_by(|a, b| a.kind == SpanKind::Syllable && b.kind == SpanKind::Syllable) {
+        if run[0].kind != SpanKind::Syllable {
+            continue;
+        }
+        let syllables: Vec<&TextSpan> = run.iter().collect();
+        let keys: Vec<String> = syllables.iter().map(|s| normalize_khmer(&s.text)).collect();
+        let mut edges = split_run(lexicon, &keys);
+
+        // consecutive unknown syllables are one misspelling
+        edges.dedup_by(|next, prev| {
+            let joined = prev.headword.is_none() && next.headword.is_none();
+            if joined {
+                prev.end = next.end;
+            }
+            joined
+        });
+
+        for edge in edges.iter().filter(|e| e.headword.is_none()) {
+            let (first, last) = (syllables[edge.start], syllables[edge.end - 1]);
+            issues.push(KmSpellingIssue {
+                text: text[first.byte_start..last.byte_end].to_string(),
+                byte_start: first.byte_start,
+                byte_end: last.byte_end,
+                char_start: first.char_start,
+                char_end: last.char_end,
+                suggestions: suggestions_for(lexicon, &keys, &syllables, (edge.start, edge.end), limit),
+            });
+        }
+    }
+
+    issues
+}
+
+fn read_word_lists(app: &AppHandle) -> Result<Vec<String>, String> {
+    let mut lists = Vec::with_capacity(WORD_LISTS.len());
+    for name in WORD_LISTS {
+        let path = app
+            .path()
+            .resolve(name, BaseDirectory::Resource)
+            .map_err(|e| format!("Failed to resolve resource: {}", e))?;
+        let bytes = app.fs().read(&path).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        lists.push(String::from_utf8(bytes).map_err(|e| e.to_string())?);
+    }
+    Ok(lists)
+}
+
+async fn load_lexicon(app: &AppHandle, pool: &SqlitePool) -> Result<KmSpellLexicon, String> {
+    let headwords = sqlx::query_as::<_, WordRow>("SELECT Word FROM km_Dict")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // reading the lists and segmenting every word of them is file IO and CPU work, keep both off the async workers
+    let app = app.clone();
+    tokio::task::spawn_blocking(move || {
+        let lists = read_word_lists(&app)?;
+        Ok(KmSpellLexicon::build(headwords.into_iter().map(|r| r.word), lists.iter().map(String::as_str)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Spans of Khmer text that aren't known words, with corrections. The word lists are loaded on first use
+#[command]
+pub async fn spellcheck_km_text(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    text: String,
+    limit: Option<usize>,
+) -> Result<Vec<KmSpellingIssue>, String> {
+    if text.chars().count() > MAX_SPELLCHECK_TEXT_CHARS {
+        return Err(format!("Text should be at most {} chars", MAX_SPELLCHECK_TEXT_CHARS));
+    }
+
+    let pool = state.get_pool().await?;
+    let lexicon = state
+        .km_spell_lexicon
+        .get_or_try_init(|| async { load_lexicon(&app, &pool).await.map(Arc::new) })
+        .await?;
+    let lexicon = Arc::clone(lexicon);
+
+    // suggestions compare the misspelling with every known word of about its length, keep it off the async workers
+    tokio::task::spawn_blocking(move || check_text(&lexicon, &text, limit.unwrap_or(DEFAULT_SUGGESTIONS_LIMIT)))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon() -> KmSpellLexicon {
+        let headwords = ["ស្រឡាញ់", "ខ្ញុំ", "អ្នក", "ការងារ"].map(String::from);
+        KmSpellLexicon::build(headwords, ["ខ្ញុំ\nអ្នក\nស្រឡាញ់\nស្រលាញ់", "ខ្ញុំ\nស្រឡាញ់\nក\nការ"])
+    }
+
+    #[test]
+    fn known_text_has_no_issues() {
+        assert!(check_text(&lexicon(), "ខ្ញុំស្រឡាញ់អ្នក ok", 5).is_empty());
+        assert!(check_text(&lexicon(), "", 5).is_empty());
+    }
+
+    #[test]
+    fn misspelling_is_flagged_with_corrections() {
+        // ស្រឡាញ without the bantoc: ស្រ is no word, ឡាញ neither
+        let text = "ខ្ញុំស្រឡាញអ្នក";
+        let issues = check_text(&lexicon(), text, 5);
+        assert_eq!(issues.len(), 1);
+        let issue = &issues[0];
+        assert_eq!(issue.text, "ស្រឡាញ");
+        assert_eq!(&text[issue.byte_start..issue.byte_end], "ស្រឡាញ");
+        let best = &issue.suggestions[0];
+        // in three lists, ស្រលាញ់ (two edits) only in one
+        assert_eq!((best.word.as_str(), best.distance, best.list_count), ("ស្រឡាញ់", 1.0, 3));
+        assert_eq!(&text[best.byte_start..best.byte_end], "ស្រឡាញ");
+    }
+
+    #[test]
+    fn empty_lexicon_has_no_suggestions() {
+        let lexicon = KmSpellLexicon::build([], []);
+        assert!(lexicon.closest("ក").is_empty());
+        assert!(lexicon.closest("").is_empty());
+        let issues = check_text(&lexicon, "ខ្ញុំ", 5);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].suggestions.is_empty());
+    }
+
+    #[test]
+    fn closest_by_edit_units() {
+        let lexicon = lexicon();
+        let mut found: Vec<(&str, f64)> =
+            lexicon.closest("ស្រឡាញ").into_iter().map(|(w, d)| (w.word.as_str(), d)).collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(found, [("ស្រឡាញ់", 1.0), ("ស្រលាញ់", 2.0)]);
+        // a unit no known word has still counts as one substitution
+        assert!(lexicon.closest("ខ្ញ៊ំ").iter().any(|(w, d)| w.word == "ខ្ញុំ" && *d == 1.0));
+    }
+
+    #[test]
+    fn list_duplicates_count_once() {
+        let lexicon = KmSpellLexicon::build([], ["ខ្ញុំ\nខ្ញុំ", "ខ្ញុំ"]);
+        assert_eq!(lexicon.get("ខ្ញុំ").map(|w| w.list_count), Some(2));
+    }
+}
@@ -1,7 +1,28 @@
+pub mod autocomplete;
 pub mod common;
+pub mod content_search;
 pub mod en;
+pub mod fts;
+pub mod global_search;
+pub mod headword_pattern;
+pub mod inflight;
 pub mod km;
+pub mod km_homophones;
+pub mod km_norm;
+pub mod km_roman;
+pub mod km_segment;
+pub mod km_sort;
+pub mod km_spellcheck;
+pub mod ocr_search;
+pub mod pagination;
+pub mod plain_text;
+pub mod plain_text_index;
 pub mod ru;
+pub mod ru_stem;
+pub mod search_query;
+pub mod source_priority;
+pub mod stream;
+pub mod suggest;
 
 
 use crate::app_state::AppState;
@@ -0,0 +1,274 @@
+use crate::app_state::AppState;
+use serde::Serialize;
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use tauri::{State, command};
+use super::common::get_placeholders;
+use super::content_search::{SearchSnippet, head_snippet, make_snippet};
+use super::fts::{index_version, set_index_version, to_fts_phrase, to_like_pattern};
+use super::pagination::{Page, PageParams};
+
+// Bump when the layout of the tables below changes
+const OCR_INDEX_VERSION: &str = "1";
+
+// What en_Dict.en_km_com HTML links images by, e.g. src="/en_Dict_en_km_com_assets_images/1295.webp"
+const IMAGE_PATH_PREFIX: &str = "en_Dict_en_km_com_assets_images/";
+
+// en_Dict rows read per query while filling en_km_com_image_refs, so the HTML is never all in memory at once
+const FILL_BATCH_ROWS: i64 = 1000;
+
+// en_km_com_ocr_fts: contentless trigram index of en_km_com_ocr.text, rowid is the image id.
+// en_km_com_image_refs: which en_Dict entries show which image, filled from the en_km_com HTML
+const CREATE_OCR_TABLES_SQL: &str = "
+    DROP TABLE IF EXISTS en_km_com_ocr_fts;
+    DROP TABLE IF EXISTS en_km_com_image_refs;
+
+    CREATE VIRTUAL TABLE en_km_com_ocr_fts USING fts5(text, content='', tokenize='trigram');
+    INSERT INTO en_km_com_ocr_fts (rowid, text) SELECT id, text FROM en_km_com_ocr;
+
+    CREATE TABLE en_km_com_image_refs (
+        image_id INTEGER NOT NULL,
+        Word TEXT NOT NULL,
+        PRIMARY KEY (image_id, Word)
+    ) WITHOUT ROWID;
+    CREATE INDEX en_km_com_image_refs_word ON en_km_com_image_refs (Word);
+";
+
+/// Ids of the en_km_com images an entry's HTML shows, in order of appearance
+fn referenced_image_ids(html: &str) -> Vec<i64> {
+    let mut ids = Vec::new();
+    for (start, _) in html.match_indices(IMAGE_PATH_PREFIX) {
+        let rest = &html[start + IMAGE_PATH_PREFIX.len()..];
+        let digits_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+        if digits_len == 0 || !rest[digits_len..].starts_with('.') {
+            continue;
+        }
+        if let Ok(id) = rest[..digits_len].parse::<i64>()
+            && !ids.contains(&id)
+        {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+#[derive(sqlx::FromRow)]
+struct EnKmComHtmlRow {
+    rowid: i64,
+    #[sqlx(rename = "Word")]
+    word: String,
+    en_km_com: String,
+}
+
+/// Indexes the OCR text of the en_km_com images and records which entries show them
+pub async fn ensure_ocr_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "ocr_index_version").await?.as_deref() == Some(OCR_INDEX_VERSION) {
+        println!("✅ OCR index is up to date (v{}).", OCR_INDEX_VERSION);
+        return Ok(());
+    }
+
+    println!("🖼️ Building OCR index...");
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_OCR_TABLES_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create OCR tables: {}", e))?;
+
+    let like = to_like_pattern(IMAGE_PATH_PREFIX);
+    let mut last_rowid = i64::MIN;
+    let mut refs = 0;
+    loop {
+        let rows = sqlx::query_as::<_, EnKmComHtmlRow>(
+            "SELECT rowid, Word, en_km_com FROM en_Dict WHERE rowid > ? AND en_km_com LIKE ? ESCAPE '\\' ORDER BY rowid LIMIT ?",
+        )
+        .bind(last_rowid)
+        .bind(&like)
+        .bind(FILL_BATCH_ROWS)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        last_rowid = last.rowid;
+
+        for row in &rows {
+            for image_id in referenced_image_ids(&row.en_km_com) {
+                // an entry listed twice in en_Dict still refers to an image once
+                refs += sqlx::query("INSERT OR IGNORE INTO en_km_com_image_refs (image_id, Word) VALUES (?, ?)")
+                    .bind(image_id)
+                    .bind(&row.word)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to fill en_km_com_image_refs: {}", e))?
+                    .rows_affected();
+            }
+        }
+    }
+
+    set_index_version(&mut tx, "ocr_index_version", OCR_INDEX_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ OCR index built, {} image references (v{}).", refs, OCR_INDEX_VERSION);
+
+    Ok(())
+}
+
+async fn fetch_image_words(pool: &SqlitePool, ids: &[i64]) -> Result<HashMap<i64, Vec<String>>, String> {
+    let sql = format!(
+        "SELECT image_id, Word FROM en_km_com_image_refs WHERE image_id IN ({}) ORDER BY Word ASC",
+        get_placeholders(ids.len())
+    );
+    let mut query = sqlx::query(&sql);
+    for id in ids {
+        query = query.bind(id);
+    }
+    let rows = query.fetch_all(pool).await.map_err(|e| e.to_string())?;
+
+    let mut words: HashMap<i64, Vec<String>> = HashMap::new();
+    for row in rows {
+        words.entry(row.get("image_id")).or_default().push(row.get("Word"));
+    }
+    Ok(words)
+}
+
+/// en_Dict headwords whose en_km_com definition shows each image. Images no entry shows map to []
+#[command]
+pub async fn get_en_km_com_image_words(
+    state: State<'_, AppState>,
+    ids: Vec<i64>,
+) -> Result<HashMap<i64, Vec<String>>, String> {
+    if ids.is_empty() {
+        return Err("List of ids should not be empty".to_string());
+    }
+
+    let pool = state.get_pool().await?;
+    let mut words = fetch_image_words(&pool, &ids).await?;
+    for id in ids {
+        words.entry(id).or_default();
+    }
+    Ok(words)
+}
+
+#[derive(Serialize, Debug)]
+pub struct OcrSearchHit {
+    pub image_id: i64,
+    // Higher is better (negated FTS5 bm25), 0 if the query was too short for the index
+    pub score: f64,
+    pub snippet: SearchSnippet,
+    // en_Dict headwords showing the image
+    pub words: Vec<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct OcrSearchRow {
+    id: i64,
+    text: String,
+    score: f64,
+}
+
+/// Images whose OCR text contains `query` (as a phrase), best matches first
+#[command]
+pub async fn search_en_km_com_ocr(
+    state: State<'_, AppState>,
+    query: String,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+) -> Result<Page<OcrSearchHit>, String> {
+    let params = PageParams::new(cursor, page_size)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Query should not be empty".to_string());
+    }
+
+    let pool = state.get_pool().await?;
+
+    // terms too short for the trigram index fall back to LIKE, unranked
+    let (rows_sql, count_sql, param) = match to_fts_phrase(query) {
+        Some(phrase) => (
+            "SELECT o.id, o.text, -f.rank AS score
+                FROM en_km_com_ocr_fts f JOIN en_km_com_ocr o ON o.id = f.rowid
+                WHERE en_km_com_ocr_fts MATCH ?
+                ORDER BY f.rank, o.id LIMIT ? OFFSET ?",
+            "SELECT COUNT(*) FROM en_km_com_ocr_fts WHERE en_km_com_ocr_fts MATCH ?",
+            phrase,
+        ),
+        None => (
+            "SELECT id, text, 0.0 AS score FROM en_km_com_ocr WHERE text LIKE ? ESCAPE '\\' ORDER BY id LIMIT ? OFFSET ?",
+            "SELECT COUNT(*) FROM en_km_com_ocr WHERE text LIKE ? ESCAPE '\\'",
+            to_like_pattern(query),
+        ),
+    };
+
+    let rows = sqlx::query_as::<_, OcrSearchRow>(rows_sql)
+        .bind(&param)
+        .bind(params.fetch_limit())
+        .bind(params.offset())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total = match params.total_estimate() {
+        Some(total) => total,
+        None => sqlx::query_scalar::<_, i64>(count_sql)
+            .bind(&param)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+
+    let ids: Vec<i64> = rows.iter().map(|r| r.id).collect();
+    let mut words = if ids.is_empty() { HashMap::new() } else { fetch_image_words(&pool, &ids).await? };
+
+    Ok(params.offset_page(rows, total, |r| {
+        Some(OcrSearchHit {
+            image_id: r.id,
+            score: r.score,
+            snippet: make_snippet(&r.text, &[query]).unwrap_or_else(|| head_snippet(&r.text)),
+            words: words.remove(&r.id).unwrap_or_default(),
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn img(name: &str) -> String {
+        format!(r#"<img src="/{}{}">"#, IMAGE_PATH_PREFIX, name)
+    }
+
+    #[test]
+    fn quoted_and_absolute_paths() {
+        let html = format!(
+            r#"{}<img src='{prefix}7.png'><a href="https://example.com/static/{prefix}42.jpg">42</a>"#,
+            img("1295.webp"),
+            prefix = IMAGE_PATH_PREFIX
+        );
+        assert_eq!(referenced_image_ids(&html), vec![1295, 7, 42]);
+    }
+
+    #[test]
+    fn duplicates_keep_the_first_position() {
+        let html = [img("3.webp"), img("1.webp"), img("3.webp")].concat();
+        assert_eq!(referenced_image_ids(&html), vec![3, 1]);
+    }
+
+    #[test]
+    fn non_numeric_paths_are_skipped() {
+        let html = [
+            img("logo.webp"),
+            img("12a.webp"),
+            img(".webp"),
+            img("99999999999999999999.webp"),
+            format!("{}5 ", IMAGE_PATH_PREFIX),
+            img("8.webp"),
+        ]
+        .concat();
+        assert_eq!(referenced_image_ids(&html), vec![8]);
+        assert!(referenced_image_ids("no images here").is_empty());
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 1000;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "t", content = "v")]
+enum CursorPosition {
+    // keyset pagination over headwords
+    AfterWord(String),
+    // ranked results have no stable key, so they are paged by offset
+    Offset(i64),
+}
+
+// What the frontend gets is hex(json(Cursor)), it should treat it as opaque
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Cursor {
+    position: CursorPosition,
+    // counted once on the first page and carried along
+    total_estimate: i64,
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    let json = serde_json::to_string(cursor).expect("cursor is always serializable");
+    json.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_cursor(raw: &str) -> Result<Cursor, String> {
+    let invalid = || format!("Invalid cursor: {}", raw);
+    if !raw.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    let bytes = (0..raw.len())
+        .step_by(2)
+        .map(|i| raw.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
+
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // None on the last page
+    pub next_cursor: Option<String>,
+    pub total_estimate: i64,
+}
+
+pub struct PageParams {
+    pub page_size: i64,
+    cursor: Option<Cursor>,
+    // false when the caller drops the total, so nothing is counted
+    counted: bool,
+}
+
+impl PageParams {
+    pub fn new(cursor: Option<String>, page_size: Option<u32>) -> Result<Self, String> {
+        let page_size = page_size.map_or(DEFAULT_PAGE_SIZE, i64::from);
+        if page_size == 0 {
+            return Err("Page size should not be 0".to_string());
+        }
+        let cursor = cursor.as_deref().map(decode_cursor).transpose()?;
+        Ok(Self {
+            page_size: page_size.min(MAX_PAGE_SIZE),
+            cursor,
+            counted: true,
+        })
+    }
+
+    /// First page whose total_estimate is left at 0, for callers that only use the items
+    pub fn uncounted(page_size: Option<u32>) -> Result<Self, String> {
+        Ok(Self {
+            counted: false,
+            ..Self::new(None, page_size)?
+        })
+    }
+
+    /// Rows to fetch: one more than the page, to know whether there is a next one
+    pub fn fetch_limit(&self) -> i64 {
+        self.page_size + 1
+    }
+
+    /// Keyset bound, "" on the first page (every headword sorts after it)
+    pub fn after_word(&self) -> &str {
+        match self.cursor.as_ref().map(|c| &c.position) {
+            Some(CursorPosition::AfterWord(word)) => word,
+            _ => "",
+        }
+    }
+
+    pub fn offset(&self) -> i64 {
+        match self.cursor.as_ref().map(|c| &c.position) {
+            Some(CursorPosition::Offset(offset)) => *offset,
+            _ => 0,
+        }
+    }
+
+    /// Count carried over from the first page (0 if uncounted), None means the caller should count now
+    pub fn total_estimate(&self) -> Option<i64> {
+        if !self.counted {
+            return Some(0);
+        }
+        self.cursor.as_ref().map(|c| c.total_estimate)
+    }
+
+    fn build_page<R, T>(
+        &self,
+        mut rows: Vec<R>,
+        total_estimate: i64,
+        next_position: impl Fn(&R) -> CursorPosition,
+        transform: impl FnMut(R) -> Option<T>,
+    ) -> Page<T> {
+        let has_more = rows.len() as i64 > self.page_size;
+        rows.truncate(self.page_size as usize);
+
+        let next_cursor = if has_more {
+            rows.last().map(|last| {
+                encode_cursor(&Cursor {
+                    position: next_position(last),
+                    total_estimate,
+                })
+            })
+        } else {
+            None
+        };
+
+        Page {
+            items: rows.into_iter().filter_map(transform).collect(),
+            next_cursor,
+            total_estimate,
+        }
+    }
+
+    /// For rows fetched with `Word > after_word() ORDER BY Word LIMIT fetch_limit()`
+    pub fn keyset_page<R, T>(
+        &self,
+        rows: Vec<R>,
+        total_estimate: i64,
+        get_word: impl Fn(&R) -> String,
+        transform: impl FnMut(R) -> Option<T>,
+    ) -> Page<T> {
+        self.build_page(rows, total_estimate, |r| CursorPosition::AfterWord(get_word(r)), transform)
+    }
+
+    /// For rows fetched with `LIMIT fetch_limit() OFFSET offset()`
+    pub fn offset_page<R, T>(
+        &self,
+        rows: Vec<R>,
+        total_estimate: i64,
+        transform: impl FnMut(R) -> Option<T>,
+    ) -> Page<T> {
+        let next_offset = self.offset() + self.page_size;
+        self.build_page(rows, total_estimate, |_| CursorPosition::Offset(next_offset), transform)
+    }
+}
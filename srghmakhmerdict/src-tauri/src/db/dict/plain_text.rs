@@ -0,0 +1,106 @@
+// Same rules as stripHtml on the frontend: block tags and <br> become a space, inline tags vanish,
+// whitespace is collapsed and trimmed
+
+const BLOCK_TAGS: &[&str] = &[
+    "br", "p", "div", "li", "ul", "ol", "tr", "td", "th", "table", "tbody", "thead", "h1", "h2", "h3", "h4", "h5",
+    "h6", "hr", "blockquote", "dd", "dt", "dl", "section", "article",
+];
+
+// Content of these is never visible text
+const SKIPPED_TAGS: &[&str] = &["script", "style"];
+
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        "ndash" => Some('–'),
+        "mdash" => Some('—'),
+        "hellip" => Some('…'),
+        "laquo" => Some('«'),
+        "raquo" => Some('»'),
+        "zwsp" => Some('\u{200B}'),
+        _ => {
+            let num = entity.strip_prefix('#')?;
+            let code = match num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => num.parse::<u32>().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+pub fn html_to_plain_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skipping: Option<String> = None;
+
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '<' => {
+                let Some(end) = rest.find('>') else {
+                    // unterminated tag, treat the rest as text
+                    if skipping.is_none() {
+                        out.push_str(rest);
+                    }
+                    break;
+                };
+                let tag = &rest[1..end];
+                let name = tag_name(tag);
+                let is_closing = tag.starts_with('/');
+
+                match &skipping {
+                    Some(skipped) => {
+                        if is_closing && *skipped == name {
+                            skipping = None;
+                        }
+                    }
+                    None => {
+                        if !is_closing && SKIPPED_TAGS.contains(&name.as_str()) && !tag.ends_with('/') {
+                            skipping = Some(name);
+                        } else if BLOCK_TAGS.contains(&name.as_str()) {
+                            out.push(' ');
+                        }
+                    }
+                }
+                rest = &rest[end + 1..];
+            }
+            '&' if skipping.is_none() => {
+                let decoded = rest[1..]
+                    .find(';')
+                    .filter(|&semi| semi <= 10)
+                    .and_then(|semi| decode_entity(&rest[1..semi + 1]).map(|ch| (ch, semi + 2)));
+                match decoded {
+                    Some((ch, len)) => {
+                        out.push(ch);
+                        rest = &rest[len..];
+                    }
+                    None => {
+                        out.push('&');
+                        rest = &rest[1..];
+                    }
+                }
+            }
+            _ => {
+                if skipping.is_none() {
+                    out.push(c);
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,124 @@
+use sqlx::{Row, SqliteConnection, SqlitePool};
+use super::plain_text::html_to_plain_text;
+use super::fts::{index_version, set_index_version};
+
+// Bump when html_to_plain_text changes
+const PLAIN_TEXT_VERSION: &str = "1";
+
+// Rows read per query while filling, so a whole dictionary of HTML is never in memory at once
+const FILL_BATCH_ROWS: i64 = 1000;
+
+/// `{table}_text` has the definition columns of `table`, HTML stripped and entities decoded
+/// (NULL if nothing is left), and `dict_rowid` pointing back to the `table` row.
+/// Content search and the FTS index use it, so they don't match markup
+struct PlainTextTable {
+    table: &'static str,
+    columns: &'static [&'static str],
+}
+
+const PLAIN_TEXT_TABLES: &[PlainTextTable] = &[
+    PlainTextTable {
+        table: "en_Dict",
+        columns: &["Desc", "en_km_com", "Desc_en_only"],
+    },
+    PlainTextTable {
+        table: "km_Dict",
+        columns: &[
+            "from_csv_rawHtml",
+            "en_km_com",
+            "Desc",
+            "from_chuon_nath",
+            "from_chuon_nath_translated",
+            "Wiktionary",
+            "from_russian_wiki",
+            "gorgoniev",
+        ],
+    },
+    PlainTextTable {
+        table: "ru_Dict",
+        columns: &["Desc"],
+    },
+];
+
+async fn fill_plain_text_table(conn: &mut SqliteConnection, t: &PlainTextTable) -> Result<usize, String> {
+    let columns = t.columns.join(", ");
+    let create_sql = format!(
+        "DROP TABLE IF EXISTS {table}_text; CREATE TABLE {table}_text (dict_rowid INTEGER PRIMARY KEY, {columns});",
+        table = t.table,
+        columns = t.columns.iter().map(|c| format!("{} TEXT", c)).collect::<Vec<_>>().join(", ")
+    );
+    sqlx::raw_sql(&create_sql)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to create {}_text: {}", t.table, e))?;
+
+    let select_sql = format!("SELECT rowid, {} FROM {} WHERE rowid > ? ORDER BY rowid LIMIT ?", columns, t.table);
+    let insert_sql = format!(
+        "INSERT INTO {}_text (dict_rowid, {}) VALUES (?, {})",
+        t.table,
+        columns,
+        vec!["?"; t.columns.len()].join(", ")
+    );
+
+    let mut last_rowid = i64::MIN;
+    let mut filled = 0;
+    loop {
+        let rows = sqlx::query(&select_sql)
+            .bind(last_rowid)
+            .bind(FILL_BATCH_ROWS)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for row in &rows {
+            last_rowid = row.try_get(0).map_err(|e| e.to_string())?;
+            let mut insert = sqlx::query(&insert_sql).bind(last_rowid);
+            for i in 1..=t.columns.len() {
+                let html: Option<String> = row.try_get(i).map_err(|e| e.to_string())?;
+                insert = insert.bind(html.map(|h| html_to_plain_text(&h)).filter(|text| !text.is_empty()));
+            }
+            insert
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| format!("Failed to fill {}_text: {}", t.table, e))?;
+        }
+        filled += rows.len();
+    }
+
+    Ok(filled)
+}
+
+/// Strips the HTML of the definitions into en_Dict_text / km_Dict_text / ru_Dict_text.
+/// Must run before ensure_fts_index, which indexes this text
+pub async fn ensure_plain_text_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "plain_text_version").await?.as_deref() == Some(PLAIN_TEXT_VERSION) {
+        println!("✅ Plain text of definitions is up to date (v{}).", PLAIN_TEXT_VERSION);
+        return Ok(());
+    }
+
+    println!("🧹 Stripping HTML from definitions...");
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let mut filled = 0;
+    for t in PLAIN_TEXT_TABLES {
+        filled += fill_plain_text_table(&mut tx, t).await?;
+    }
+
+    // the FTS index holds the old text, rebuild it
+    sqlx::query("DELETE FROM metadata WHERE key = 'fts_version'")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    set_index_version(&mut tx, "plain_text_version", PLAIN_TEXT_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Plain text built for {} rows (v{}).", filled, PLAIN_TEXT_VERSION);
+
+    Ok(())
+}
@@ -1,10 +1,18 @@
 use crate::app_state::AppState;
+use crate::russian::morph::stem_ru_term;
 use serde::Serialize;
-use std::collections::HashMap;
+use sqlx::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+use tauri::ipc::Channel;
 use tauri::{State, command};
+use super::content_search::{ContentSearchHit, ContentSearchTable, build_hit, fetch_content_search_page};
+use super::ru_stem::{RuLemmaMatch, resolve_ru_lemma};
+use super::search_query::{CompiledQuery, compile_query, parse_query};
+use super::pagination::{Page, PageParams};
+use super::stream::{BatchSender, StreamEvent, StreamedDetail, for_each_row};
 use super::common::{WordRow, ShortDefinitionRu, RuShortDefinitionSource, validate_words_not_empty, get_placeholders, to_strict_map, to_optional_map, to_optional_map_wrap, fetch_many};
 
-const RU_SHORT_DESC_SOURCE: &str = "1";
+pub const RU_SHORT_DESC_SOURCE: &str = "1";
 
 #[derive(Serialize, sqlx::FromRow)]
 pub struct WordDetailRu {
@@ -13,6 +21,10 @@ pub struct WordDetailRu {
     pub word_display: Option<String>,
     #[sqlx(rename = "Desc")]
     pub desc: String,
+    // set when the requested form is not a headword itself
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lemma: Option<RuLemmaMatch>,
 }
 
 #[derive(sqlx::FromRow)]
@@ -30,6 +42,7 @@ impl From<RuDetailRaw> for WordDetailRu {
         Self {
             word_display: raw.word_display,
             desc: raw.desc,
+            lemma: None,
         }
     }
 }
@@ -45,6 +58,47 @@ pub async fn get_ru_words(state: State<'_, AppState>) -> Result<Vec<String>, Str
     Ok(rows.into_iter().map(|r| r.word).collect())
 }
 
+#[command]
+pub async fn stream_ru_words(
+    state: State<'_, AppState>,
+    on_event: Channel<StreamEvent<String>>,
+) -> Result<(), String> {
+    let pool = state.get_pool().await?;
+    let sql = "SELECT Word FROM ru_Dict ORDER BY Word ASC";
+
+    let mut sender = BatchSender::new(&on_event);
+    for_each_row::<WordRow>(&pool, sql, &[], |r| sender.push(r.word)).await?;
+    sender.finish()
+}
+
+#[command]
+pub async fn get_ru_words_page(
+    state: State<'_, AppState>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+) -> Result<Page<String>, String> {
+    let params = PageParams::new(cursor, page_size)?;
+    let pool = state.get_pool().await?;
+
+    let sql = "SELECT Word FROM ru_Dict WHERE Word > ? ORDER BY Word ASC LIMIT ?";
+    let rows = sqlx::query_as::<_, WordRow>(sql)
+        .bind(params.after_word())
+        .bind(params.fetch_limit())
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let total = match params.total_estimate() {
+        Some(total) => total,
+        None => sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM ru_Dict")
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| e.to_string())?,
+    };
+
+    Ok(params.keyset_page(rows, total, |r| r.word.clone(), |r| Some(r.word)))
+}
+
 #[command]
 pub async fn get_word_detail_ru(
     state: State<'_, AppState>,
@@ -53,27 +107,124 @@ pub async fn get_word_detail_ru(
     let pool = state.get_pool().await?;
     let sql = "SELECT * FROM ru_Dict WHERE Word = ?";
     let row = sqlx::query_as::<_, WordDetailRu>(&sql)
-        .bind(word)
+        .bind(&word)
         .fetch_optional(&pool)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(row)
+    if row.is_some() {
+        return Ok(row);
+    }
+
+    // кошки -> кошка, пошёл -> пойти
+    let Some(lemma) = resolve_ru_lemma(&pool, &word).await? else { return Ok(None) };
+    let row = sqlx::query_as::<_, WordDetailRu>(sql)
+        .bind(&lemma.lemma)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| WordDetailRu { lemma: Some(lemma), ..r }))
+}
+
+/// HTML-free text of the definition (see plain_text_index.rs), for TTS and copying. Same keys as WordDetailRu
+#[derive(Serialize, sqlx::FromRow, Debug)]
+pub struct WordPlainTextRu {
+    #[sqlx(rename = "Desc")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
+}
+
+#[command]
+pub async fn get_word_plain_text_ru(state: State<'_, AppState>, word: String) -> Result<Option<WordPlainTextRu>, String> {
+    let pool = state.get_pool().await?;
+    let sql = "SELECT x.* FROM ru_Dict d JOIN ru_Dict_text x ON x.dict_rowid = d.rowid WHERE d.Word = ?";
+    sqlx::query_as::<_, WordPlainTextRu>(sql)
+        .bind(&word)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+const RU_CONTENT_SEARCH: ContentSearchTable = ContentSearchTable {
+    table: "ru_Dict",
+    columns: "d.Word, x.Desc",
+};
+
+#[derive(sqlx::FromRow)]
+pub struct RuContentSearchRow {
+    #[sqlx(rename = "Word")]
+    pub word: String,
+    pub score: f64,
+    #[sqlx(rename = "Desc")]
+    pub desc: Option<String>,
+}
+
+impl RuContentSearchRow {
+    fn into_hit(self, query: &CompiledQuery<RuShortDefinitionSource>) -> Option<ContentSearchHit<RuShortDefinitionSource>> {
+        build_hit(
+            self.word,
+            self.score,
+            query,
+            vec![(RuShortDefinitionSource::Desc, self.desc)],
+        )
+    }
+}
+
+/// A term of the query and what it was searched as: its stem, or the folded word if the stem is too short
+#[derive(Serialize, Clone, Debug)]
+pub struct RuSearchedTerm {
+    pub typed: String,
+    pub searched: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct RuContentSearchPage {
+    #[serde(flatten)]
+    pub page: Page<ContentSearchHit<RuShortDefinitionSource>>,
+    // in query order, so the frontend can show why кошки matched кошку
+    pub terms: Vec<RuSearchedTerm>,
+}
+
+/// One page of `search_ru_content`, also used by the global search
+pub async fn find_ru_content(
+    conn: &mut SqliteConnection,
+    query: &str,
+    sources: Option<&[RuShortDefinitionSource]>,
+    params: &PageParams,
+) -> Result<RuContentSearchPage, String> {
+    let mut node = parse_query::<RuShortDefinitionSource>(query)?;
+    let terms = node
+        .terms()
+        .into_iter()
+        .map(|term| RuSearchedTerm { typed: term.text.clone(), searched: stem_ru_term(&term.text) })
+        .collect();
+    // inflected forms share the stem: кошки finds кошка, кошку, кошек...
+    node.map_terms(&stem_ru_term);
+    let compiled = compile_query(&node, RU_CONTENT_SEARCH.table, sources.unwrap_or_default());
+
+    let (rows, total) = fetch_content_search_page::<RuContentSearchRow, _>(conn, &RU_CONTENT_SEARCH, &compiled, params).await?;
+
+    Ok(RuContentSearchPage { page: params.offset_page(rows, total, |r| r.into_hit(&compiled)), terms })
 }
 
 #[command]
 pub async fn search_ru_content(
     state: State<'_, AppState>,
     query: String,
-) -> Result<Vec<String>, String> {
+    sources: Option<Vec<RuShortDefinitionSource>>,
+    cursor: Option<String>,
+    page_size: Option<u32>,
+    request_key: Option<String>,
+) -> Result<RuContentSearchPage, String> {
+    let params = PageParams::new(cursor, page_size)?;
     let pool = state.get_pool().await?;
-    let pattern = format!("%{}%", query);
-    let sql = "SELECT Word FROM ru_Dict WHERE Desc LIKE ? ORDER BY LENGTH(Word) ASC LIMIT 50";
-    let rows = sqlx::query_as::<_, WordRow>(&sql)
-        .bind(pattern)
-        .fetch_all(&pool)
+
+    state
+        .inflight
+        .run(request_key.as_deref(), |scope| async move {
+            let mut conn = scope.acquire(&pool).await?;
+            find_ru_content(&mut conn, &query, sources.as_deref(), &params).await
+        })
         .await
-        .map_err(|e| e.to_string())?;
-    Ok(rows.into_iter().map(|r| r.word).collect())
 }
 
 #[derive(Serialize, sqlx::FromRow)]
@@ -151,3 +302,26 @@ pub async fn ru_for_many_short_description_throws_if_word_not_found(
 
     to_strict_map(words, rows, |r| r.word.clone(), |r| ShortDefinitionRu { definition: r.definition, source: r.source })
 }
+
+#[command]
+pub async fn stream_ru_full_details(
+    state: State<'_, AppState>,
+    words: Vec<String>,
+    on_event: Channel<StreamEvent<StreamedDetail<WordDetailRu>>>,
+) -> Result<(), String> {
+    validate_words_not_empty(&words)?;
+
+    let pool = state.get_pool().await?;
+    let sql = format!("SELECT * FROM ru_Dict WHERE Word IN ({})", get_placeholders(words.len()));
+
+    let mut sender = BatchSender::new(&on_event);
+    let mut sent: HashSet<String> = HashSet::new();
+
+    for_each_row::<RuDetailRaw>(&pool, &sql, &words, |raw| {
+        sent.insert(raw.word.clone());
+        sender.push(StreamedDetail { word: raw.word.clone(), detail: Some(WordDetailRu::from(raw)) })
+    })
+    .await?;
+
+    sender.finish_details(&words, &sent)
+}
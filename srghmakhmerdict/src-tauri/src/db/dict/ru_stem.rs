@@ -0,0 +1,159 @@
+use crate::russian::morph::{fold_ru_word, stem_ru, suppletive_lemmas};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use super::common::WordRow;
+use super::fts::{index_version, set_index_version};
+
+// Bump when the stemmer or fold_ru changes
+const RU_STEM_VERSION: &str = "1";
+
+// ru_Dict.Word -> folded form and Snowball stem. Computed in Rust, SQLite can't do it
+const CREATE_RU_STEM_TABLE_SQL: &str = "
+    DROP TABLE IF EXISTS ru_Dict_stem;
+    CREATE TABLE ru_Dict_stem (Word TEXT PRIMARY KEY, folded TEXT NOT NULL, stem TEXT NOT NULL);
+";
+
+const CREATE_RU_STEM_INDEXES_SQL: &str = "
+    CREATE INDEX ru_Dict_stem_folded ON ru_Dict_stem (folded);
+    CREATE INDEX ru_Dict_stem_stem ON ru_Dict_stem (stem);
+";
+
+/// Fills ru_Dict_stem with the folded and stemmed headwords
+pub async fn ensure_ru_stem_index(pool: &SqlitePool) -> Result<(), String> {
+    if index_version(pool, "ru_stem_version").await?.as_deref() == Some(RU_STEM_VERSION) {
+        println!("✅ Russian stems are up to date (v{}).", RU_STEM_VERSION);
+        return Ok(());
+    }
+
+    println!("🔤 Building Russian stems...");
+
+    let words = sqlx::query_as::<_, WordRow>("SELECT Word FROM ru_Dict")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::raw_sql(CREATE_RU_STEM_TABLE_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to create ru_Dict_stem: {}", e))?;
+
+    for row in &words {
+        sqlx::query("INSERT OR IGNORE INTO ru_Dict_stem (Word, folded, stem) VALUES (?, ?, ?)")
+            .bind(&row.word)
+            .bind(fold_ru_word(&row.word))
+            .bind(stem_ru(&row.word))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to fill ru_Dict_stem: {}", e))?;
+    }
+
+    sqlx::raw_sql(CREATE_RU_STEM_INDEXES_SQL)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    set_index_version(&mut tx, "ru_stem_version", RU_STEM_VERSION).await?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    println!("✅ Russian stems built for {} words (v{}).", words.len(), RU_STEM_VERSION);
+
+    Ok(())
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum RuLemmaVia {
+    // differs only in case or ё/е
+    Folded,
+    // a form the stemmer can't relate to its lemma, from a built-in table
+    Suppletive,
+    // same Snowball stem
+    Stem,
+}
+
+/// The headword a form that is not in ru_Dict itself was resolved to
+#[derive(Serialize, Clone, Debug)]
+pub struct RuLemmaMatch {
+    pub lemma: String,
+    pub via: RuLemmaVia,
+}
+
+const BY_FOLDED_SQL: &str = "SELECT Word FROM ru_Dict_stem WHERE folded = ? ORDER BY Word ASC LIMIT 1";
+
+async fn first_word(pool: &SqlitePool, sql: &str, key: &str) -> Result<Option<String>, String> {
+    let row = sqlx::query_as::<_, WordRow>(sql)
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(row.map(|r| r.word))
+}
+
+/// Headword for a word form, tried in order: folded, suppletive table, stem.
+/// Of several headwords with the same stem the shortest wins (кошки -> кошка, not кошкин)
+pub async fn resolve_ru_lemma(pool: &SqlitePool, word: &str) -> Result<Option<RuLemmaMatch>, String> {
+    let folded = fold_ru_word(word);
+    if folded.is_empty() {
+        return Ok(None);
+    }
+
+    if let Some(lemma) = first_word(pool, BY_FOLDED_SQL, &folded).await? {
+        return Ok(Some(RuLemmaMatch { lemma, via: RuLemmaVia::Folded }));
+    }
+
+    // the table lists the likelier lemma first
+    for lemma in suppletive_lemmas(word) {
+        if let Some(lemma) = first_word(pool, BY_FOLDED_SQL, &fold_ru_word(lemma)).await? {
+            return Ok(Some(RuLemmaMatch { lemma, via: RuLemmaVia::Suppletive }));
+        }
+    }
+
+    let sql = "SELECT Word FROM ru_Dict_stem WHERE stem = ? ORDER BY LENGTH(Word) ASC, Word ASC LIMIT 1";
+    if let Some(lemma) = first_word(pool, sql, &stem_ru(word)).await? {
+        return Ok(Some(RuLemmaMatch { lemma, via: RuLemmaVia::Stem }));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn pool(words: &[&str]) -> SqlitePool {
+        // one connection, every new one would open an empty in-memory database
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE ru_Dict (Word TEXT PRIMARY KEY)").execute(&pool).await.unwrap();
+        for word in words {
+            sqlx::query("INSERT INTO ru_Dict (Word) VALUES (?)").bind(word).execute(&pool).await.unwrap();
+        }
+        ensure_ru_stem_index(&pool).await.unwrap();
+        pool
+    }
+
+    async fn resolve(pool: &SqlitePool, word: &str) -> Option<(String, RuLemmaVia)> {
+        resolve_ru_lemma(pool, word).await.unwrap().map(|m| (m.lemma, m.via))
+    }
+
+    #[tokio::test]
+    async fn folded_then_suppletive_then_stem() {
+        let pool = pool(&["Кошка", "есть", "ель", "идти"]).await;
+        assert_eq!(resolve(&pool, "КОШКА").await, Some(("Кошка".to_string(), RuLemmaVia::Folded)));
+        assert_eq!(resolve(&pool, "шёл").await, Some(("идти".to_string(), RuLemmaVia::Suppletive)));
+        // ели shares its stem with ель, the table's likelier lemma wins
+        assert_eq!(resolve(&pool, "ели").await, Some(("есть".to_string(), RuLemmaVia::Suppletive)));
+        assert_eq!(resolve(&pool, "кошки").await, Some(("Кошка".to_string(), RuLemmaVia::Stem)));
+        assert_eq!(resolve(&pool, "собака").await, None);
+        assert_eq!(resolve(&pool, " ").await, None);
+    }
+
+    #[tokio::test]
+    async fn shortest_headword_of_a_stem_wins() {
+        // кошками sorts first, but is longer
+        let pool = pool(&["кошками", "кошки"]).await;
+        assert_eq!(resolve(&pool, "кошку").await, Some(("кошки".to_string(), RuLemmaVia::Stem)));
+    }
+}
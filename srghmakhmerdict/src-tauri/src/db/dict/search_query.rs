@@ -0,0 +1,624 @@
+// Query language of the content search:
+//   кошка                    term, matched as a substring of the definition
+//   "домашнее животное"      phrase
+//   cat dog, cat AND dog     both
+//   cat OR dog               either
+//   NOT dog, -dog            without
+//   (cat OR dog) -wild       grouping
+//   source:gorgoniev кошка   only in the given source(s), also source:wiktionary,desc (...)
+// Operators are upper case only, so "or" and "and" are searched as words.
+// NOT binds tighter than AND, AND tighter than OR.
+//
+// The compiled SQL takes every term as a bound parameter, column names only come from the source enums.
+
+use super::common::{EnShortDefinitionSource, KmShortDefinitionSource, RuShortDefinitionSource};
+use super::fts::{to_fts_phrase, to_like_pattern};
+use super::source_priority::DefinitionSource;
+
+/// Definition source of a `*_Dict` table that the content search can be restricted to
+pub trait SearchableSource: DefinitionSource {
+    /// Column of the `*_fts` table (see fts.rs)
+    fn fts_column(self) -> &'static str;
+    /// Columns of the `*_Dict_text` table, searched with LIKE when a term is too short for the trigram index
+    fn like_columns(self) -> &'static [&'static str];
+    /// Names accepted after `source:`, lower case without '_'; the first one is shown in errors
+    fn query_names(self) -> &'static [&'static str];
+}
+
+impl SearchableSource for KmShortDefinitionSource {
+    fn fts_column(self) -> &'static str {
+        match self {
+            KmShortDefinitionSource::FromCsvRawHtml => "from_csv_rawHtml",
+            KmShortDefinitionSource::EnKmCom => "en_km_com",
+            KmShortDefinitionSource::Desc => "Desc",
+            // km original and translation are indexed as one column
+            KmShortDefinitionSource::FromChuonNathTranslated => "from_chuon_nath",
+            KmShortDefinitionSource::Wiktionary => "wiktionary",
+            KmShortDefinitionSource::FromRussianWiki => "from_russian_wiki",
+            KmShortDefinitionSource::Gorgoniev => "gorgoniev",
+        }
+    }
+
+    fn like_columns(self) -> &'static [&'static str] {
+        match self {
+            KmShortDefinitionSource::FromCsvRawHtml => &["from_csv_rawHtml"],
+            KmShortDefinitionSource::EnKmCom => &["en_km_com"],
+            KmShortDefinitionSource::Desc => &["Desc"],
+            KmShortDefinitionSource::FromChuonNathTranslated => &["from_chuon_nath", "from_chuon_nath_translated"],
+            KmShortDefinitionSource::Wiktionary => &["Wiktionary"],
+            KmShortDefinitionSource::FromRussianWiki => &["from_russian_wiki"],
+            KmShortDefinitionSource::Gorgoniev => &["gorgoniev"],
+        }
+    }
+
+    fn query_names(self) -> &'static [&'static str] {
+        match self {
+            KmShortDefinitionSource::FromCsvRawHtml => &["csv", "fromcsvrawhtml"],
+            KmShortDefinitionSource::EnKmCom => &["enkmcom"],
+            KmShortDefinitionSource::Desc => &["desc"],
+            KmShortDefinitionSource::FromChuonNathTranslated => &["chuonnath", "fromchuonnathtranslated"],
+            KmShortDefinitionSource::Wiktionary => &["wiktionary"],
+            KmShortDefinitionSource::FromRussianWiki => &["russianwiki", "fromrussianwiki"],
+            KmShortDefinitionSource::Gorgoniev => &["gorgoniev"],
+        }
+    }
+}
+
+impl SearchableSource for EnShortDefinitionSource {
+    fn fts_column(self) -> &'static str {
+        self.column()
+    }
+
+    fn like_columns(self) -> &'static [&'static str] {
+        match self {
+            EnShortDefinitionSource::Desc => &["Desc"],
+            EnShortDefinitionSource::EnKmCom => &["en_km_com"],
+            EnShortDefinitionSource::DescEnOnly => &["Desc_en_only"],
+        }
+    }
+
+    fn query_names(self) -> &'static [&'static str] {
+        match self {
+            EnShortDefinitionSource::Desc => &["desc"],
+            EnShortDefinitionSource::EnKmCom => &["enkmcom"],
+            EnShortDefinitionSource::DescEnOnly => &["descenonly"],
+        }
+    }
+}
+
+impl SearchableSource for RuShortDefinitionSource {
+    fn fts_column(self) -> &'static str {
+        self.column()
+    }
+
+    fn like_columns(self) -> &'static [&'static str] {
+        &["Desc"]
+    }
+
+    fn query_names(self) -> &'static [&'static str] {
+        &["desc"]
+    }
+}
+
+fn source_by_name<S: SearchableSource>(name: &str) -> Result<S, String> {
+    let key: String = name.chars().filter(|c| *c != '_' && *c != '-').flat_map(char::to_lowercase).collect();
+    S::DEFAULT_ORDER
+        .iter()
+        .copied()
+        .find(|s| s.query_names().contains(&key.as_str()))
+        .ok_or_else(|| {
+            let known: Vec<&str> = S::DEFAULT_ORDER.iter().map(|s| s.query_names()[0]).collect();
+            format!("Unknown source: {} (known: {})", name, known.join(", "))
+        })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term<S> {
+    pub text: String,
+    // None = any source
+    pub sources: Option<Vec<S>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryNode<S> {
+    Term(Term<S>),
+    And(Vec<QueryNode<S>>),
+    Or(Vec<QueryNode<S>>),
+    Not(Box<QueryNode<S>>),
+}
+
+impl<S> QueryNode<S> {
+    /// Rewrites the text of every term, e.g. to stem it
+    pub fn map_terms(&mut self, f: &impl Fn(&str) -> String) {
+        match self {
+            QueryNode::Term(term) => term.text = f(&term.text),
+            QueryNode::And(items) | QueryNode::Or(items) => items.iter_mut().for_each(|item| item.map_terms(f)),
+            QueryNode::Not(inner) => inner.map_terms(f),
+        }
+    }
+
+    /// Every term in query order, negated ones included
+    pub fn terms(&self) -> Vec<&Term<S>> {
+        match self {
+            QueryNode::Term(term) => vec![term],
+            QueryNode::And(items) | QueryNode::Or(items) => items.iter().flat_map(QueryNode::terms).collect(),
+            QueryNode::Not(inner) => inner.terms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<S> {
+    Text(String),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Source(Vec<S>),
+}
+
+fn tokenize<S: SearchableSource>(input: &str) -> Result<Vec<Token<S>>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err("Unterminated quote in search query".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(phrase));
+            }
+            '-' => {
+                chars.next();
+                // "-word" is NOT word, a lone "-" is searched as is
+                match chars.peek() {
+                    Some(&next) if !next.is_whitespace() && next != ')' => tokens.push(Token::Not),
+                    _ => tokens.push(Token::Text("-".to_string())),
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => match word.split_once(':') {
+                        Some((field, names)) if field.eq_ignore_ascii_case("source") => {
+                            if names.is_empty() {
+                                return Err("source: needs a source name, e.g. source:desc".to_string());
+                            }
+                            let sources = names.split(',').filter(|n| !n.is_empty()).map(source_by_name).collect::<Result<Vec<S>, String>>()?;
+                            Token::Source(sources)
+                        }
+                        _ => Token::Text(word),
+                    },
+                };
+                tokens.push(token);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<S> {
+    tokens: Vec<Token<S>>,
+    pos: usize,
+}
+
+impl<S: SearchableSource> Parser<S> {
+    fn peek(&self) -> Option<&Token<S>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token<S>> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self, scope: &Option<Vec<S>>) -> Result<QueryNode<S>, String> {
+        let mut items = vec![self.parse_and(scope)?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            items.push(self.parse_and(scope)?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { QueryNode::Or(items) })
+    }
+
+    fn parse_and(&mut self, scope: &Option<Vec<S>>) -> Result<QueryNode<S>, String> {
+        let mut items = vec![self.parse_unary(scope)?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                    items.push(self.parse_unary(scope)?);
+                }
+                // juxtaposition is AND
+                Some(Token::Text(_) | Token::LParen | Token::Not | Token::Source(_)) => items.push(self.parse_unary(scope)?),
+                _ => break,
+            }
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { QueryNode::And(items) })
+    }
+
+    fn parse_unary(&mut self, scope: &Option<Vec<S>>) -> Result<QueryNode<S>, String> {
+        match self.next() {
+            Some(Token::Not) => match self.parse_unary(scope)? {
+                QueryNode::Not(inner) => Ok(*inner),
+                inner => Ok(QueryNode::Not(Box::new(inner))),
+            },
+            Some(Token::Source(sources)) => self.parse_unary(&Some(sources)),
+            Some(Token::LParen) => {
+                let inner = self.parse_or(scope)?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing ) in search query".to_string()),
+                }
+            }
+            Some(Token::Text(text)) => {
+                let text = text.trim().to_string();
+                if text.is_empty() {
+                    return Err("Empty phrase in search query".to_string());
+                }
+                Ok(QueryNode::Term(Term { text, sources: scope.clone() }))
+            }
+            Some(Token::RParen) => Err("Unexpected ) in search query".to_string()),
+            Some(Token::And) => Err("AND needs a term on both sides".to_string()),
+            Some(Token::Or) => Err("OR needs a term on both sides".to_string()),
+            None => Err("Search query ends unexpectedly".to_string()),
+        }
+    }
+}
+
+fn has_positive_term<S>(node: &QueryNode<S>) -> bool {
+    match node {
+        QueryNode::Term(_) => true,
+        QueryNode::And(items) => items.iter().any(has_positive_term),
+        // every branch has to be able to match something
+        QueryNode::Or(items) => items.iter().all(has_positive_term),
+        QueryNode::Not(_) => false,
+    }
+}
+
+pub fn parse_query<S: SearchableSource>(input: &str) -> Result<QueryNode<S>, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+
+    let mut parser = Parser { tokens, pos: 0 };
+    let node = parser.parse_or(&None)?;
+    if parser.pos < parser.tokens.len() {
+        return Err("Unexpected ) in search query".to_string());
+    }
+    if !has_positive_term(&node) {
+        return Err("Search query needs a term that is not negated".to_string());
+    }
+
+    Ok(node)
+}
+
+/// WHERE clause over `{table} d`, its parameters in order, plus what is needed for ranking and snippets
+pub struct CompiledQuery<S> {
+    pub where_sql: String,
+    pub params: Vec<String>,
+    // FTS5 expression of all positive terms for bm25, None if they are all too short for the index
+    pub rank_match: Option<String>,
+    // terms not under NOT, for highlighting
+    pub positive_terms: Vec<Term<S>>,
+    // sources the search is restricted to, in default order
+    pub allowed: Vec<S>,
+}
+
+struct Compiler<'a, S> {
+    table: &'a str,
+    allowed: &'a [S],
+    params: Vec<String>,
+    rank_parts: Vec<String>,
+    positive_terms: Vec<Term<S>>,
+}
+
+impl<S: SearchableSource> Compiler<'_, S> {
+    fn term_sources(&self, term: &Term<S>) -> Vec<S> {
+        self.allowed
+            .iter()
+            .copied()
+            .filter(|s| term.sources.as_ref().is_none_or(|only| only.contains(s)))
+            .collect()
+    }
+
+    fn compile(&mut self, node: &QueryNode<S>, negated: bool) -> String {
+        match node {
+            QueryNode::Term(term) => self.compile_term(term, negated),
+            QueryNode::And(items) => self.join(items, " AND ", negated),
+            QueryNode::Or(items) => self.join(items, " OR ", negated),
+            QueryNode::Not(inner) => format!("NOT {}", self.compile(inner, !negated)),
+        }
+    }
+
+    fn join(&mut self, items: &[QueryNode<S>], op: &str, negated: bool) -> String {
+        let parts: Vec<String> = items.iter().map(|item| self.compile(item, negated)).collect();
+        format!("({})", parts.join(op))
+    }
+
+    fn compile_term(&mut self, term: &Term<S>, negated: bool) -> String {
+        let sources = self.term_sources(term);
+        if sources.is_empty() {
+            // restricted to sources that are all excluded
+            return "0".to_string();
+        }
+
+        if !negated {
+            self.positive_terms.push(term.clone());
+        }
+
+        match to_fts_phrase(&term.text) {
+            Some(phrase) => {
+                let columns: Vec<&str> = sources.iter().map(|s| s.fts_column()).collect();
+                let expr = format!("{{{}}} : {}", columns.join(" "), phrase);
+                if !negated {
+                    self.rank_parts.push(format!("({})", expr));
+                }
+                self.params.push(expr);
+                format!("d.rowid IN (SELECT rowid FROM {}_fts WHERE {}_fts MATCH ?)", self.table, self.table)
+            }
+            None => {
+                let pattern = to_like_pattern(&term.text);
+                let likes: Vec<String> = sources
+                    .iter()
+                    .flat_map(|s| s.like_columns())
+                    .map(|column| {
+                        self.params.push(pattern.clone());
+                        // empty sources are NULL, NULL LIKE ? would make a negated term drop the row
+                        format!("COALESCE(x.{}, '') LIKE ? ESCAPE '\\'", column)
+                    })
+                    .collect();
+                format!("({})", likes.join(" OR "))
+            }
+        }
+    }
+}
+
+/// `allowed` restricts every term to these sources (all if empty)
+pub fn compile_query<S: SearchableSource>(node: &QueryNode<S>, table: &str, allowed: &[S]) -> CompiledQuery<S> {
+    let allowed: Vec<S> = S::DEFAULT_ORDER
+        .iter()
+        .copied()
+        .filter(|s| allowed.is_empty() || allowed.contains(s))
+        .collect();
+
+    let mut compiler = Compiler {
+        table,
+        allowed: &allowed,
+        params: Vec::new(),
+        rank_parts: Vec::new(),
+        positive_terms: Vec::new(),
+    };
+    let where_sql = compiler.compile(node, false);
+    let rank_match = (!compiler.rank_parts.is_empty()).then(|| compiler.rank_parts.join(" OR "));
+
+    CompiledQuery {
+        where_sql,
+        params: compiler.params,
+        rank_match,
+        positive_terms: compiler.positive_terms,
+        allowed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use KmShortDefinitionSource::*;
+
+    type Node = QueryNode<KmShortDefinitionSource>;
+
+    fn term(text: &str) -> Node {
+        QueryNode::Term(Term { text: text.to_string(), sources: None })
+    }
+
+    fn scoped(text: &str, sources: &[KmShortDefinitionSource]) -> Node {
+        QueryNode::Term(Term { text: text.to_string(), sources: Some(sources.to_vec()) })
+    }
+
+    fn not(node: Node) -> Node {
+        QueryNode::Not(Box::new(node))
+    }
+
+    fn parse(input: &str) -> Result<Node, String> {
+        parse_query::<KmShortDefinitionSource>(input)
+    }
+
+    #[test]
+    fn parses_terms_and_phrases() {
+        assert_eq!(parse("кошка"), Ok(term("кошка")));
+        assert_eq!(parse("  ឆ្មា  "), Ok(term("ឆ្មា")));
+        assert_eq!(parse("\"домашнее животное\""), Ok(term("домашнее животное")));
+        assert_eq!(parse("\" spaced \""), Ok(term("spaced")));
+        assert_eq!(parse("e-mail"), Ok(term("e-mail")));
+        assert_eq!(parse("3:00"), Ok(term("3:00")));
+    }
+
+    #[test]
+    fn juxtaposition_and_explicit_and() {
+        assert_eq!(parse("cat dog"), Ok(QueryNode::And(vec![term("cat"), term("dog")])));
+        assert_eq!(parse("cat AND dog"), Ok(QueryNode::And(vec![term("cat"), term("dog")])));
+        assert_eq!(
+            parse("cat \"big dog\" bird"),
+            Ok(QueryNode::And(vec![term("cat"), term("big dog"), term("bird")]))
+        );
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(
+            parse("a1a AND b2b OR c3c"),
+            Ok(QueryNode::Or(vec![QueryNode::And(vec![term("a1a"), term("b2b")]), term("c3c")]))
+        );
+        assert_eq!(
+            parse("a1a OR b2b c3c"),
+            Ok(QueryNode::Or(vec![term("a1a"), QueryNode::And(vec![term("b2b"), term("c3c")])]))
+        );
+        assert_eq!(
+            parse("(a1a OR b2b) c3c"),
+            Ok(QueryNode::And(vec![QueryNode::Or(vec![term("a1a"), term("b2b")]), term("c3c")]))
+        );
+    }
+
+    #[test]
+    fn lower_case_operators_are_words() {
+        assert_eq!(parse("cat or dog"), Ok(QueryNode::And(vec![term("cat"), term("or"), term("dog")])));
+        assert_eq!(parse("\"OR\""), Ok(term("OR")));
+    }
+
+    #[test]
+    fn not_and_minus() {
+        assert_eq!(parse("cat NOT dog"), Ok(QueryNode::And(vec![term("cat"), not(term("dog"))])));
+        assert_eq!(parse("cat -dog"), Ok(QueryNode::And(vec![term("cat"), not(term("dog"))])));
+        assert_eq!(parse("cat -\"big dog\""), Ok(QueryNode::And(vec![term("cat"), not(term("big dog"))])));
+        assert_eq!(
+            parse("cat -(dog OR bird)"),
+            Ok(QueryNode::And(vec![term("cat"), not(QueryNode::Or(vec![term("dog"), term("bird")]))]))
+        );
+        assert_eq!(parse("cat - dog"), Ok(QueryNode::And(vec![term("cat"), term("-"), term("dog")])));
+        assert_eq!(parse("NOT NOT cat"), Ok(term("cat")));
+        assert_eq!(parse("cat -NOT dog"), Ok(QueryNode::And(vec![term("cat"), term("dog")])));
+    }
+
+    #[test]
+    fn source_prefix_scopes_the_next_term_or_group() {
+        assert_eq!(parse("source:gorgoniev кошка"), Ok(scoped("кошка", &[Gorgoniev])));
+        assert_eq!(parse("SOURCE:Wiktionary cat"), Ok(scoped("cat", &[Wiktionary])));
+        assert_eq!(parse("source:wiktionary,desc cat"), Ok(scoped("cat", &[Wiktionary, Desc])));
+        assert_eq!(parse("source:chuon_nath cat"), Ok(scoped("cat", &[FromChuonNathTranslated])));
+        assert_eq!(parse("source:russian-wiki cat"), Ok(scoped("cat", &[FromRussianWiki])));
+        assert_eq!(parse("source:csv \"a b\""), Ok(scoped("a b", &[FromCsvRawHtml])));
+        assert_eq!(
+            parse("source:gorgoniev кошка собака"),
+            Ok(QueryNode::And(vec![scoped("кошка", &[Gorgoniev]), term("собака")]))
+        );
+        assert_eq!(
+            parse("source:gorgoniev (кошка OR собака)"),
+            Ok(QueryNode::Or(vec![scoped("кошка", &[Gorgoniev]), scoped("собака", &[Gorgoniev])]))
+        );
+        assert_eq!(
+            parse("source:gorgoniev (кошка source:desc cat)"),
+            Ok(QueryNode::And(vec![scoped("кошка", &[Gorgoniev]), scoped("cat", &[Desc])]))
+        );
+        assert_eq!(parse("-source:desc cat ឆ្មា"), Ok(QueryNode::And(vec![not(scoped("cat", &[Desc])), term("ឆ្មា")])));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse("").is_err());
+        assert!(parse("   ").is_err());
+        assert!(parse("\"unterminated").is_err());
+        assert!(parse("\"\"").is_err());
+        assert!(parse("(cat").is_err());
+        assert!(parse("cat)").is_err());
+        assert!(parse("()").is_err());
+        assert!(parse("cat AND").is_err());
+        assert!(parse("OR cat").is_err());
+        assert!(parse("cat OR OR dog").is_err());
+        assert!(parse("NOT").is_err());
+        assert!(parse("source:").is_err());
+        assert!(parse("source:gorgoniev").is_err());
+        assert!(parse("source:nope cat").unwrap_err().contains("Unknown source: nope"));
+        // nothing to look for
+        assert!(parse("NOT cat").is_err());
+        assert!(parse("-cat -dog").is_err());
+        assert!(parse("cat OR -dog").is_err());
+        assert!(parse("NOT NOT cat").is_ok());
+    }
+
+    #[test]
+    fn compiles_long_terms_to_fts_and_short_terms_to_like() {
+        let q = compile_query(&parse("кошка").unwrap(), "km_Dict", &[]);
+        assert_eq!(q.where_sql, "d.rowid IN (SELECT rowid FROM km_Dict_fts WHERE km_Dict_fts MATCH ?)");
+        assert_eq!(
+            q.params,
+            vec!["{from_csv_rawHtml en_km_com Desc from_chuon_nath wiktionary from_russian_wiki gorgoniev} : \"кошка\""]
+        );
+        assert_eq!(q.rank_match, Some(format!("({})", q.params[0])));
+
+        let q = compile_query(&parse("source:gorgoniev,chuonnath ко").unwrap(), "km_Dict", &[]);
+        assert_eq!(
+            q.where_sql,
+            "(COALESCE(x.from_chuon_nath, '') LIKE ? ESCAPE '\\' OR COALESCE(x.from_chuon_nath_translated, '') LIKE ? ESCAPE '\\' OR COALESCE(x.gorgoniev, '') LIKE ? ESCAPE '\\')"
+        );
+        assert_eq!(q.params, vec!["%ко%", "%ко%", "%ко%"]);
+        assert_eq!(q.rank_match, None);
+    }
+
+    #[test]
+    fn compiles_operators() {
+        let q = compile_query(&parse("source:desc cat -dog OR bird").unwrap(), "km_Dict", &[]);
+        let fts = "d.rowid IN (SELECT rowid FROM km_Dict_fts WHERE km_Dict_fts MATCH ?)";
+        assert_eq!(q.where_sql, format!("(({fts} AND NOT {fts}) OR {fts})"));
+        assert_eq!(
+            q.params[..2],
+            ["{Desc} : \"cat\"".to_string(), "{from_csv_rawHtml en_km_com Desc from_chuon_nath wiktionary from_russian_wiki gorgoniev} : \"dog\"".to_string()]
+        );
+        // negated terms are neither ranked nor highlighted
+        assert_eq!(q.positive_terms.iter().map(|t| t.text.as_str()).collect::<Vec<_>>(), vec!["cat", "bird"]);
+        assert!(!q.rank_match.unwrap().contains("dog"));
+    }
+
+    #[test]
+    fn negated_short_terms_keep_rows_with_empty_sources() {
+        let q = compile_query(&parse("кошка -ab").unwrap(), "km_Dict", &[Desc, Gorgoniev]);
+        let fts = "d.rowid IN (SELECT rowid FROM km_Dict_fts WHERE km_Dict_fts MATCH ?)";
+        assert_eq!(
+            q.where_sql,
+            format!("({fts} AND NOT (COALESCE(x.Desc, '') LIKE ? ESCAPE '\\' OR COALESCE(x.gorgoniev, '') LIKE ? ESCAPE '\\'))")
+        );
+        assert_eq!(q.params[1..], ["%ab%", "%ab%"]);
+    }
+
+    #[test]
+    fn short_terms_match_wildcards_literally() {
+        let q = compile_query(&parse("source:desc 5%").unwrap(), "km_Dict", &[]);
+        assert_eq!(q.params, vec!["%5\\%%"]);
+        let q = compile_query(&parse("source:desc a_").unwrap(), "km_Dict", &[]);
+        assert_eq!(q.params, vec!["%a\\_%"]);
+    }
+
+    #[test]
+    fn allowed_sources_restrict_every_term() {
+        let q = compile_query(&parse("кошка").unwrap(), "km_Dict", &[Gorgoniev, Wiktionary]);
+        assert_eq!(q.params, vec!["{wiktionary gorgoniev} : \"кошка\""]);
+        assert_eq!(q.allowed, vec![Wiktionary, Gorgoniev]);
+
+        // scoped to a source that is not allowed: matches nothing
+        let q = compile_query(&parse("source:desc cat OR кошка").unwrap(), "km_Dict", &[Gorgoniev]);
+        assert_eq!(q.where_sql, "(0 OR d.rowid IN (SELECT rowid FROM km_Dict_fts WHERE km_Dict_fts MATCH ?))");
+        assert_eq!(q.params, vec!["{gorgoniev} : \"кошка\""]);
+    }
+}
@@ -0,0 +1,131 @@
+use serde::Deserialize;
+use super::common::{EnShortDefinitionSource, KmShortDefinitionSource, RuShortDefinitionSource};
+
+/// A definition column a short definition can be taken from.
+/// SQL is only ever built from `column()`, never from user input
+pub trait DefinitionSource: Copy + PartialEq + 'static {
+    /// First non-NULL wins, unless the user reordered them
+    const DEFAULT_ORDER: &'static [Self];
+    fn column(self) -> &'static str;
+    fn id(self) -> i32;
+}
+
+impl DefinitionSource for KmShortDefinitionSource {
+    const DEFAULT_ORDER: &'static [Self] = &[
+        KmShortDefinitionSource::FromCsvRawHtml,
+        KmShortDefinitionSource::EnKmCom,
+        KmShortDefinitionSource::Desc,
+        KmShortDefinitionSource::FromChuonNathTranslated,
+        KmShortDefinitionSource::Wiktionary,
+        KmShortDefinitionSource::FromRussianWiki,
+        KmShortDefinitionSource::Gorgoniev,
+    ];
+
+    fn column(self) -> &'static str {
+        match self {
+            KmShortDefinitionSource::FromCsvRawHtml => "from_csv_rawHtml",
+            KmShortDefinitionSource::EnKmCom => "en_km_com",
+            KmShortDefinitionSource::Desc => "Desc",
+            KmShortDefinitionSource::FromChuonNathTranslated => "from_chuon_nath_translated",
+            KmShortDefinitionSource::Wiktionary => "Wiktionary",
+            KmShortDefinitionSource::FromRussianWiki => "from_russian_wiki",
+            KmShortDefinitionSource::Gorgoniev => "gorgoniev",
+        }
+    }
+
+    fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+impl DefinitionSource for EnShortDefinitionSource {
+    const DEFAULT_ORDER: &'static [Self] = &[
+        EnShortDefinitionSource::Desc,
+        EnShortDefinitionSource::EnKmCom,
+        EnShortDefinitionSource::DescEnOnly,
+    ];
+
+    fn column(self) -> &'static str {
+        match self {
+            EnShortDefinitionSource::Desc => "Desc",
+            EnShortDefinitionSource::EnKmCom => "en_km_com",
+            EnShortDefinitionSource::DescEnOnly => "Desc_en_only",
+        }
+    }
+
+    fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+impl DefinitionSource for RuShortDefinitionSource {
+    const DEFAULT_ORDER: &'static [Self] = &[RuShortDefinitionSource::Desc];
+
+    fn column(self) -> &'static str {
+        "Desc"
+    }
+
+    fn id(self) -> i32 {
+        self as i32
+    }
+}
+
+/// Per-user choice of definition sources, comes from the frontend settings
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "S: Deserialize<'de>"))]
+pub struct SourcePreferences<S> {
+    // preferred first; sources not listed keep their default order after these
+    #[serde(default)]
+    pub order: Vec<S>,
+    // never used for the short definition, and left out of word details
+    #[serde(default)]
+    pub excluded: Vec<S>,
+}
+
+impl<S: DefinitionSource> SourcePreferences<S> {
+    pub fn is_excluded(&self, source: S) -> bool {
+        self.excluded.contains(&source)
+    }
+}
+
+pub fn effective_order<S: DefinitionSource>(prefs: Option<&SourcePreferences<S>>) -> Vec<S> {
+    let preferred = prefs.map(|p| p.order.as_slice()).unwrap_or_default();
+    let mut order: Vec<S> = Vec::with_capacity(S::DEFAULT_ORDER.len());
+    for &source in preferred.iter().chain(S::DEFAULT_ORDER) {
+        let excluded = prefs.is_some_and(|p| p.is_excluded(source));
+        if !excluded && !order.contains(&source) {
+            order.push(source);
+        }
+    }
+    order
+}
+
+/// `definition` and `source` select expressions for the short definition, in the given priority
+pub struct ShortDefinitionSql {
+    pub definition: String,
+    pub source: String,
+}
+
+pub fn short_definition_sql<S: DefinitionSource>(prefs: Option<&SourcePreferences<S>>) -> ShortDefinitionSql {
+    let order = effective_order(prefs);
+
+    // COALESCE needs at least two arguments
+    let definition = match order.as_slice() {
+        [] => "NULL".to_string(),
+        [only] => only.column().to_string(),
+        _ => format!("COALESCE({})", order.iter().map(|s| s.column()).collect::<Vec<_>>().join(", ")),
+    };
+
+    // NULL when no allowed source has a definition
+    let source = if order.is_empty() {
+        "NULL".to_string()
+    } else {
+        let whens: String = order
+            .iter()
+            .map(|s| format!(" WHEN {} IS NOT NULL THEN {}", s.column(), s.id()))
+            .collect();
+        format!("(CASE{} END)", whens)
+    };
+
+    ShortDefinitionSql { definition, source }
+}
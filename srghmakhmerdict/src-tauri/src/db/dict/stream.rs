@@ -0,0 +1,100 @@
+use futures_util::TryStreamExt;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::hash::Hash;
+use tauri::ipc::Channel;
+
+/// Rows per `Batch` message; small enough for the webview to render one without jank
+pub const STREAM_BATCH_SIZE: usize = 500;
+
+/// Message pushed over a streaming command's channel
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum StreamEvent<T> {
+    Batch(Vec<T>),
+    // always the last message of a stream that didn't fail
+    End { total: usize },
+}
+
+/// One requested word of a full details stream, `detail` is None if the word was not found
+#[derive(Serialize, Debug, Clone)]
+pub struct StreamedDetail<T> {
+    pub word: String,
+    pub detail: Option<T>,
+}
+
+/// Buffers items and sends them as `Batch` messages of `STREAM_BATCH_SIZE`
+pub struct BatchSender<'a, T: Serialize> {
+    channel: &'a Channel<StreamEvent<T>>,
+    batch: Vec<T>,
+    total: usize,
+}
+
+impl<'a, T: Serialize> BatchSender<'a, T> {
+    pub fn new(channel: &'a Channel<StreamEvent<T>>) -> Self {
+        Self { channel, batch: Vec::with_capacity(STREAM_BATCH_SIZE), total: 0 }
+    }
+
+    pub fn push(&mut self, item: T) -> Result<(), String> {
+        self.batch.push(item);
+        if self.batch.len() >= STREAM_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), String> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(STREAM_BATCH_SIZE));
+        self.total += batch.len();
+        self.channel.send(StreamEvent::Batch(batch)).map_err(|e| e.to_string())
+    }
+
+    /// Sends the remaining items and the `End` message
+    pub fn finish(mut self) -> Result<(), String> {
+        self.flush()?;
+        self.channel.send(StreamEvent::End { total: self.total }).map_err(|e| e.to_string())
+    }
+}
+
+impl<T: Serialize> BatchSender<'_, StreamedDetail<T>> {
+    /// Sends the requested `words` missing from `found` as not found, last and once each, then finishes
+    pub fn finish_details<K>(mut self, words: &[String], found: &HashSet<K>) -> Result<(), String>
+    where
+        K: Borrow<str> + Eq + Hash,
+    {
+        let mut missing: HashSet<&str> = HashSet::new();
+        for word in words {
+            if !found.contains(word.as_str()) && missing.insert(word) {
+                self.push(StreamedDetail { word: word.clone(), detail: None })?;
+            }
+        }
+        self.finish()
+    }
+}
+
+/// Runs `sql` and hands each row to `on_row` as SQLite yields it, without collecting them
+pub async fn for_each_row<T>(
+    pool: &SqlitePool,
+    sql: &str,
+    binds: &[String],
+    mut on_row: impl FnMut(T) -> Result<(), String>,
+) -> Result<(), String>
+where
+    for<'r> T: sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
+{
+    let mut query = sqlx::query_as::<sqlx::Sqlite, T>(sql);
+    for bind in binds {
+        query = query.bind(bind);
+    }
+
+    let mut rows = query.fetch(pool);
+    while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
+        on_row(row)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use crate::app_state::AppState;
+use crate::edit_distance::{osa_distance, weighted_osa_distance};
+use crate::english::keyboard::are_adjacent_keys;
+use crate::english::metaphone::metaphone;
+use crate::khmer::normalize::normalize_khmer;
+use crate::khmer::units::split_edit_units;
+use crate::russian::morph::fold_ru;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{State, command};
+
+const DEFAULT_SUGGESTIONS_LIMIT: usize = 10;
+
+// Typing the neighbour key is a likelier typo than an arbitrary letter
+const ADJACENT_KEY_COST: f64 = 0.5;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SuggestionKind {
+    EditDistance,
+    // en only: same Metaphone key, used when nothing is close enough by spelling
+    Phonetic,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WordSuggestion {
+    pub word: String,
+    pub distance: f64,
+    pub kind: SuggestionKind,
+}
+
+pub(super) fn max_distance(units: usize) -> f64 {
+    match units {
+        0..=4 => 1.0,
+        5..=8 => 2.0,
+        _ => 3.0,
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CandidateRow {
+    #[sqlx(rename = "Word")]
+    word: String,
+    key: String,
+}
+
+/// Headwords with the form they are compared by (`key_column`), whose key length (in chars) is within `slack` of
+/// `len`: the distance can't be smaller than the length difference
+async fn fetch_candidates(
+    pool: &SqlitePool,
+    table: &str,
+    key_column: &str,
+    filter: Option<&str>,
+    len: usize,
+    slack: usize,
+) -> Result<Vec<CandidateRow>, String> {
+    let sql = format!(
+        "SELECT Word, {key} AS key FROM {} WHERE ({}) AND LENGTH({key}) BETWEEN ? AND ?",
+        table,
+        filter.unwrap_or("1"),
+        key = key_column
+    );
+    sqlx::query_as::<_, CandidateRow>(&sql)
+        .bind(len.saturating_sub(slack) as i64)
+        .bind((len + slack) as i64)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn rank(mut suggestions: Vec<WordSuggestion>, limit: usize) -> Vec<WordSuggestion> {
+    suggestions.sort_by(|a, b| {
+        a.distance
+            .total_cmp(&b.distance)
+            .then_with(|| a.word.chars().count().cmp(&b.word.chars().count()))
+            .then_with(|| a.word.cmp(&b.word))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+fn closest_by<U: PartialEq>(
+    candidates: &[CandidateRow],
+    word: &str,
+    max: f64,
+    units: impl Fn(&str) -> Vec<U>,
+    distance: impl Fn(&[U], &[U]) -> f64,
+) -> Vec<WordSuggestion> {
+    let target = units(word);
+    candidates
+        .iter()
+        .filter(|candidate| candidate.key != word)
+        .filter_map(|candidate| {
+            let d = distance(&target, &units(&candidate.key));
+            (d <= max).then(|| WordSuggestion {
+                word: candidate.word.clone(),
+                distance: d,
+                kind: SuggestionKind::EditDistance,
+            })
+        })
+        .collect()
+}
+
+// Comparing every candidate is CPU bound, keep it off the async workers
+async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn get_word_suggestions_km(
+    state: State<'_, AppState>,
+    word: String,
+    limit: Option<usize>,
+) -> Result<Vec<WordSuggestion>, String> {
+    let word = normalize_khmer(&word);
+    let units = split_edit_units(&word).len();
+    let max = max_distance(units);
+
+    let pool = state.get_pool().await?;
+    // stored spellings are compared normalized, like the input. One unit is at most 2 chars (coeng + consonant)
+    let candidates =
+        fetch_candidates(&pool, "km_Dict_norm", "norm", None, word.chars().count(), 2 * max as usize).await?;
+
+    run_blocking(move || {
+        let suggestions = closest_by(
+            &candidates,
+            &word,
+            max,
+            |w| split_edit_units(w).into_iter().map(str::to_string).collect(),
+            osa_distance,
+        );
+        rank(suggestions, limit.unwrap_or(DEFAULT_SUGGESTIONS_LIMIT))
+    })
+    .await
+}
+
+#[command]
+pub async fn get_word_suggestions_en(
+    state: State<'_, AppState>,
+    word: String,
+    limit: Option<usize>,
+) -> Result<Vec<WordSuggestion>, String> {
+    let word = word.trim().to_lowercase();
+    let len = word.chars().count();
+    let max = max_distance(len);
+    let limit = limit.unwrap_or(DEFAULT_SUGGESTIONS_LIMIT);
+
+    let pool = state.get_pool().await?;
+    let candidates = fetch_candidates(
+        &pool,
+        "en_Dict",
+        "Word",
+        Some("Desc IS NOT NULL OR en_km_com IS NOT NULL"),
+        len,
+        3,
+    )
+    .await?;
+
+    run_blocking(move || {
+        let suggestions = closest_by(
+            &candidates,
+            &word,
+            max,
+            |w| w.to_lowercase().chars().collect::<Vec<char>>(),
+            |a, b| {
+                weighted_osa_distance(a, b, |x, y| if are_adjacent_keys(*x, *y) { ADJACENT_KEY_COST } else { 1.0 })
+            },
+        );
+
+        if !suggestions.is_empty() {
+            return rank(suggestions, limit);
+        }
+
+        // nothing close by spelling, try by sound ("fonetik" -> "phonetic")
+        let key = metaphone(&word);
+        if key.is_empty() {
+            return vec![];
+        }
+        let word_chars: Vec<char> = word.chars().collect();
+        let phonetic = candidates
+            .into_iter()
+            .filter(|candidate| metaphone(&candidate.word) == key)
+            .map(|candidate| WordSuggestion {
+                distance: osa_distance(&word_chars, &candidate.word.to_lowercase().chars().collect::<Vec<char>>()),
+                word: candidate.word,
+                kind: SuggestionKind::Phonetic,
+            })
+            .collect();
+
+        rank(phonetic, limit)
+    })
+    .await
+}
+
+#[command]
+pub async fn get_word_suggestions_ru(
+    state: State<'_, AppState>,
+    word: String,
+    limit: Option<usize>,
+) -> Result<Vec<WordSuggestion>, String> {
+    let word = word.trim().to_string();
+    let len = word.chars().count();
+    let max = max_distance(len);
+
+    let pool = state.get_pool().await?;
+    let candidates = fetch_candidates(&pool, "ru_Dict", "Word", None, len, max as usize).await?;
+
+    run_blocking(move || {
+        let suggestions = closest_by(
+            &candidates,
+            &word,
+            max,
+            |w| w.chars().map(fold_ru).collect::<Vec<char>>(),
+            osa_distance,
+        );
+        rank(suggestions, limit.unwrap_or(DEFAULT_SUGGESTIONS_LIMIT))
+    })
+    .await
+}
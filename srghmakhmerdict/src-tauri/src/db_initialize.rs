@@ -1,10 +1,13 @@
 use crate::app_state::AppState;
+use crate::db::dict::{fts, km_homophones, km_norm, km_roman, km_sort, ocr_search, plain_text_index, ru_stem};
 use flate2::read::GzDecoder;
-use sqlx::sqlite::SqlitePoolOptions;
+use crate::khmer::collation::{self, KHMER_COLLATION};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::{Row};
 use std::fs;
 use std::io::Cursor;
 use std::path::PathBuf;
+use std::str::FromStr;
 use tauri::path::BaseDirectory;
 use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_fs::FsExt;
@@ -103,6 +106,37 @@ async fn ensure_dict_db(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(dest_path)
 }
 
+/// Khmer headword keys, which listing km_Dict and looking up its words go through. Quick to build
+async fn ensure_headword_tables(pool: &sqlx::SqlitePool) -> Result<(), String> {
+    km_norm::ensure_km_norm_index(pool).await?;
+    km_sort::ensure_km_sort_index(pool).await
+}
+
+/// Search indexes the app computes on top of the shipped DB, built once the dictionary is already usable.
+/// Each that fails is logged and sent as `db-index-error`, the others are still built
+async fn ensure_search_tables(app_handle: &AppHandle, pool: &sqlx::SqlitePool) {
+    let results = [
+        ("plain text", plain_text_index::ensure_plain_text_index(pool).await),
+        ("full text search", fts::ensure_fts_index(pool).await),
+        ("OCR search", ocr_search::ensure_ocr_index(pool).await),
+        ("Khmer romanization", km_roman::ensure_km_roman_index(pool).await),
+        ("Khmer homophones", km_homophones::ensure_km_pron_index(pool).await),
+        ("Russian stems", ru_stem::ensure_ru_stem_index(pool).await),
+    ];
+
+    for (name, result) in results {
+        if let Err(e) = result {
+            report_index_error(app_handle, name, e);
+        }
+    }
+}
+
+fn report_index_error(app_handle: &AppHandle, name: &str, error: String) {
+    let message = format!("Failed to build the {} index: {}", name, error);
+    eprintln!("⚠️ {}", message);
+    let _ = app_handle.emit("db-index-error", message);
+}
+
 pub async fn init_db_process(app_handle: AppHandle) {
     let state = app_handle.state::<AppState>();
     let dest_path_res = ensure_dict_db(&app_handle).await;
@@ -112,20 +146,31 @@ pub async fn init_db_process(app_handle: AppHandle) {
             let db_url = format!("sqlite://{}", path.display());
             println!("🔥 Connecting to Main DB at: {}", db_url);
 
-            let pool_res = SqlitePoolOptions::new()
-                .max_connections(5)
-                .connect(&db_url)
-                .await;
+            // every connection of the pool gets the collation, queries say `COLLATE khmer`
+            let pool_res = match SqliteConnectOptions::from_str(&db_url) {
+                Ok(options) => SqlitePoolOptions::new()
+                    .max_connections(5)
+                    .connect_with(options.collation(KHMER_COLLATION, collation::compare))
+                    .await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
 
             match pool_res {
                 Ok(pool) => {
-                    let mut guard = state.dict_pool.write().await;
-                    *guard = Some(pool);
+                    // without them listing Khmer words and finding ones not stored exactly as typed fail,
+                    // everything else still works
+                    if let Err(e) = ensure_headword_tables(&pool).await {
+                        report_index_error(&app_handle, "Khmer headword", e);
+                    }
+
+                    *state.dict_pool.write().await = Some(pool.clone());
                     println!("✅ Dictionary DB Connected");
                     let _ = app_handle.emit("db-initialized", ());
+
+                    ensure_search_tables(&app_handle, &pool).await;
                 }
-                Err(e) => {
-                    let err_msg = e.to_string();
+                Err(err_msg) => {
                     eprintln!("❌ DB Connection Error: {}", err_msg);
                     let mut error_guard = state.init_error.write().await;
                     *error_guard = Some(err_msg.clone());
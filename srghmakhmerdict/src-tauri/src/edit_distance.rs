@@ -0,0 +1,79 @@
+/// Optimal string alignment distance (Levenshtein + adjacent transpositions) with a custom substitution cost.
+/// Insert, delete and transpose cost 1.
+pub fn weighted_osa_distance<T: PartialEq>(a: &[T], b: &[T], substitution_cost: impl Fn(&T, &T) -> f64) -> f64 {
+    let (n, m) = (a.len(), b.len());
+    if n == 0 {
+        return m as f64;
+    }
+    if m == 0 {
+        return n as f64;
+    }
+
+    // three rolling rows: i-2, i-1, i
+    let mut prev_prev = vec![0.0; m + 1];
+    let mut prev: Vec<f64> = (0..=m).map(|j| j as f64).collect();
+    let mut cur = vec![0.0; m + 1];
+
+    for i in 1..=n {
+        cur[0] = i as f64;
+        for j in 1..=m {
+            let sub = if a[i - 1] == b[j - 1] { 0.0 } else { substitution_cost(&a[i - 1], &b[j - 1]) };
+            let mut best = (prev[j] + 1.0).min(cur[j - 1] + 1.0).min(prev[j - 1] + sub);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(prev_prev[j - 2] + 1.0);
+            }
+            cur[j] = best;
+        }
+        std::mem::swap(&mut prev_prev, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    prev[m]
+}
+
+pub fn osa_distance<T: PartialEq>(a: &[T], b: &[T]) -> f64 {
+    weighted_osa_distance(a, b, |_, _| 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn osa(a: &str, b: &str) -> f64 {
+        osa_distance(&chars(a), &chars(b))
+    }
+
+    #[test]
+    fn insert_delete_substitute() {
+        assert_eq!(osa("", ""), 0.0);
+        assert_eq!(osa("", "abc"), 3.0);
+        assert_eq!(osa("abc", ""), 3.0);
+        assert_eq!(osa("cat", "cat"), 0.0);
+        assert_eq!(osa("cat", "cats"), 1.0);
+        assert_eq!(osa("cat", "at"), 1.0);
+        assert_eq!(osa("cat", "cut"), 1.0);
+        assert_eq!(osa("kitten", "sitting"), 3.0);
+    }
+
+    #[test]
+    fn adjacent_transposition_is_one_edit() {
+        assert_eq!(osa("ca", "ac"), 1.0);
+        assert_eq!(osa("recieve", "receive"), 1.0);
+        assert_eq!(osa("ab", "ba"), osa("ba", "ab"));
+        // a transposed pair can't be edited again: ca -> ac -> abc is not allowed, unlike Damerau-Levenshtein
+        assert_eq!(osa("ca", "abc"), 3.0);
+    }
+
+    #[test]
+    fn substitution_cost_is_custom() {
+        let half = |a: &char, b: &char| if a.eq_ignore_ascii_case(b) { 0.5 } else { 1.0 };
+        assert_eq!(weighted_osa_distance(&chars("Cat"), &chars("cat"), half), 0.5);
+        assert_eq!(weighted_osa_distance(&chars("Cat"), &chars("cut"), half), 1.5);
+        // insert + delete is cheaper than an expensive substitution
+        assert_eq!(weighted_osa_distance(&chars("a"), &chars("b"), |_, _| 5.0), 2.0);
+    }
+}
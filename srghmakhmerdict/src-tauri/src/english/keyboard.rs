@@ -0,0 +1,43 @@
+const QWERTY_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+fn position(c: char) -> Option<(usize, usize)> {
+    let c = c.to_ascii_lowercase();
+    QWERTY_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, keys)| keys.find(c).map(|col| (row, col)))
+}
+
+/// Keys touching each other on a QWERTY layout (rows are staggered: a row's key c touches c and c+1 of the row above)
+pub fn are_adjacent_keys(a: char, b: char) -> bool {
+    let (Some((ra, ca)), Some((rb, cb))) = (position(a), position(b)) else {
+        return false;
+    };
+    match (ra as isize - rb as isize, ca as isize - cb as isize) {
+        (0, d) => d.abs() == 1,
+        // a is one row below b
+        (1, d) => d == 0 || d == -1,
+        (-1, d) => d == 0 || d == 1,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::are_adjacent_keys;
+
+    #[test]
+    fn neighbours_in_a_row_and_across_rows() {
+        let adjacent = [
+            ('a', 's'), ('s', 'a'), ('q', 'w'), ('a', 'q'), ('a', 'w'), ('q', 'a'), ('z', 'a'), ('z', 's'), ('m', 'k'),
+            ('m', 'j'), ('P', 'o'),
+        ];
+        for (a, b) in adjacent {
+            assert!(are_adjacent_keys(a, b), "{} {}", a, b);
+        }
+        let apart = [('a', 'a'), ('a', 'd'), ('s', 'q'), ('q', 'z'), ('p', 'a'), ('z', 'q'), ('m', 'h'), ('1', 'q'), ('a', '-')];
+        for (a, b) in apart {
+            assert!(!are_adjacent_keys(a, b), "{} {}", a, b);
+        }
+    }
+}
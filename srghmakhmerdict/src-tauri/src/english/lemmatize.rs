@@ -0,0 +1,336 @@
+// Rule-based English lemmatizer for forms en_Extension doesn't list.
+// It only proposes candidates, most likely first; the caller keeps the first one that is a headword,
+// so a rule producing a non-word (boxes -> boxe) is harmless.
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum LemmaStep {
+    // Running -> running
+    CaseFolded,
+    // colour -> color, center -> centre
+    SpellingVariant,
+    // went -> go, mice -> mouse
+    Irregular,
+    // running -> run is Suffix("-ing")
+    Suffix(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LemmaCandidate {
+    pub lemma: String,
+    pub steps: Vec<LemmaStep>,
+}
+
+// (suffix, replacement, reported as); a doubled final consonant is undone separately (running -> run)
+const SUFFIX_RULES: &[(&str, &str, &str)] = &[
+    ("'s", "", "-'s"),
+    ("s'", "s", "-'s"),
+    ("s", "", "-s"),
+    ("es", "", "-es"),
+    ("ies", "y", "-s"),
+    ("ves", "f", "-s"),
+    ("ves", "fe", "-s"),
+    ("ing", "", "-ing"),
+    ("ing", "e", "-ing"),
+    ("ying", "ie", "-ing"),
+    ("ed", "", "-ed"),
+    ("d", "", "-ed"),
+    ("ied", "y", "-ed"),
+    ("er", "", "-er"),
+    ("r", "", "-er"),
+    ("ier", "y", "-er"),
+    ("est", "", "-est"),
+    ("st", "", "-est"),
+    ("iest", "y", "-est"),
+    ("ly", "", "-ly"),
+    ("ily", "y", "-ly"),
+    ("ly", "le", "-ly"),
+    ("ally", "", "-ly"),
+];
+
+// Suffixes after which a doubled consonant is undone: stopped -> stopp -> stop
+const DOUBLING_SUFFIXES: &[&str] = &["ing", "ed", "er", "est"];
+
+const IRREGULAR_FORMS: &[(&str, &str)] = &[
+    // verbs
+    ("am", "be"), ("is", "be"), ("are", "be"), ("was", "be"), ("were", "be"), ("been", "be"), ("being", "be"),
+    ("has", "have"), ("had", "have"), ("does", "do"), ("did", "do"), ("done", "do"),
+    ("went", "go"), ("gone", "go"), ("goes", "go"),
+    ("ate", "eat"), ("eaten", "eat"), ("began", "begin"), ("begun", "begin"),
+    ("broke", "break"), ("broken", "break"), ("brought", "bring"), ("built", "build"),
+    ("bought", "buy"), ("caught", "catch"), ("chose", "choose"), ("chosen", "choose"),
+    ("came", "come"), ("dealt", "deal"), ("drew", "draw"), ("drawn", "draw"),
+    ("drank", "drink"), ("drunk", "drink"), ("drove", "drive"), ("driven", "drive"),
+    ("fell", "fall"), ("fallen", "fall"), ("felt", "feel"), ("fought", "fight"),
+    ("found", "find"), ("flew", "fly"), ("flown", "fly"), ("forgot", "forget"), ("forgotten", "forget"),
+    ("froze", "freeze"), ("frozen", "freeze"), ("got", "get"), ("gotten", "get"),
+    ("gave", "give"), ("given", "give"), ("grew", "grow"), ("grown", "grow"),
+    ("hung", "hang"), ("heard", "hear"), ("hid", "hide"), ("hidden", "hide"), ("held", "hold"),
+    ("kept", "keep"), ("knew", "know"), ("known", "know"), ("laid", "lay"), ("led", "lead"),
+    ("left", "leave"), ("lent", "lend"), ("lay", "lie"), ("lain", "lie"), ("lost", "lose"),
+    ("made", "make"), ("meant", "mean"), ("met", "meet"), ("paid", "pay"),
+    ("ran", "run"), ("rode", "ride"), ("ridden", "ride"), ("rang", "ring"), ("rung", "ring"),
+    ("rose", "rise"), ("risen", "rise"), ("said", "say"), ("saw", "see"), ("seen", "see"),
+    ("sold", "sell"), ("sent", "send"), ("shook", "shake"), ("shaken", "shake"),
+    ("shot", "shoot"), ("showed", "show"), ("shown", "show"), ("sang", "sing"), ("sung", "sing"),
+    ("sank", "sink"), ("sunk", "sink"), ("sat", "sit"), ("slept", "sleep"), ("spoke", "speak"),
+    ("spoken", "speak"), ("spent", "spend"), ("stood", "stand"), ("stole", "steal"), ("stolen", "steal"),
+    ("struck", "strike"), ("swam", "swim"), ("swum", "swim"), ("took", "take"), ("taken", "take"),
+    ("taught", "teach"), ("tore", "tear"), ("torn", "tear"), ("told", "tell"), ("thought", "think"),
+    ("threw", "throw"), ("thrown", "throw"), ("understood", "understand"), ("woke", "wake"),
+    ("woken", "wake"), ("wore", "wear"), ("worn", "wear"), ("won", "win"), ("wrote", "write"),
+    ("written", "write"), ("fled", "flee"), ("fed", "feed"), ("bled", "bleed"), ("bit", "bite"),
+    ("bitten", "bite"), ("blew", "blow"), ("blown", "blow"), ("bent", "bend"), ("bound", "bind"),
+    ("dug", "dig"), ("dove", "dive"), ("forbade", "forbid"), ("forgave", "forgive"),
+    ("forgiven", "forgive"), ("ground", "grind"), ("knelt", "kneel"), ("leapt", "leap"),
+    ("learnt", "learn"), ("lit", "light"), ("sought", "seek"), ("shone", "shine"), ("shrank", "shrink"),
+    ("slid", "slide"), ("sped", "speed"), ("spun", "spin"), ("sprang", "spring"), ("sprung", "spring"),
+    ("stuck", "stick"), ("stung", "sting"), ("stank", "stink"), ("strove", "strive"), ("swore", "swear"),
+    ("sworn", "swear"), ("swept", "sweep"), ("swung", "swing"), ("wept", "weep"), ("wound", "wind"),
+    ("withdrew", "withdraw"), ("withdrawn", "withdraw"), ("dreamt", "dream"), ("burnt", "burn"),
+    ("spelt", "spell"), ("spilt", "spill"), ("smelt", "smell"),
+    // nouns
+    ("men", "man"), ("women", "woman"), ("children", "child"), ("people", "person"),
+    ("feet", "foot"), ("teeth", "tooth"), ("geese", "goose"), ("mice", "mouse"), ("lice", "louse"),
+    ("oxen", "ox"), ("dice", "die"), ("pence", "penny"), ("data", "datum"), ("criteria", "criterion"),
+    ("phenomena", "phenomenon"), ("analyses", "analysis"), ("crises", "crisis"), ("theses", "thesis"),
+    ("diagnoses", "diagnosis"), ("hypotheses", "hypothesis"), ("cacti", "cactus"), ("fungi", "fungus"),
+    ("nuclei", "nucleus"), ("stimuli", "stimulus"), ("alumni", "alumnus"), ("larvae", "larva"),
+    ("indices", "index"), ("matrices", "matrix"), ("appendices", "appendix"), ("media", "medium"),
+    ("bacteria", "bacterium"), ("curricula", "curriculum"),
+    // adjectives and adverbs
+    ("better", "good"), ("best", "good"), ("worse", "bad"), ("worst", "bad"),
+    ("more", "much"), ("most", "much"), ("less", "little"), ("least", "little"),
+    ("further", "far"), ("furthest", "far"), ("farther", "far"), ("farthest", "far"),
+    ("elder", "old"), ("eldest", "old"),
+    // pronouns
+    ("me", "i"), ("us", "we"), ("him", "he"), ("them", "they"),
+];
+
+// British / American pairs no suffix rule covers
+const SPELLING_PAIRS: &[(&str, &str)] = &[
+    ("grey", "gray"), ("tyre", "tire"), ("programme", "program"), ("aluminium", "aluminum"),
+    ("plough", "plow"), ("mould", "mold"), ("sceptic", "skeptic"), ("cheque", "check"),
+    ("kerb", "curb"), ("pyjamas", "pajamas"), ("cosy", "cozy"), ("draught", "draft"),
+    ("jewellery", "jewelry"), ("storey", "story"), ("manoeuvre", "maneuver"), ("moustache", "mustache"),
+    ("doughnut", "donut"), ("gaol", "jail"), ("whisky", "whiskey"), ("catalogue", "catalog"),
+];
+
+// (British, American) endings, tried in both directions
+const SPELLING_ENDINGS: &[(&str, &str)] = &[
+    ("our", "or"),
+    ("ise", "ize"),
+    ("ised", "ized"),
+    ("ising", "izing"),
+    ("isation", "ization"),
+    ("yse", "yze"),
+    ("tre", "ter"),
+    ("bre", "ber"),
+    ("ogue", "og"),
+    ("ence", "ense"),
+];
+
+/// British <-> American spellings of a word
+pub fn spelling_variants(word: &str) -> Vec<String> {
+    let mut variants = Vec::new();
+
+    for &(british, american) in SPELLING_PAIRS {
+        if word == british {
+            variants.push(american.to_string());
+        } else if word == american {
+            variants.push(british.to_string());
+        }
+    }
+
+    for &(british, american) in SPELLING_ENDINGS {
+        if let Some(stem) = word.strip_suffix(british).filter(|s| s.len() >= 2) {
+            variants.push(format!("{}{}", stem, american));
+        }
+        if let Some(stem) = word.strip_suffix(american).filter(|s| s.len() >= 2) {
+            variants.push(format!("{}{}", stem, british));
+        }
+    }
+
+    // anaemia -> anemia, oestrogen -> estrogen
+    for digraph in ["ae", "oe"] {
+        if word.len() > 4 && word.contains(digraph) {
+            variants.push(word.replacen(digraph, "e", 1));
+        }
+    }
+
+    variants
+}
+
+fn is_consonant(c: char) -> bool {
+    c.is_ascii_alphabetic() && !matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+// One vowel then one consonant at the end (hop, car, us): the stem of a form that dropped a silent e
+// more often than a base, since a base like that doubles its consonant (hoping, but hopping)
+fn ends_in_short_syllable(stem: &str) -> bool {
+    let mut tail = stem.chars().rev();
+    match (tail.next(), tail.next(), tail.next()) {
+        (Some(last), Some(vowel), before) => {
+            is_consonant(last) && !matches!(last, 'w' | 'x' | 'y') && is_vowel(vowel) && !before.is_some_and(is_vowel)
+        }
+        _ => false,
+    }
+}
+
+/// Base forms of an inflected word with the suffix each undoes, most likely first
+fn strip_suffixes(word: &str) -> Vec<(String, &'static str)> {
+    let mut stems = Vec::new();
+
+    for &(suffix, replacement, rule) in SUFFIX_RULES {
+        let Some(stem) = word.strip_suffix(suffix).filter(|s| s.chars().count() >= 2) else { continue };
+        stems.push((format!("{}{}", stem, replacement), rule));
+
+        // running -> runn -> run, but not for -ss (missing -> miss)
+        let mut tail = stem.chars().rev();
+        if replacement.is_empty() && DOUBLING_SUFFIXES.contains(&suffix)
+            && let (Some(last), Some(before)) = (tail.next(), tail.next())
+            && last == before && is_consonant(last) && last != 's'
+        {
+            stems.push((stem[..stem.len() - last.len_utf8()].to_string(), rule));
+        }
+    }
+
+    // hoped -> hope before hop, both come from the same rule
+    let mut i = 0;
+    while i < stems.len() {
+        let (stem, rule) = &stems[i];
+        let with_e = ends_in_short_syllable(stem)
+            .then(|| stems[i + 1..].iter().position(|(s, r)| r == rule && s.strip_suffix('e') == Some(stem)))
+            .flatten();
+        if let Some(j) = with_e {
+            let restored = stems.remove(i + 1 + j);
+            stems.insert(i, restored);
+            i += 1;
+        }
+        i += 1;
+    }
+
+    stems
+}
+
+fn irregular_lemma(word: &str) -> Option<&'static str> {
+    IRREGULAR_FORMS.iter().find(|(form, _)| *form == word).map(|(_, lemma)| *lemma)
+}
+
+/// Possible headwords for a form that is not a headword itself, most likely first:
+/// case folding, spelling variant, irregular form, then suffix rules (each also with spelling variants)
+pub fn lemma_candidates(word: &str) -> Vec<LemmaCandidate> {
+    let word = word.trim();
+    if word.is_empty() {
+        return vec![];
+    }
+
+    let mut bases = vec![(word.to_string(), vec![])];
+    let lower = word.to_lowercase();
+    if lower != word {
+        bases.push((lower, vec![LemmaStep::CaseFolded]));
+    }
+
+    let mut candidates: Vec<LemmaCandidate> = Vec::new();
+    let mut push = |lemma: String, steps: Vec<LemmaStep>| {
+        if lemma != word && !candidates.iter().any(|c| c.lemma == lemma) {
+            candidates.push(LemmaCandidate { lemma, steps });
+        }
+    };
+    let with = |steps: &[LemmaStep], step: LemmaStep| -> Vec<LemmaStep> {
+        steps.iter().copied().chain([step]).collect()
+    };
+
+    for (base, steps) in &bases {
+        push(base.clone(), steps.clone());
+    }
+    for (base, steps) in &bases {
+        for variant in spelling_variants(base) {
+            push(variant, with(steps, LemmaStep::SpellingVariant));
+        }
+    }
+    for (base, steps) in &bases {
+        if let Some(lemma) = irregular_lemma(base) {
+            push(lemma.to_string(), with(steps, LemmaStep::Irregular));
+        }
+    }
+    for (base, steps) in &bases {
+        for (stem, rule) in strip_suffixes(base) {
+            let steps = with(steps, LemmaStep::Suffix(rule));
+            let variants = spelling_variants(&stem);
+            push(stem, steps.clone());
+            // colours -> colour -> color
+            for variant in variants {
+                push(variant, with(&steps, LemmaStep::SpellingVariant));
+            }
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lemmas(word: &str) -> Vec<String> {
+        lemma_candidates(word).into_iter().map(|c| c.lemma).collect()
+    }
+
+    // as resolve_en_lemma does: the first candidate that is a headword
+    fn resolve(word: &str, headwords: &[&str]) -> Option<String> {
+        lemmas(word).into_iter().find(|l| headwords.contains(&l.as_str()))
+    }
+
+    #[test]
+    fn silent_e_is_restored_before_the_bare_stem() {
+        let headwords = [
+            "hop", "hope", "car", "care", "not", "note", "us", "use", "make", "run", "stop", "visit", "box",
+        ];
+        let cases = [
+            ("hoping", "hope"),
+            ("hoped", "hope"),
+            ("cared", "care"),
+            ("noted", "note"),
+            ("used", "use"),
+            ("making", "make"),
+            ("hopping", "hop"),
+            ("hopped", "hop"),
+            ("visited", "visit"),
+            ("boxing", "box"),
+        ];
+        for (word, lemma) in cases {
+            assert_eq!(resolve(word, &headwords).as_deref(), Some(lemma), "word: {}", word);
+        }
+        assert_eq!(lemmas("hoping")[..2], ["hope", "hop"]);
+        assert_eq!(lemmas("making")[..2], ["make", "mak"]);
+    }
+
+    #[test]
+    fn doubled_consonant_is_undone() {
+        let headwords = ["run", "stop", "big", "miss"];
+        assert_eq!(resolve("running", &headwords).as_deref(), Some("run"));
+        assert_eq!(resolve("stopped", &headwords).as_deref(), Some("stop"));
+        assert_eq!(resolve("biggest", &headwords).as_deref(), Some("big"));
+        // -ss stays
+        assert_eq!(resolve("missing", &headwords).as_deref(), Some("miss"));
+        assert!(!lemmas("missing").contains(&"mis".to_string()));
+    }
+
+    #[test]
+    fn case_spelling_and_irregular_forms_come_first() {
+        let folded = LemmaCandidate { lemma: "running".into(), steps: vec![LemmaStep::CaseFolded] };
+        assert_eq!(lemma_candidates("Running")[0], folded);
+        assert_eq!(resolve("colours", &["colour", "color"]).as_deref(), Some("colour"));
+        assert_eq!(resolve("colours", &["color"]).as_deref(), Some("color"));
+        assert_eq!(resolve("went", &["go", "wen"]).as_deref(), Some("go"));
+        assert_eq!(resolve("studies", &["study"]).as_deref(), Some("study"));
+        assert_eq!(resolve("knives", &["knife"]).as_deref(), Some("knife"));
+        assert!(lemmas("").is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+// Original Metaphone (Lawrence Philips, 1990), '0' stands for "th"
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'A' | 'E' | 'I' | 'O' | 'U')
+}
+
+fn is_front_vowel(c: Option<char>) -> bool {
+    matches!(c, Some('E' | 'I' | 'Y'))
+}
+
+pub fn metaphone(word: &str) -> String {
+    let mut w: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    // initial letter exceptions
+    match (w.first(), w.get(1)) {
+        (Some('A'), Some('E')) | (Some('G' | 'K' | 'P'), Some('N')) | (Some('W'), Some('R')) => {
+            w.remove(0);
+        }
+        (Some('X'), _) => w[0] = 'S',
+        (Some('W'), Some('H')) => {
+            w.remove(1);
+        }
+        _ => {}
+    }
+
+    let at = |i: isize| -> Option<char> {
+        if i < 0 {
+            None
+        } else {
+            w.get(i as usize).copied()
+        }
+    };
+
+    let mut out = String::new();
+
+    for (idx, &c) in w.iter().enumerate() {
+        let i = idx as isize;
+        let prev = at(i - 1);
+        let next = at(i + 1);
+        let next2 = at(i + 2);
+
+        // double letters make one sound, except CC (accident)
+        if prev == Some(c) && c != 'C' {
+            continue;
+        }
+
+        match c {
+            'A' | 'E' | 'I' | 'O' | 'U' => {
+                if idx == 0 {
+                    out.push(c);
+                }
+            }
+            'B' => {
+                // dumb, lamb
+                if !(prev == Some('M') && next.is_none()) {
+                    out.push('B');
+                }
+            }
+            'C' => {
+                if next == Some('I') && next2 == Some('A') {
+                    out.push('X');
+                } else if next == Some('H') {
+                    out.push(if prev == Some('S') { 'K' } else { 'X' });
+                } else if is_front_vowel(next) {
+                    // science
+                    if prev != Some('S') {
+                        out.push('S');
+                    }
+                } else {
+                    out.push('K');
+                }
+            }
+            'D' => {
+                if next == Some('G') && is_front_vowel(next2) {
+                    out.push('J');
+                } else {
+                    out.push('T');
+                }
+            }
+            'G' => {
+                let silent_gh = next == Some('H') && !(next2.is_some_and(is_vowel));
+                let silent_gn = next == Some('N') && (next2.is_none() || (next2 == Some('E') && at(i + 3) == Some('D') && at(i + 4).is_none()));
+                // edge, judge
+                let after_d = prev == Some('D') && is_front_vowel(next);
+                if silent_gh || silent_gn || after_d {
+                    continue;
+                }
+                if is_front_vowel(next) && prev != Some('G') {
+                    out.push('J');
+                } else {
+                    out.push('K');
+                }
+            }
+            'H' => {
+                let after_modifier = matches!(prev, Some('C' | 'S' | 'P' | 'T' | 'G'));
+                let between_vowel_and_consonant = prev.is_some_and(is_vowel) && !next.is_some_and(is_vowel);
+                if !after_modifier && !between_vowel_and_consonant {
+                    out.push('H');
+                }
+            }
+            'K' => {
+                if prev != Some('C') {
+                    out.push('K');
+                }
+            }
+            'P' => out.push(if next == Some('H') { 'F' } else { 'P' }),
+            'Q' => out.push('K'),
+            'S' => {
+                if next == Some('H') || (next == Some('I') && matches!(next2, Some('O' | 'A'))) {
+                    out.push('X');
+                } else {
+                    out.push('S');
+                }
+            }
+            'T' => {
+                if next == Some('I') && matches!(next2, Some('O' | 'A')) {
+                    out.push('X');
+                } else if next == Some('H') {
+                    out.push('0');
+                } else if !(next == Some('C') && next2 == Some('H')) {
+                    out.push('T');
+                }
+            }
+            'V' => out.push('F'),
+            'W' | 'Y' => {
+                if next.is_some_and(is_vowel) {
+                    out.push(c);
+                }
+            }
+            'X' => out.push_str("KS"),
+            'Z' => out.push('S'),
+            _ => out.push(c),
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::metaphone;
+
+    #[test]
+    fn codes() {
+        let cases = [
+            ("thumb", "0M"),
+            ("smith", "SM0"),
+            ("knight", "NT"),
+            ("wright", "RT"),
+            ("xylophone", "SLFN"),
+            ("phone", "FN"),
+            ("science", "SNS"),
+            ("dumb", "TM"),
+            ("edge", "EJ"),
+            ("church", "XRX"),
+            ("accident", "AKSTNT"),
+            ("nation", "NXN"),
+            ("", ""),
+            ("123", ""),
+        ];
+        for (word, code) in cases {
+            assert_eq!(metaphone(word), code, "word: {:?}", word);
+        }
+    }
+
+    #[test]
+    fn sound_alikes_share_a_code() {
+        assert_eq!(metaphone("their"), metaphone("there"));
+        assert_eq!(metaphone("write"), metaphone("right"));
+        assert_eq!(metaphone("filosofy"), metaphone("philosophy"));
+    }
+}
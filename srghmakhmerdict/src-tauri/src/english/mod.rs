@@ -0,0 +1,3 @@
+pub mod keyboard;
+pub mod lemmatize;
+pub mod metaphone;
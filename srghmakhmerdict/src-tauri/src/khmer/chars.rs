@@ -0,0 +1,57 @@
+// Khmer block U+1780..U+17FF, classes as in the Unicode Khmer chapter
+// (same grouping as khmer-consonants-vovels.ts on the frontend)
+
+pub const COENG: char = '\u{17D2}';
+
+pub fn is_khmer(c: char) -> bool {
+    matches!(c, '\u{1780}'..='\u{17FF}' | '\u{19E0}'..='\u{19FF}')
+}
+
+pub fn is_consonant(c: char) -> bool {
+    matches!(c, '\u{1780}'..='\u{17A2}')
+}
+
+pub fn is_independent_vowel(c: char) -> bool {
+    matches!(c, '\u{17A3}'..='\u{17B3}')
+}
+
+/// ឴ ឵ (U+17B4, U+17B5), invisible and deprecated
+pub fn is_inherent_vowel(c: char) -> bool {
+    matches!(c, '\u{17B4}' | '\u{17B5}')
+}
+
+pub fn is_dependent_vowel(c: char) -> bool {
+    matches!(c, '\u{17B6}'..='\u{17C5}')
+}
+
+/// ំ ះ ៈ ៉ ៊ ់ ៌ ៍ ៎ ៏ ័ ៑ ៓ ៝
+pub fn is_sign(c: char) -> bool {
+    matches!(c, '\u{17C6}'..='\u{17D1}' | '\u{17D3}' | '\u{17DD}')
+}
+
+/// ៉ ៊ (muusikatoan, triisap), change the series of the consonant they sit on
+pub fn is_register_shifter(c: char) -> bool {
+    matches!(c, '\u{17C9}' | '\u{17CA}')
+}
+
+/// Anything that attaches to the preceding base: dependent vowels, signs, coeng
+pub fn is_combining(c: char) -> bool {
+    is_inherent_vowel(c) || is_dependent_vowel(c) || is_sign(c) || c == COENG
+}
+
+/// Can start an orthographic syllable
+pub fn is_base(c: char) -> bool {
+    is_consonant(c) || is_independent_vowel(c)
+}
+
+pub fn is_digit(c: char) -> bool {
+    matches!(c, '\u{17E0}'..='\u{17E9}' | '\u{17F0}'..='\u{17F9}')
+}
+
+pub fn is_punctuation(c: char) -> bool {
+    matches!(c, '\u{17D4}'..='\u{17DA}' | '\u{17DC}')
+}
+
+pub fn contains_khmer(s: &str) -> bool {
+    s.chars().any(is_khmer)
+}
@@ -0,0 +1,187 @@
+// Dictionary order of Khmer words (as in Chuon Nath), registered on the pool as the SQLite collation "khmer":
+// syllable by syllable, by base consonant, then subscripts (none first), then vowel in the traditional order.
+// Codepoint order gets the vowels wrong: ំ / ះ combinations land wherever their first sign is (កាំ < កុំ < កំ
+// instead of កុំ < កំ < កាំ), and a shifter or sign typed before the vowel outweighs the vowel
+
+use std::cmp::Ordering;
+use super::chars::{COENG, is_base, is_combining, is_consonant};
+
+/// Name the collation is registered under, `ORDER BY Word COLLATE khmer`
+pub const KHMER_COLLATION: &str = "khmer";
+
+const NIKAHIT: char = '\u{17C6}'; // ំ
+const REAHMUK: char = '\u{17C7}'; // ះ
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+const DOTTED_CIRCLE: char = '\u{25CC}'; // ◌, where marks without a base are written
+
+// A sort key is a sequence of weights, one syllable is
+// base, subscripts..., END_OF_CLUSTER, vowel (one weight, or one per sign if the combination is unknown), signs...
+// The ranges are ordered so that at every position the entry that should come first has the lower weight
+const END_OF_CLUSTER: u32 = 1; // below SUBSCRIPT: ក before ក្ក
+const SIGN: u32 = 0x10; // + offset in the Khmer block; shifters, bantoc, robat, ...
+const VOWEL: u32 = 0x100; // + index in VOWEL_ORDER
+const OTHER: u32 = 0x1000; // + code point; non-Khmer text, digits, punctuation
+const SUBSCRIPT: u32 = 0x20_0000; // + consonant index
+const BASE: u32 = 0x30_0000; // + index: consonants, then independent vowels
+
+// Chuon Nath order; "" is the inherent vowel. Every single sign is here too, so unknown combinations
+// get a weight per sign
+const VOWEL_ORDER: &[&str] = &[
+    "", "ា", "ិ", "ី", "ឹ", "ឺ", "ុ", "ូ", "ួ", "ើ", "ឿ", "ៀ", "េ", "ែ", "ៃ", "ោ", "ៅ", "ុំ", "ំ", "ាំ", "ះ", "ាះ", "ិះ",
+    "ុះ", "េះ", "ោះ",
+];
+
+fn is_vowel_sign(c: char) -> bool {
+    matches!(c, '\u{17B6}'..='\u{17C5}') || c == NIKAHIT || c == REAHMUK
+}
+
+fn base_weight(c: char) -> u32 {
+    if is_consonant(c) { BASE + (c as u32 - 0x1780) } else { BASE + 0x40 + (c as u32 - 0x17A3) }
+}
+
+fn sign_weight(c: char) -> u32 {
+    match c {
+        ZWNJ | ZWJ => SIGN + 0x40 + (c as u32 - ZWNJ as u32),
+        c => SIGN + (c as u32).saturating_sub(0x17B0),
+    }
+}
+
+fn push_vowel(written: &str, key: &mut Vec<u32>) {
+    let index = |v: &str| VOWEL_ORDER.iter().position(|&known| known == v);
+    if let Some(i) = index(written) {
+        key.push(VOWEL + i as u32);
+        return;
+    }
+    for c in written.chars() {
+        key.extend(index(c.encode_utf8(&mut [0; 4])).map(|i| VOWEL + i as u32));
+    }
+}
+
+/// Weights `compare` orders by. Different strings can have equal keys (the same marks typed in another order)
+pub fn sort_key(text: &str) -> Vec<u32> {
+    let mut key = Vec::with_capacity(text.len());
+    let mut vowel = String::new();
+    let mut signs: Vec<u32> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if is_base(c) {
+            key.push(base_weight(c));
+            chars.next();
+        } else if c == DOTTED_CIRCLE || is_combining(c) {
+            // marks without a base sort as if written on ◌, so a lone "ា" is ordered among the vowels
+            key.push(OTHER + DOTTED_CIRCLE as u32);
+            if c == DOTTED_CIRCLE {
+                chars.next();
+            }
+        } else {
+            key.push(OTHER + c as u32);
+            chars.next();
+            continue;
+        }
+
+        vowel.clear();
+        signs.clear();
+        while let Some(&next) = chars.peek() {
+            if next == COENG {
+                let mut ahead = chars.clone();
+                ahead.next();
+                match ahead.next() {
+                    Some(sub) if is_consonant(sub) => {
+                        key.push(SUBSCRIPT + (sub as u32 - 0x1780));
+                        chars.next();
+                    }
+                    _ => signs.push(sign_weight(next)),
+                }
+            } else if is_vowel_sign(next) {
+                vowel.push(next);
+            } else if is_combining(next) || next == ZWJ || next == ZWNJ {
+                signs.push(sign_weight(next));
+            } else {
+                break;
+            }
+            chars.next();
+        }
+
+        key.push(END_OF_CLUSTER);
+        push_vowel(&vowel, &mut key);
+        signs.sort_unstable();
+        key.extend(&signs);
+    }
+
+    key
+}
+
+/// Khmer dictionary order, ties (equal sort keys) broken by code point so that only equal strings compare equal,
+/// as SQLite requires of a collation
+pub fn compare(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+    sort_key(a).cmp(&sort_key(b)).then_with(|| a.cmp(b))
+}
+
+/// `sort_key` as a string that compares the same way with plain `<` (six hex digits per weight), for the frontend
+pub fn sort_key_hex(text: &str) -> String {
+    sort_key(text).iter().map(|w| format!("{:06x}", w)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: &[&str]) -> Vec<String> {
+        let mut words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        words.sort_by(|a, b| compare(a, b));
+        words
+    }
+
+    #[test]
+    fn dictionary_order() {
+        // already in Chuon Nath order
+        let expected = [
+            "ក", "កក", "កង", "ករ", "កា", "ការ", "ការងារ", "កាល", "កិច្ច", "កី", "កុ", "កូន", "កើត", "កែ", "កោ", "កៅ", "កុំ",
+            "កំ", "កាំ", "កះ", "ក្ក", "ក្រ", "ក្រុម", "ខ", "ខ្មែរ", "គោ", "ង", "អ", "អា", "ឥ", "ឧ",
+        ];
+        let mut shuffled: Vec<&str> = expected.to_vec();
+        shuffled.reverse();
+        shuffled.swap(3, 17);
+        assert_eq!(sorted(&shuffled), expected);
+    }
+
+    #[test]
+    fn codepoint_order_differs() {
+        // ា (U+17B6) is below ុ (U+17BB), but ុំ comes before ាំ
+        assert!("កាំ" < "កុំ");
+        assert_eq!(compare("កុំ", "កាំ"), Ordering::Less);
+        // ៉ (U+17C9) is above ុ (U+17BB), but the shifter only counts after the vowel
+        assert!("បុក" < "ប៉ុ");
+        assert_eq!(compare("ប៉ុ", "បុក"), Ordering::Less);
+    }
+
+    #[test]
+    fn non_khmer_first_and_only_equal_strings_are_equal() {
+        assert_eq!(compare("abc", "ក"), Ordering::Less);
+        assert_eq!(compare("១", "ក"), Ordering::Less);
+        assert_eq!(compare("ក", "ក"), Ordering::Equal);
+        // same key, different mark order: still ordered, consistently
+        let (a, b) = ("ក៊ុ", "កុ៊");
+        assert_eq!(sort_key(a), sort_key(b));
+        assert_eq!(compare(a, b), compare(b, a).reverse());
+        assert_ne!(compare(a, b), Ordering::Equal);
+    }
+
+    #[test]
+    fn marks_without_base_follow_vowel_order() {
+        assert_eq!(sorted(&["ាំ", "ំ", "ា", "ុំ", "◌ី"]), ["ា", "◌ី", "ុំ", "ំ", "ាំ"]);
+    }
+
+    #[test]
+    fn hex_key_orders_like_compare() {
+        let words = ["កា", "ក្ក", "ក", "កំ", "ខ", "abc", "ឥ", "ាំ", "ុំ"];
+        let mut by_hex: Vec<&str> = words.to_vec();
+        by_hex.sort_by_key(|w| sort_key_hex(w));
+        assert_eq!(by_hex, sorted(&words));
+    }
+}
@@ -0,0 +1,42 @@
+use super::collation::sort_key_hex;
+use super::orthography::{OrthographyViolation, repair_orthography, validate_orthography};
+use super::series::{SyllableSeries, analyze_series};
+use super::syllables::{TextSpan, segment_syllables};
+use super::transliterate::{KhmerTransliteration, transliterate_all};
+
+/// Orthographic syllables of `text`, with the runs of other chars between them
+#[tauri::command(async)]
+pub fn segment_khmer_syllables(text: String) -> Vec<TextSpan> {
+    segment_syllables(&text)
+}
+
+/// UNGEGN, ALA-LC and IPA spellings of `text`, generated by rules from the Khmer spelling
+#[tauri::command(async)]
+pub fn transliterate_khmer(text: String) -> KhmerTransliteration {
+    transliterate_all(&text)
+}
+
+/// Series of every syllable of `text` (base, subscripts and shifter decide it) and how its vowel reads there
+#[tauri::command(async)]
+pub fn analyze_khmer_series(text: String) -> Vec<SyllableSeries> {
+    analyze_series(&text)
+}
+
+/// Khmer dictionary sort keys of `texts` (in the same order), strings that order like the "khmer" collation
+#[tauri::command(async)]
+pub fn khmer_sort_keys(texts: Vec<String>) -> Vec<String> {
+    texts.iter().map(|t| sort_key_hex(t)).collect()
+}
+
+/// Sequences of `text` Khmer spelling doesn't allow (double coeng, vowel typed before its consonant, ...),
+/// each with its position and a fix
+#[tauri::command(async)]
+pub fn validate_khmer_orthography(text: String) -> Vec<OrthographyViolation> {
+    validate_orthography(&text)
+}
+
+/// `text` with every fix of validate_khmer_orthography applied
+#[tauri::command(async)]
+pub fn repair_khmer_orthography(text: String) -> String {
+    repair_orthography(&text)
+}
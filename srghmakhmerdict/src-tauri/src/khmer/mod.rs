@@ -0,0 +1,11 @@
+pub mod chars;
+pub mod collation;
+pub mod commands;
+pub mod normalize;
+pub mod orthography;
+pub mod pronunciation;
+pub mod romanize;
+pub mod series;
+pub mod syllables;
+pub mod transliterate;
+pub mod units;
@@ -0,0 +1,431 @@
+// Canonical form of Khmer text for lookups, same idea as khnormal (khmer-normalizer) that was used
+// to clean the km_Dict headwords:
+// - invisible chars (ZWSP, ZWJ, ZWNJ, soft hyphen, BOM, ឴ ឵) are dropped
+// - deprecated letters are replaced: ឣ -> អ, ឤ -> អា, ឲ / ឲ្យ -> ឱ្យ
+// - inside each orthographic syllable the marks are reordered:
+//   base, robat, subscripts (coeng ro last), shifter, vowel, signs
+// - split/duplicated vowels are merged: េ + ា -> ោ, េ + ី -> ើ, ា + ា -> ា
+// - ុ written instead of a shifter next to an upper vowel becomes ៉ / ៊ (សុី -> ស៊ី)
+// - coeng da is written as coeng ta (្ដ -> ្ត), they look the same and the dictionary uses ្ត
+
+use super::chars::{COENG, is_consonant};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Cat {
+    Other,
+    Base,
+    Robat,
+    Coeng,
+    Shift,
+    VowelPre,
+    VowelBelow,
+    VowelAbove,
+    VowelPost,
+    SignAbove,
+    SignFinal,
+}
+
+fn cat(c: char) -> Cat {
+    match c {
+        '\u{1780}'..='\u{17A2}' | '\u{17A5}'..='\u{17B3}' => Cat::Base,
+        '\u{17B6}' => Cat::VowelPost,
+        '\u{17B7}'..='\u{17BA}' => Cat::VowelAbove,
+        '\u{17BB}'..='\u{17BD}' => Cat::VowelBelow,
+        '\u{17BE}'..='\u{17C5}' => Cat::VowelPre,
+        '\u{17C6}' | '\u{17CB}' | '\u{17CD}'..='\u{17D1}' | '\u{17D3}' | '\u{17DD}' => Cat::SignAbove,
+        '\u{17C7}' | '\u{17C8}' => Cat::SignFinal,
+        '\u{17C9}' | '\u{17CA}' => Cat::Shift,
+        '\u{17CC}' => Cat::Robat,
+        COENG => Cat::Coeng,
+        _ => Cat::Other,
+    }
+}
+
+const IGNORED: &[char] = &[
+    '\u{200B}', // zero width space
+    '\u{200C}', // zero width non-joiner
+    '\u{200D}', // zero width joiner
+    '\u{2060}', // word joiner
+    '\u{FEFF}', // BOM / zero width no-break space
+    '\u{00AD}', // soft hyphen
+    '\u{17B4}', // inherent vowel aq (invisible, deprecated)
+    '\u{17B5}', // inherent vowel aa (invisible, deprecated)
+];
+
+const VOWEL_U: char = '\u{17BB}'; // ុ
+const MUUSIKATOAN: char = '\u{17C9}'; // ៉
+const TRIISAP: char = '\u{17CA}'; // ៊
+
+// Upper vowels that push a shifter down to ុ in handwriting (and wrongly in typed text)
+fn is_upper_vowel(c: char) -> bool {
+    matches!(c, '\u{17B7}'..='\u{17BA}' | '\u{17BE}' | '\u{17D0}')
+}
+
+// Which shifter the consonant takes: ៊ moves ស ហ អ to the o-series, ៉ moves ង ញ ម យ រ វ to the a-series and makes ប a p
+fn shifter_for(base: char) -> Option<char> {
+    match base {
+        'ស' | 'ហ' | 'អ' => Some(TRIISAP),
+        'ប' | 'ង' | 'ញ' | 'ម' | 'យ' | 'រ' | 'វ' => Some(MUUSIKATOAN),
+        _ => None,
+    }
+}
+
+fn replace_deprecated(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().filter(|c| !IGNORED.contains(c)).peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{17A3}' => out.push('អ'),
+            '\u{17A4}' => out.push_str("អា"),
+            // ឲ is only used in one word, the modern spelling is ឱ្យ
+            '\u{17B2}' => {
+                out.push_str("ឱ្យ");
+                let mut rest = chars.clone();
+                if rest.next() == Some(COENG) && rest.next() == Some('យ') {
+                    chars.next();
+                    chars.next();
+                }
+            }
+            // a run of coengs is one coeng
+            COENG => {
+                out.push(c);
+                while chars.peek() == Some(&COENG) {
+                    chars.next();
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[derive(Debug)]
+struct Token {
+    cat: Cat,
+    text: String,
+}
+
+fn normalize_syllable(mut tokens: Vec<Token>) -> String {
+    let base = tokens[0].text.chars().next().unwrap_or_default();
+
+    // សុី -> ស៊ី, only for a bare consonant (with subscripts it's unclear which letter the shifter is for)
+    let has_shift = tokens.iter().any(|t| t.cat == Cat::Shift);
+    let has_coeng = tokens.iter().any(|t| t.cat == Cat::Coeng);
+    let has_upper = tokens.iter().any(|t| t.text.chars().all(is_upper_vowel));
+    let shifter = shifter_for(base).filter(|_| !has_shift && !has_coeng && has_upper);
+    if let (Some(shifter), Some(u)) = (shifter, tokens.iter_mut().find(|t| t.text.starts_with(VOWEL_U))) {
+        *u = Token { cat: Cat::Shift, text: shifter.to_string() };
+    }
+
+    // the base stays first, the rest is ordered by category (stable, so subscripts keep their order)
+    tokens[1..].sort_by_key(|t| t.cat);
+
+    // coeng ro goes after the other subscripts (ន្ត្រ, not ន្រ្ត)
+    let coeng_ro_count = tokens.iter().filter(|t| t.text == "្រ").count();
+    if coeng_ro_count > 0 {
+        let first_coeng = tokens.iter().position(|t| t.cat == Cat::Coeng).unwrap_or(1);
+        let last_coeng = tokens.iter().rposition(|t| t.cat == Cat::Coeng).unwrap_or(0);
+        tokens[first_coeng..=last_coeng].sort_by_key(|t| t.text == "្រ");
+    }
+
+    tokens.dedup_by(|b, a| a.text == b.text && a.cat != Cat::Base);
+
+    let mut out: String = tokens.into_iter().map(|t| t.text).collect();
+
+    for (from, to) in [("\u{17C1}\u{17B6}", "\u{17C4}"), ("\u{17C1}\u{17B8}", "\u{17BE}"), ("្ដ", "្ត")] {
+        if out.contains(from) {
+            out = out.replace(from, to);
+        }
+    }
+
+    out
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::with_capacity(text.len() / 2);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == COENG && is_consonant(next) => {
+                chars.next();
+                tokens.push(Token { cat: Cat::Coeng, text: format!("{}{}", c, next) });
+            }
+            _ => tokens.push(Token { cat: cat(c), text: c.to_string() }),
+        }
+    }
+    tokens
+}
+
+/// Canonical form of a Khmer word or text, used as the lookup key for km_Dict
+pub fn normalize_khmer(text: &str) -> String {
+    let text = replace_deprecated(text);
+    let mut tokens = tokenize(&text).into_iter().peekable();
+    let mut out = String::with_capacity(text.len());
+
+    while let Some(token) = tokens.next() {
+        if token.cat != Cat::Base {
+            out.push_str(&token.text);
+            continue;
+        }
+        let mut syllable = vec![token];
+        while let Some(next) = tokens.next_if(|t| t.cat > Cat::Base) {
+            syllable.push(next);
+        }
+        out.push_str(&normalize_syllable(syllable));
+    }
+
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_khmer;
+
+    #[test]
+    fn already_canonical_is_unchanged() {
+        let cases = [
+            "ក",
+            "ការ",
+            "ឆ្មា",
+            "ឈ្មោះ",
+            "ស្រឡាញ់",
+            "ស្ត្រី",
+            "ក្រុម",
+            "ប៉ុន្តែ",
+            "ស៊ី",
+            "ម៉ឺន",
+            "អោយ",
+            "ឱ្យ",
+            "ព្រះ",
+            "ធម៌",
+            "កម្ពុជា",
+            "ខ្មែរ",
+            "ភាសាខ្មែរ",
+            "សួស្តី",
+            "អរគុណ",
+            "ឯកសារ",
+            "ឥឡូវ",
+            "រៀន",
+            "ញ៉ាំ",
+            "ហ្វ៊ីល",
+            "កាហ្វេ",
+            "ពេលវេលា",
+            "hello",
+            "кошка",
+            "",
+        ];
+        for input in cases {
+            assert_eq!(normalize_khmer(input), input, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn invisible_chars_are_removed() {
+        let cases = [
+            ("ក\u{200B}ារ", "ការ"),
+            ("\u{200B}ឆ្មា\u{200B}", "ឆ្មា"),
+            ("ឆ\u{200D}្មា", "ឆ្មា"),
+            ("ឆ្\u{200C}មា", "ឆ្មា"),
+            ("\u{FEFF}ខ្មែរ", "ខ្មែរ"),
+            ("ភាសា\u{00AD}ខ្មែរ", "ភាសាខ្មែរ"),
+            ("ក\u{17B4}", "ក"),
+            ("ក\u{17B5}ារ", "ការ"),
+            ("ក\u{2060}ារ", "ការ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn deprecated_letters_are_replaced() {
+        let cases = [
+            ("ឣ", "អ"),
+            ("ឣក", "អក"),
+            ("ឤប", "អាប"),
+            ("ឤហារ", "អាហារ"),
+            ("កមរិតឤករ", "កមរិតអាករ"),
+            ("ការតតយលតានឤហារ", "ការតតយលតានអាហារ"),
+            ("ឲ", "ឱ្យ"),
+            ("ឲ្យ", "ឱ្យ"),
+            ("ឲ្យគេ", "ឱ្យគេ"),
+            ("ផ្តល់ឲ", "ផ្តល់ឱ្យ"),
+            ("ឱ្យ", "ឱ្យ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn vowel_typed_before_subscript_is_moved_after_it() {
+        let cases = [
+            ("ធា្ល", "ធ្លា"),
+            ("សា្លាយ", "ស្លាយ"),
+            ("អងា្កំ", "អង្កាំ"),
+            ("ឥទិ្ធ", "ឥទ្ធិ"),
+            ("ឱ្យនរណាមា្នា", "ឱ្យនរណាម្នា"),
+            ("កៅស៊ូបា្លា", "កៅស៊ូប្លា"),
+            ("ការបំផា្ល្ញ", "ការបំផ្ល្ញា"),
+            ("អាេយសិតិ្ធ", "អោយសិត្ធិ"),
+            ("ខេ្មរ", "ខ្មេរ"),
+            ("ែខ្ម", "ែខ្ម"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn coeng_ro_goes_last() {
+        let cases = [
+            ("កន្រ្ដៃ", "កន្ត្រៃ"),
+            ("កនិតសាស្រ្ដ", "កនិតសាស្ត្រ"),
+            ("គណិតសាស្រ្ត", "គណិតសាស្ត្រ"),
+            ("ភាសាសាស្រ្ត", "ភាសាសាស្ត្រ"),
+            ("សាស្រ្តា", "សាស្ត្រា"),
+            ("សំស្រ្កឹត", "សំស្ក្រឹត"),
+            ("មេកន្រ្ទាញ", "មេកន្ទ្រាញ"),
+            ("អចិន្រ្ដៃយ៍", "អចិន្ត្រៃយ៍"),
+            ("អចិន្រ្តៃយ៍", "អចិន្ត្រៃយ៍"),
+            ("កហ្រ្ជាង", "កហ្ជ្រាង"),
+            ("ស្ត្រី", "ស្ត្រី"),
+            ("ស្រ្តី", "ស្ត្រី"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn coeng_da_becomes_coeng_ta() {
+        let cases = [
+            ("កក់ក្ដោ", "កក់ក្តោ"),
+            ("កន្ដាល", "កន្តាល"),
+            ("ចិត្ដ", "ចិត្ត"),
+            ("ប៉ុន្ដែ", "ប៉ុន្តែ"),
+            ("សេចក្ដី", "សេចក្តី"),
+            ("ផ្ដើម", "ផ្តើម"),
+            ("រាស្ដ្រ", "រាស្ត្រ"),
+            ("អចិន្ដ្រៃយ៍", "អចិន្ត្រៃយ៍"),
+            ("កណ្ដាល", "កណ្តាល"),
+            ("ដ", "ដ"),
+            ("ដើរ", "ដើរ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn split_vowels_are_merged() {
+        let cases = [
+            ("អាេយ", "អោយ"),
+            ("កយរអាេយខ្លាច", "កយរអោយខ្លាច"),
+            ("អាេបរឹត", "អោបរឹត"),
+            ("កេា", "កោ"),
+            ("កោ", "កោ"),
+            ("កេី", "កើ"),
+            ("កីេ", "កើ"),
+            ("ដេីរ", "ដើរ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn marks_are_reordered() {
+        let cases = [
+            ("រុះេ", "រេុះ"),
+            ("ស្រះុ", "ស្រុះ"),
+            ("ស្រះូ", "ស្រូះ"),
+            ("ករផ្តល់េ", "ករផ្តលេ់"),
+            ("អាុវុធ", "អុាវុធ"),
+            ("កំា", "កាំ"),
+            ("កះា", "កាះ"),
+            ("អ៊ុយរា៉ញ៉ូម", "អ៊ុយរ៉ាញ៉ូម"),
+            ("កើតអាប់សយា៉", "កើតអាប់សយ៉ា"),
+            ("ញាំ៉", "ញ៉ាំ"),
+            ("ធ៌ម", "ធ៌ម"),
+            ("ក្រ៌", "ក៌្រ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn u_instead_of_shifter_is_fixed() {
+        let cases = [
+            ("សុី", "ស៊ី"),
+            ("កត់សុី", "កត់ស៊ី"),
+            ("ការកត់សុី", "ការកត់ស៊ី"),
+            ("បុី", "ប៉ី"),
+            ("មុឺនុយ", "ម៉ឺនុយ"),
+            ("កូកាអីុ", "កូកាអ៊ី"),
+            ("រឺសុ័រ", "រឺស៊័រ"),
+            ("ហុិ", "ហ៊ិ"),
+            ("រុឹ", "រ៉ឹ"),
+            // a consonant that takes no shifter keeps ុ
+            ("កុី", "កុី"),
+            // no upper vowel, ុ is the vowel
+            ("សុខ", "សុខ"),
+            ("បុណ្យ", "បុណ្យ"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn duplicates_are_collapsed() {
+        let cases = [
+            ("ការបាច់មុះះ", "ការបាច់មុះ"),
+            ("កាា", "កា"),
+            ("កណា្ដាស់", "កណ្តាស់"),
+            ("ក់់", "ក់"),
+            ("ក្្ក", "ក្ក"),
+            ("កោះស្្យ", "កោះស្យ"),
+            ("ក្ក្ក", "ក្ក"),
+            // repeated bases are separate syllables
+            ("កក", "កក"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn whitespace_is_trimmed_and_collapsed() {
+        let cases = [
+            ("  ឆ្មា ", "ឆ្មា"),
+            ("ឆ្មា   ខ្មៅ", "ឆ្មា ខ្មៅ"),
+            ("\tឆ្មា\n", "ឆ្មា"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(normalize_khmer(input), expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let cases = [
+            "កណា្ដាស់",
+            "អាេយសិតិ្ធ",
+            "ឲ",
+            "ឤហារ",
+            "សុី",
+            "ស្រ្តី",
+            "ក\u{200B}ារ",
+            "រុះេ",
+            "ក្រ៌",
+            "ហ្វ្មា៉្សីុដ្ល",
+            "ែខ្ម",
+        ];
+        for input in cases {
+            let once = normalize_khmer(input);
+            assert_eq!(normalize_khmer(&once), once, "input: {:?}", input);
+        }
+    }
+}
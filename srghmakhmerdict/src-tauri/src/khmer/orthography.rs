@@ -0,0 +1,439 @@
+// Structural checks of Khmer text, no dictionary involved: sequences no Khmer word can have, as OCR and
+// legacy keyboards produce them (a double coeng, េ typed before its consonant, ឥ្ធ, ាា ...).
+// Within a syllable the marks are written base, robat, subscripts, shifter, vowel, signs. Every violation
+// comes with a fix, the syllable it is in with all of its violations repaired.
+// normalize.rs goes further (order of the vowels and signs among themselves, coeng ro last, ្ដ -> ្ត)
+// but reports nothing; lookups fall back to the repaired text when the input itself isn't found
+
+use std::ops::Range;
+use serde::Serialize;
+use super::chars::{COENG, is_consonant, is_dependent_vowel, is_inherent_vowel, is_register_shifter};
+use super::syllables::{SpanKind, TextSpan, segment_syllables};
+
+const ROBAT: char = '\u{17CC}'; // ៌
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    // ្្, the extra coeng is dropped
+    DoubleCoeng,
+    // ្ with no consonant after it, dropped
+    DanglingCoeng,
+    // vowel signs or diacritics with no base before them (at the start, after a space, ...), dropped
+    StrayMark,
+    // េ ែ ៃ ... typed before the consonant they are written left of, moved after it
+    VowelBeforeConsonant,
+    // ឥ្ធ: only consonants take subscripts (but ឱ្យ and the old ឲ្យ), the coeng is dropped
+    SubscriptOnIndependentVowel,
+    // a mark the syllable already has, or a second shifter, dropped
+    DuplicateMark,
+    // a vowel typed as its halves, េ + ា for ោ, merged
+    SplitVowel,
+    // written after a mark that comes later in the syllable (ធា្ល for ធ្លា), moved
+    MarkOrder,
+}
+
+/// Replace `byte_start..byte_end` (char_start..char_end) of the input with `replacement`
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrthographyFix {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub replacement: String,
+}
+
+/// The offending chars, offsets into the input as in segment_khmer_syllables. Violations in the same
+/// syllable share their fix
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct OrthographyViolation {
+    pub kind: ViolationKind,
+    pub text: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub fix: OrthographyFix,
+}
+
+// Where a mark is written in a syllable, in order
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Slot {
+    Robat,
+    Subscript,
+    Shifter,
+    Vowel,
+    Sign,
+}
+
+// A char of the input and where it is
+#[derive(Clone, Copy)]
+struct Located {
+    c: char,
+    byte: usize,
+    char: usize,
+}
+
+fn located(span: &TextSpan) -> impl Iterator<Item = Located> + '_ {
+    span.text.char_indices().enumerate().map(|(i, (byte, c))| Located {
+        c,
+        byte: span.byte_start + byte,
+        char: span.char_start + i,
+    })
+}
+
+// Written left of the consonant: ើ ឿ ៀ េ ែ ៃ ោ ៅ
+fn is_left_vowel(c: char) -> bool {
+    matches!(c, '\u{17BE}'..='\u{17C5}')
+}
+
+// ឱ្យ, written ឲ្យ before the spelling reform
+fn takes_subscript(base: char, sub: char) -> bool {
+    is_consonant(base) || (matches!(base, 'ឱ' | 'ឲ') && sub == 'យ')
+}
+
+// ្េល: a left vowel typed between the coeng and its consonant, the consonant then starts the next syllable
+fn ends_with_split_subscript(span: &TextSpan) -> bool {
+    let mut marks = span.text.chars().rev().skip_while(|&c| is_left_vowel(c));
+    span.text.chars().next_back().is_some_and(is_left_vowel) && marks.next() == Some(COENG)
+}
+
+fn merge_split_vowel(a: char, b: char) -> Option<char> {
+    match (a, b) {
+        ('េ', 'ា') | ('ា', 'េ') => Some('ោ'),
+        ('េ', 'ី') | ('ី', 'េ') => Some('ើ'),
+        _ => None,
+    }
+}
+
+// A syllable as it will be written once repaired
+struct Parts {
+    base: char,
+    robat: bool,
+    subscripts: Vec<char>,
+    shifter: Option<char>,
+    vowels: Vec<char>,
+    signs: Vec<char>,
+}
+
+impl Parts {
+    // Adds a vowel, or says why it can't be added as it is
+    fn add_vowel(&mut self, c: char) -> Option<ViolationKind> {
+        if self.vowels.contains(&c) {
+            return Some(ViolationKind::DuplicateMark);
+        }
+        let split = self.vowels.iter_mut().find_map(|v| merge_split_vowel(*v, c).map(|merged| (v, merged)));
+        match split {
+            Some((v, merged)) => {
+                *v = merged;
+                Some(ViolationKind::SplitVowel)
+            }
+            None => {
+                self.vowels.push(c);
+                None
+            }
+        }
+    }
+
+    fn write(&self) -> String {
+        let mut out = String::new();
+        out.push(self.base);
+        if self.robat {
+            out.push(ROBAT);
+        }
+        for &sub in &self.subscripts {
+            // on an independent vowel the consonant is written after it instead
+            if takes_subscript(self.base, sub) {
+                out.push(COENG);
+            }
+            out.push(sub);
+        }
+        out.extend(self.shifter);
+        out.extend(&self.vowels);
+        out.extend(&self.signs);
+        out
+    }
+}
+
+/// Violations of one syllable (`chars[moved]` is its base, `chars[..moved]` left vowels typed before it,
+/// `after` the char that follows it) as ranges of `chars`, and the syllable repaired. The syllable is kept
+/// as it is if there are none
+fn check_syllable(chars: &[Located], moved: usize, after: Option<char>) -> (Vec<(ViolationKind, Range<usize>)>, String) {
+    use ViolationKind::*;
+
+    let base = chars[moved].c;
+    let mut parts = Parts {
+        base,
+        robat: false,
+        subscripts: Vec::new(),
+        shifter: None,
+        vowels: Vec::new(),
+        signs: Vec::new(),
+    };
+    let mut found: Vec<(ViolationKind, Range<usize>)> = Vec::new();
+    let mut last_slot = Slot::Robat;
+
+    let mut i = moved + 1;
+    while let Some(&Located { c, .. }) = chars.get(i) {
+        let mut len = 1;
+        let (slot, rejected) = if c == COENG {
+            let next = chars.get(i + 1).map(|l| l.c);
+            match next.or(after.filter(|_| i + 1 == chars.len())) {
+                Some(COENG) => (Slot::Subscript, Some(DoubleCoeng)),
+                Some(sub) if is_consonant(sub) => {
+                    len = 2;
+                    if !takes_subscript(base, sub) {
+                        found.push((SubscriptOnIndependentVowel, i..i + 2));
+                    }
+                    if parts.subscripts.last() == Some(&sub) {
+                        (Slot::Subscript, Some(DuplicateMark))
+                    } else {
+                        parts.subscripts.push(sub);
+                        (Slot::Subscript, None)
+                    }
+                }
+                // ្ឫ ្ឬ are written under the base in Sanskrit loans (សុហ្ឫទ), though they start a syllable
+                Some('ឫ' | 'ឬ') => {
+                    parts.signs.push(c);
+                    (Slot::Sign, None)
+                }
+                Some(next) if is_left_vowel(next) => {
+                    let vowels = chars[i + 1..].iter().take_while(|l| is_left_vowel(l.c)).count();
+                    match chars.get(i + 1 + vowels) {
+                        Some(&Located { c: sub, .. }) if is_consonant(sub) => {
+                            // the vowels move after the subscript, the marks of the next syllable follow
+                            found.push((MarkOrder, i + 1..i + 1 + vowels));
+                            for (j, l) in chars[i + 1..i + 1 + vowels].iter().enumerate() {
+                                if let Some(kind) = parts.add_vowel(l.c) {
+                                    found.push((kind, i + 1 + j..i + 2 + j));
+                                }
+                            }
+                            parts.subscripts.push(sub);
+                            last_slot = Slot::Vowel;
+                            i += vowels + 2;
+                            continue;
+                        }
+                        _ => (Slot::Subscript, Some(DanglingCoeng)),
+                    }
+                }
+                _ => (Slot::Subscript, Some(DanglingCoeng)),
+            }
+        } else if c == ROBAT {
+            if parts.robat {
+                (Slot::Robat, Some(DuplicateMark))
+            } else {
+                parts.robat = true;
+                (Slot::Robat, None)
+            }
+        } else if is_register_shifter(c) {
+            if parts.shifter.is_some() {
+                (Slot::Shifter, Some(DuplicateMark))
+            } else {
+                parts.shifter = Some(c);
+                (Slot::Shifter, None)
+            }
+        } else if is_dependent_vowel(c) || is_inherent_vowel(c) {
+            (Slot::Vowel, parts.add_vowel(c))
+        } else if parts.signs.contains(&c) {
+            (Slot::Sign, Some(DuplicateMark))
+        } else {
+            parts.signs.push(c);
+            (Slot::Sign, None)
+        };
+
+        if let Some(kind) = rejected {
+            found.push((kind, i..i + len));
+        }
+        // a merged vowel is still written here, the other rejected marks are dropped
+        if rejected.is_none_or(|kind| kind == SplitVowel) {
+            if slot < last_slot {
+                found.push((MarkOrder, i..i + len));
+            }
+            last_slot = last_slot.max(slot);
+        }
+        i += len;
+    }
+
+    if moved > 0 {
+        found.push((VowelBeforeConsonant, 0..moved));
+        for (j, l) in chars[..moved].iter().enumerate() {
+            if let Some(kind) = parts.add_vowel(l.c) {
+                found.push((kind, j..j + 1));
+            }
+        }
+    }
+
+    if found.is_empty() {
+        return (found, chars.iter().map(|l| l.c).collect());
+    }
+    found.sort_by_key(|(_, range)| range.start);
+    (found, parts.write())
+}
+
+fn fix_of(chars: &[Located], replacement: String) -> OrthographyFix {
+    let (first, last) = (chars[0], chars[chars.len() - 1]);
+    OrthographyFix {
+        byte_start: first.byte,
+        byte_end: last.byte + last.c.len_utf8(),
+        char_start: first.char,
+        char_end: last.char + 1,
+        replacement,
+    }
+}
+
+fn violation(kind: ViolationKind, chars: &[Located], fix: &OrthographyFix) -> OrthographyViolation {
+    let range = fix_of(chars, String::new());
+    OrthographyViolation {
+        kind,
+        text: chars.iter().map(|l| l.c).collect(),
+        byte_start: range.byte_start,
+        byte_end: range.byte_end,
+        char_start: range.char_start,
+        char_end: range.char_end,
+        fix: fix.clone(),
+    }
+}
+
+fn check(text: &str) -> (Vec<OrthographyViolation>, String) {
+    let spans = segment_syllables(text);
+    let mut violations: Vec<OrthographyViolation> = Vec::new();
+    let mut repaired = String::with_capacity(text.len());
+
+    let mut i = 0;
+    while let Some(span) = spans.get(i) {
+        i += 1;
+        let mut chars: Vec<Located> = located(span).collect();
+        let moved = match span.kind {
+            SpanKind::StrayMarks => match spans.get(i) {
+                Some(next) if next.kind == SpanKind::Syllable && span.text.chars().all(is_left_vowel) => {
+                    chars.extend(located(next));
+                    i += 1;
+                    span.char_end - span.char_start
+                }
+                _ => {
+                    let fix = fix_of(&chars, String::new());
+                    violations.push(violation(ViolationKind::StrayMark, &chars, &fix));
+                    continue;
+                }
+            },
+            SpanKind::Syllable => {
+                if ends_with_split_subscript(span)
+                    && let Some(next) = spans.get(i).filter(|next| next.kind == SpanKind::Syllable)
+                    && is_consonant(next.text.chars().next().unwrap_or_default())
+                {
+                    chars.extend(located(next));
+                    i += 1;
+                }
+                0
+            }
+            _ => {
+                repaired.push_str(&span.text);
+                continue;
+            }
+        };
+
+        let after = spans.get(i).and_then(|next| next.text.chars().next());
+        let (found, syllable) = check_syllable(&chars, moved, after);
+        if !found.is_empty() {
+            let fix = fix_of(&chars, syllable.clone());
+            violations.extend(found.into_iter().map(|(kind, range)| violation(kind, &chars[range], &fix)));
+        }
+        repaired.push_str(&syllable);
+    }
+
+    (violations, repaired)
+}
+
+/// Sequences of `text` Khmer spelling doesn't allow, in order
+pub fn validate_orthography(text: &str) -> Vec<OrthographyViolation> {
+    check(text).0
+}
+
+/// `text` with every fix of `validate_orthography` applied; text without violations is returned as it is
+pub fn repair_orthography(text: &str) -> String {
+    check(text).1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ViolationKind::*;
+
+    fn kinds(text: &str) -> Vec<(ViolationKind, String)> {
+        validate_orthography(text).into_iter().map(|v| (v.kind, v.text)).collect()
+    }
+
+    #[test]
+    fn well_formed_text_has_no_violations() {
+        let cases = [
+            "ការ",
+            "ស្រឡាញ់",
+            "ស្ត្រី",
+            "ប៉ុន្តែ",
+            "ស៊ី",
+            "ញ៉ាំ",
+            "ធម៌",
+            "ក៌្រ",
+            "ឱ្យ",
+            "ឲ្យ",
+            "សុហ្ឫទ",
+            "ឥឡូវ",
+            "អុាវុធ",
+            "រេុះ",
+            "ខ្ញុំស្រឡាញ់ភាសាខ្មែរ។",
+            "hello ១២៣",
+            "",
+        ];
+        for text in cases {
+            assert_eq!(kinds(text), [], "text: {:?}", text);
+            assert_eq!(repair_orthography(text), text);
+        }
+    }
+
+    #[test]
+    fn violations_and_repairs() {
+        let s = |t: &str| t.to_string();
+        let cases = [
+            ("ក្្ក", vec![(DoubleCoeng, s("្"))], "ក្ក"),
+            ("ក្ា", vec![(DanglingCoeng, s("្"))], "កា"),
+            ("ក្", vec![(DanglingCoeng, s("្"))], "ក"),
+            ("ា ក", vec![(StrayMark, s("ា"))], " ក"),
+            ("ែខ្មរ", vec![(VowelBeforeConsonant, s("ែ"))], "ខ្មែរ"),
+            ("េកា", vec![(VowelBeforeConsonant, s("េ")), (SplitVowel, s("េ"))], "កោ"),
+            ("ឥ្ធិ", vec![(SubscriptOnIndependentVowel, s("្ធ"))], "ឥធិ"),
+            ("កាា", vec![(DuplicateMark, s("ា"))], "កា"),
+            ("មុះះ", vec![(DuplicateMark, s("ះ"))], "មុះ"),
+            ("ស៊៉ី", vec![(DuplicateMark, s("៉"))], "ស៊ី"),
+            ("ក្ក្ក", vec![(DuplicateMark, s("្ក"))], "ក្ក"),
+            ("ជន្េលញ", vec![(MarkOrder, s("េ"))], "ជន្លេញ"),
+            ("កេា", vec![(SplitVowel, s("ា"))], "កោ"),
+            ("ធា្ល", vec![(MarkOrder, s("្ល"))], "ធ្លា"),
+            ("ញាំ៉", vec![(MarkOrder, s("៉"))], "ញ៉ាំ"),
+            ("កំា", vec![(MarkOrder, s("ា"))], "កាំ"),
+        ];
+        for (text, expected, repaired) in cases {
+            assert_eq!(kinds(text), expected, "text: {:?}", text);
+            assert_eq!(repair_orthography(text), repaired, "text: {:?}", text);
+            assert_eq!(kinds(repaired), [], "repaired: {:?}", repaired);
+        }
+    }
+
+    #[test]
+    fn offsets_and_shared_fix() {
+        // ខ្ញុំ | ធា្ល | ់់: two violations in one syllable, both fixed by rewriting it
+        let text = "ខ្ញុំធា្ល់់";
+        let violations = validate_orthography(text);
+        assert_eq!(violations.len(), 2);
+        let (order, duplicate) = (&violations[0], &violations[1]);
+
+        assert_eq!((order.kind, order.char_start, order.char_end), (MarkOrder, 7, 9));
+        assert_eq!(&text[order.byte_start..order.byte_end], "្ល");
+        assert_eq!((duplicate.kind, duplicate.char_start, duplicate.char_end), (DuplicateMark, 10, 11));
+
+        let fix = &order.fix;
+        assert_eq!(fix, &duplicate.fix);
+        assert_eq!((fix.char_start, fix.char_end), (5, 11));
+        assert_eq!(&text[fix.byte_start..fix.byte_end], "ធា្ល់់");
+        assert_eq!(fix.replacement, "ធ្លា់");
+        assert_eq!(repair_orthography(text), "ខ្ញុំធ្លា់");
+    }
+}
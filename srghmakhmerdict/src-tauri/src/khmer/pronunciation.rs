@@ -0,0 +1,45 @@
+// Pronunciation strings of km_Dict (Phonetic, from_csv_pronunciations), compared for homophones.
+// Unlike fold_romanization this keeps every vowel and aspiration distinction the notation makes,
+// it only drops what doesn't change the sound: case, brackets, stress and syllable marks, spacing,
+// and spells alternative symbols of the same sound one way
+
+const IGNORED: &[char] = &[
+    '/', '[', ']', '(', ')', // /kaa/, [kaː]
+    'ˈ', 'ˌ', '\'', '’', 'ʼ', '.', '-', '‿', // stress marks, syllable breaks
+    '\u{0361}', '\u{035C}', // tie bars: t͡ʃ
+    '\u{200B}', '\u{00AD}', // zero width space, soft hyphen
+];
+
+// separators of alternative pronunciations written in one cell
+const ALTERNATIVE_SEPARATORS: &[char] = &[',', ';', '|', '~'];
+
+fn normalize_one(pronunciation: &str) -> String {
+    pronunciation
+        .to_lowercase()
+        .chars()
+        .filter(|c| !IGNORED.contains(c) && !c.is_whitespace())
+        .map(|c| match c {
+            ':' => 'ː',
+            'ɡ' => 'g',
+            c => c,
+        })
+        .collect()
+}
+
+pub struct Pronunciation<'a> {
+    pub written: &'a str,
+    pub normalized: String,
+}
+
+/// Every pronunciation written in `text` (one cell can list alternatives: "kaa, kaː"),
+/// normalized so the same sound is the same string. Empty ones are dropped, duplicates removed
+pub fn split_pronunciations(text: &str) -> Vec<Pronunciation<'_>> {
+    let mut pronunciations: Vec<Pronunciation> = Vec::new();
+    for written in text.split(ALTERNATIVE_SEPARATORS) {
+        let normalized = normalize_one(written);
+        if !normalized.is_empty() && !pronunciations.iter().any(|p| p.normalized == normalized) {
+            pronunciations.push(Pronunciation { written: written.trim(), normalized });
+        }
+    }
+    pronunciations
+}
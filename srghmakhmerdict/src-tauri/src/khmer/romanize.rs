@@ -0,0 +1,165 @@
+// Latin spelling of Khmer words, for finding a word by how it is pronounced/typed in Latin letters:
+// - romanize: UNGEGN romanization of a Khmer word (ស្រឡាញ់ -> srâlănh), see transliterate.rs
+// - fold_romanization: collapses the spelling variants people use for the same sounds
+//   (UNGEGN diacritics, IPA from the dictionaries, chat spelling: chhmuoh / chmuos / chhmuəh -> cmuoh)
+// - romanization_key: consonant skeleton of a folded spelling, what the index is looked up by
+
+use super::transliterate::{Scheme, transliterate};
+
+/// UNGEGN romanization by rules: each syllable is onset + vowel (read by the series) + final.
+/// Non-Khmer chars are kept, Khmer digits become ASCII digits, Khmer punctuation becomes a space
+pub fn romanize(word: &str) -> String {
+    transliterate(word, Scheme::Ungegn)
+}
+
+/// Plain lowercase letters for one char of a romanization: diacritics dropped, IPA symbols spelled the way people type them
+fn push_plain(c: char, out: &mut Vec<char>) {
+    let plain = match c {
+        'â' | 'ă' | 'á' | 'à' | 'ä' | 'ã' | 'å' | 'ā' | 'ɑ' | 'ɐ' | 'æ' => "a",
+        'é' | 'è' | 'ê' | 'ë' | 'ĕ' | 'ě' | 'ē' | 'ə' | 'ɛ' | 'ɘ' | 'ɜ' => "e",
+        'í' | 'ì' | 'î' | 'ï' | 'ĭ' | 'ī' | 'ɪ' => "i",
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ŏ' | 'ō' | 'ɔ' | 'ɒ' => "o",
+        'ú' | 'ù' | 'û' | 'ü' | 'ŭ' | 'ū' | 'ʊ' => "u",
+        'œ' | 'ɨ' | 'ɯ' | 'ʉ' => "eu",
+        'ŋ' => "ng",
+        'ɲ' | 'ñ' => "nh",
+        'ɓ' => "b",
+        'ɗ' => "d",
+        'ʋ' => "v",
+        'ʰ' => "h",
+        'ɡ' => "g",
+        c if c.is_ascii_alphanumeric() => {
+            out.push(c);
+            return;
+        }
+        // spaces, hyphens, apostrophes, ʔ, ː, combining marks
+        _ => "",
+    };
+    out.extend(plain.chars());
+}
+
+fn is_folded_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Collapses spelling variants of a romanization, so that UNGEGN, the dictionaries' IPA-like phonetics and
+/// chat spelling of a word end up (nearly) equal:
+/// - lowercase ASCII only, diacritics and separators dropped
+/// - nh / ny / ñ -> ñ, ng -> ŋ (one letter each)
+/// - aspiration is ignored (kh -> k, chh -> c), j / ch -> c, g -> k, w -> v
+/// - y / v after a vowel and not before one are the vowels i / u (ey -> ei, tov -> tou)
+/// - word-final s is h and a final r is silent, as they are pronounced
+/// - doubled letters are single (chhmaa -> cma)
+pub fn fold_romanization(text: &str) -> String {
+    let mut plain: Vec<char> = Vec::with_capacity(text.len());
+    for c in text.to_lowercase().chars() {
+        push_plain(c, &mut plain);
+    }
+
+    let mut letters: Vec<char> = Vec::with_capacity(plain.len());
+    let mut i = 0;
+    while i < plain.len() {
+        let c = plain[i];
+        let digraph = match (c, plain.get(i + 1)) {
+            ('n', Some('h' | 'y')) => Some('ñ'),
+            ('n', Some('g')) => Some('ŋ'),
+            _ => None,
+        };
+        if let Some(digraph) = digraph {
+            letters.push(digraph);
+            i += 2;
+            continue;
+        }
+        match c {
+            // aspiration
+            'h' if letters.last().is_some_and(|&prev| !is_folded_vowel(prev)) => {}
+            'g' | 'q' => letters.push('k'),
+            'j' => letters.push('c'),
+            'w' => letters.push('v'),
+            'z' | 'x' => letters.push('s'),
+            c => letters.push(c),
+        }
+        i += 1;
+    }
+
+    let mut folded = String::with_capacity(letters.len());
+    for (i, &c) in letters.iter().enumerate() {
+        let after_vowel = i > 0 && is_folded_vowel(letters[i - 1]);
+        let before_vowel = letters.get(i + 1).is_some_and(|&next| is_folded_vowel(next));
+        let c = match c {
+            'y' if after_vowel && !before_vowel => Some('i'),
+            'v' if after_vowel && !before_vowel => Some('u'),
+            's' if after_vowel && i + 1 == letters.len() => Some('h'),
+            'r' if after_vowel && !before_vowel => None,
+            c => Some(c),
+        };
+        if let Some(c) = c
+            && !folded.ends_with(c)
+        {
+            folded.push(c);
+        }
+    }
+
+    folded
+}
+
+/// Consonant skeleton of a `fold_romanization` result (the vowels are spelled too differently to index on),
+/// the folded spelling itself if it has no consonants. Voiced and voiceless stops are merged,
+/// chat spelling mixes them up (sousdey for សួស្តី)
+pub fn romanization_key(folded: &str) -> String {
+    let key: String = folded
+        .chars()
+        .filter(|&c| !is_folded_vowel(c))
+        .map(|c| match c {
+            'd' => 't',
+            'b' => 'p',
+            c => c,
+        })
+        .collect();
+    if key.is_empty() { folded.to_string() } else { key }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_of(spelling: &str) -> String {
+        romanization_key(&fold_romanization(spelling))
+    }
+
+    #[test]
+    fn romanize_by_ungegn() {
+        assert_eq!(romanize("ឈ្មោះ"), "chhmuŏh");
+        assert_eq!(romanize("ស្រឡាញ់"), "srâlănh");
+        assert_eq!(romanize("ឆ្មា ១២"), "chhma 12");
+    }
+
+    #[test]
+    fn fold_collapses_spelling_variants() {
+        // UNGEGN, chat spelling and the dictionaries' IPA of ឈ្មោះ
+        assert_eq!(fold_romanization("chhmuŏh"), "cmuoh");
+        assert_eq!(fold_romanization("chmuos"), "cmuoh");
+        assert_eq!(fold_romanization("cʰmuəh"), "cmueh");
+        assert_eq!(fold_romanization("Sour-Sdey"), "sousdei");
+        assert_eq!(fold_romanization("tov"), "tou");
+        assert_eq!(fold_romanization("ngo nyom"), "ŋoñom");
+        assert_eq!(fold_romanization("ʔ-ː"), "");
+    }
+
+    #[test]
+    fn key_is_the_consonant_skeleton() {
+        assert_eq!(romanization_key("cmuoh"), "cmh");
+        assert_eq!(romanization_key("sousdei"), "sst");
+        assert_eq!(romanization_key("bat"), "pt");
+        assert_eq!(romanization_key("ai"), "ai");
+        assert_eq!(romanization_key(""), "");
+    }
+
+    #[test]
+    fn chat_spelling_finds_the_word() {
+        let examples = [("chhmuos", "ឈ្មោះ"), ("srolanh", "ស្រឡាញ់"), ("sousdey", "សួស្តី")];
+        for (typed, word) in examples {
+            assert_eq!(key_of(typed), key_of(&romanize(word)), "{} -> {}", typed, word);
+        }
+    }
+}
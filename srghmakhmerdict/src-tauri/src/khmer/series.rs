@@ -0,0 +1,196 @@
+// Consonant series of Khmer syllables and what their vowel sounds like in it.
+// Every consonant is a-series (ក ខ ច ...) or o-series (គ ឃ ង ...), and most vowel signs read differently
+// after each: កា ka, គា kéa. A cluster reads in the series of its first subscript unless that one is
+// a sonorant (ស្ត a, ស្ន a), and the shifters override both: ៉ moves ង ញ ម យ ... to the a-series,
+// ៊ moves ស ហ ប ... to the o-series. Also used by transliterate.rs, so both agree on every syllable
+
+use serde::Serialize;
+use super::chars::is_consonant;
+use super::syllables::{BaseKind, segment_syllables};
+use super::transliterate::{Cluster, Scheme, independent_vowel, vowel_reading};
+
+const MUUSIKATOAN: char = '\u{17C9}'; // ៉
+const TRIISAP: char = '\u{17CA}'; // ៊
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Series {
+    A,
+    O,
+}
+
+impl Series {
+    fn other(self) -> Series {
+        match self {
+            Series::A => Series::O,
+            Series::O => Series::A,
+        }
+    }
+}
+
+/// What decided the series of a syllable
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SeriesSource {
+    Base,
+    Subscript,
+    Shifter,
+}
+
+/// None for anything but a consonant
+pub fn consonant_series(c: char) -> Option<Series> {
+    match c {
+        'ក' | 'ខ' | 'ច' | 'ឆ' | 'ដ' | 'ឋ' | 'ណ' | 'ត' | 'ថ' | 'ប' | 'ផ' | 'ឝ' | 'ឞ' | 'ស' | 'ហ' | 'ឡ' | 'អ' => Some(Series::A),
+        c if is_consonant(c) => Some(Series::O),
+        _ => None,
+    }
+}
+
+// ង ញ ន ម យ រ ល វ: in a cluster they take the series of the other consonant
+fn is_sonorant(c: char) -> bool {
+    matches!(c, 'ង' | 'ញ' | 'ន' | 'ម' | 'យ' | 'រ' | 'ល' | 'វ')
+}
+
+/// Series of a base consonant followed by `consonants[1..]` as subscripts, None if there is no consonant
+pub fn cluster_series(consonants: &[char], shifter: Option<char>) -> Option<(Series, SeriesSource)> {
+    let &base = consonants.first()?;
+    match shifter {
+        Some(MUUSIKATOAN) => return Some((Series::A, SeriesSource::Shifter)),
+        Some(TRIISAP) => return Some((Series::O, SeriesSource::Shifter)),
+        _ => {}
+    }
+    let (from, source) = match consonants.get(1) {
+        Some(&sub) if !is_sonorant(sub) => (sub, SeriesSource::Subscript),
+        _ => (base, SeriesSource::Base),
+    };
+    Some((consonant_series(from).unwrap_or(Series::A), source))
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct VowelReading {
+    pub ungegn: String,
+    pub ipa: String,
+}
+
+/// One orthographic syllable with its series and vowel reading
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SyllableSeries {
+    pub text: String,
+    // offsets into the input as in syllables.rs: bytes and chars, end exclusive
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    pub base: char,
+    pub base_kind: BaseKind,
+    pub subscripts: Vec<char>,
+    // ៉ or ៊
+    pub shifter: Option<char>,
+    // None for independent vowels, they read the same in both series
+    pub series: Option<Series>,
+    pub series_source: Option<SeriesSource>,
+    // dependent vowel signs plus ំ / ះ, "" for the inherent vowel
+    pub vowel: String,
+    // of the vowel in `series`, of the independent vowel itself for independent vowels
+    pub reading: VowelReading,
+    // of the same vowel in the other series, None for independent vowels
+    pub other_series_reading: Option<VowelReading>,
+}
+
+fn read(written: &str, series: Series) -> VowelReading {
+    VowelReading {
+        ungegn: vowel_reading(written, series, Scheme::Ungegn),
+        ipa: vowel_reading(written, series, Scheme::Ipa),
+    }
+}
+
+/// Series analysis of every syllable of `text`; text between syllables is skipped
+pub fn analyze_series(text: &str) -> Vec<SyllableSeries> {
+    segment_syllables(text)
+        .into_iter()
+        .filter_map(|span| {
+            let parts = span.parts?;
+            let cluster = Cluster::from(&parts);
+            let (series, series_source, reading, other_series_reading) = match parts.base_kind {
+                BaseKind::Consonant => {
+                    let (series, source) = cluster_series(&cluster.consonants, cluster.shifter)?;
+                    let other = read(&cluster.vowel, series.other());
+                    (Some(series), Some(source), read(&cluster.vowel, series), Some(other))
+                }
+                BaseKind::IndependentVowel => {
+                    let reading = VowelReading {
+                        ungegn: independent_vowel(parts.base, Scheme::Ungegn).unwrap_or("").to_string(),
+                        ipa: independent_vowel(parts.base, Scheme::Ipa).unwrap_or("").to_string(),
+                    };
+                    (None, None, reading, None)
+                }
+            };
+            Some(SyllableSeries {
+                text: span.text,
+                byte_start: span.byte_start,
+                byte_end: span.byte_end,
+                char_start: span.char_start,
+                char_end: span.char_end,
+                base: parts.base,
+                base_kind: parts.base_kind,
+                subscripts: parts.subscripts,
+                shifter: parts.shifter,
+                series,
+                series_source,
+                vowel: cluster.vowel,
+                reading,
+                other_series_reading,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Row = (String, Option<Series>, Option<SeriesSource>, String);
+
+    fn series_of_syllables(text: &str) -> Vec<Row> {
+        analyze_series(text)
+            .into_iter()
+            .map(|s| (s.text, s.series, s.series_source, s.reading.ipa))
+            .collect()
+    }
+
+    fn row(text: &str, series: Series, source: SeriesSource, ipa: &str) -> Row {
+        (text.to_string(), Some(series), Some(source), ipa.to_string())
+    }
+
+    #[test]
+    fn series_of_base_subscript_and_shifter() {
+        use Series::*;
+        use SeriesSource::*;
+        assert_eq!(series_of_syllables("កា"), [row("កា", A, Base, "aː")]);
+        assert_eq!(series_of_syllables("គា"), [row("គា", O, Base, "iə")]);
+        // ម is a sonorant, the cluster keeps the series of ឆ
+        assert_eq!(series_of_syllables("ឆ្មា"), [row("ឆ្មា", A, Base, "aː")]);
+        // ព is not, ស្ពា reads in the o-series
+        assert_eq!(series_of_syllables("ស្ពាន"), [row("ស្ពា", O, Subscript, "iə"), row("ន", O, Base, "ɔː")]);
+        assert_eq!(series_of_syllables("ស៊ី"), [row("ស៊ី", O, Shifter, "iː")]);
+        assert_eq!(series_of_syllables("ម៉ោង")[0], row("ម៉ោ", A, Shifter, "aə"));
+    }
+
+    #[test]
+    fn both_readings_and_independent_vowels() {
+        let [s] = analyze_series("ពៅ").try_into().unwrap();
+        assert_eq!(s.base, 'ព');
+        assert_eq!(s.vowel, "ៅ");
+        assert_eq!(s.reading, VowelReading { ungegn: "ŏu".into(), ipa: "ɨw".into() });
+        assert_eq!(s.other_series_reading, Some(VowelReading { ungegn: "au".into(), ipa: "aw".into() }));
+
+        let [s] = analyze_series("ឥ").try_into().unwrap();
+        assert_eq!((s.series, s.series_source, s.other_series_reading), (None, None, None));
+        assert_eq!(s.reading.ipa, "ʔe");
+
+        // ំ is part of the vowel, text between syllables is skipped
+        let syllables = analyze_series("ខ្ញុំ ok");
+        assert_eq!(syllables.len(), 1);
+        assert_eq!(syllables[0].vowel, "ុំ");
+        assert_eq!(syllables[0].subscripts, ['ញ']);
+    }
+}
@@ -0,0 +1,436 @@
+// Orthographic syllables of Khmer text, natively (the webview splits by the same units in
+// khmer_parse_tokenize.ts): a base, consonant or independent vowel, with everything written on it:
+// robat, coeng subscripts, shifter, dependent vowel and signs.
+// Text between syllables is split into runs of spaces, digits, punctuation and other chars.
+// Marks are taken in any order, the segmenter doesn't judge spelling, it only says what belongs to which base
+
+use serde::Serialize;
+use super::chars::{
+    COENG, is_base, is_consonant, is_dependent_vowel, is_digit, is_inherent_vowel, is_punctuation, is_register_shifter,
+    is_sign,
+};
+
+const ROBAT: char = '\u{17CC}'; // ៌
+const ZWSP: char = '\u{200B}';
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanKind {
+    Syllable,
+    // whitespace and zero width spaces
+    Space,
+    // Khmer or ASCII digits
+    Digits,
+    // ។ ៕ ៖ ៗ ...
+    Punctuation,
+    // vowel signs, coeng or diacritics with no base before them
+    StrayMarks,
+    // anything else: Latin, symbols, ...
+    Other,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseKind {
+    Consonant,
+    IndependentVowel,
+}
+
+/// What a syllable is made of, in the order the chars are written
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SyllableParts {
+    pub base: char,
+    pub base_kind: BaseKind,
+    pub robat: bool,
+    // consonants written under the base, without their coeng
+    pub subscripts: Vec<char>,
+    // ៉ or ៊
+    pub shifter: Option<char>,
+    // one in well-formed text
+    pub vowels: Vec<char>,
+    // ំ ះ ់ ៍ ..., and whatever else sits on the base: a coeng with no consonant after it,
+    // a second robat or shifter, ZWJ / ZWNJ
+    pub signs: Vec<char>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TextSpan {
+    pub kind: SpanKind,
+    pub text: String,
+    // offsets into the input, end exclusive: bytes of its UTF-8, and chars (code points)
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+    // Some for syllables only
+    pub parts: Option<SyllableParts>,
+}
+
+fn is_syllable_mark(c: char) -> bool {
+    is_dependent_vowel(c) || is_inherent_vowel(c) || is_sign(c) || c == COENG
+}
+
+/// Kind of a run a char outside of syllables belongs to
+fn run_kind(c: char) -> SpanKind {
+    if c.is_whitespace() || c == ZWSP {
+        SpanKind::Space
+    } else if is_digit(c) || c.is_ascii_digit() {
+        SpanKind::Digits
+    } else if is_punctuation(c) {
+        SpanKind::Punctuation
+    } else if is_syllable_mark(c) {
+        SpanKind::StrayMarks
+    } else {
+        SpanKind::Other
+    }
+}
+
+/// Reads the marks after `base` (at `chars[0]`), returns the parts and how many chars the syllable has
+fn read_syllable(chars: &[char]) -> (SyllableParts, usize) {
+    let base = chars[0];
+    let mut parts = SyllableParts {
+        base,
+        base_kind: if is_consonant(base) { BaseKind::Consonant } else { BaseKind::IndependentVowel },
+        robat: false,
+        subscripts: Vec::new(),
+        shifter: None,
+        vowels: Vec::new(),
+        signs: Vec::new(),
+    };
+
+    let mut i = 1;
+    while let Some(&c) = chars.get(i) {
+        match c {
+            COENG => match chars.get(i + 1) {
+                Some(&sub) if is_consonant(sub) => {
+                    parts.subscripts.push(sub);
+                    i += 1;
+                }
+                _ => parts.signs.push(c),
+            },
+            ROBAT if !parts.robat => parts.robat = true,
+            c if is_register_shifter(c) && parts.shifter.is_none() => parts.shifter = Some(c),
+            c if is_dependent_vowel(c) || is_inherent_vowel(c) => parts.vowels.push(c),
+            c if is_sign(c) || c == ZWJ || c == ZWNJ => parts.signs.push(c),
+            _ => break,
+        }
+        i += 1;
+    }
+
+    (parts, i)
+}
+
+/// Splits `text` into syllables and runs of other chars. The spans are contiguous and cover the whole text
+pub fn segment_syllables(text: &str) -> Vec<TextSpan> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans: Vec<TextSpan> = Vec::new();
+    let mut char_start = 0;
+    let mut byte_start = 0;
+
+    while char_start < chars.len() {
+        let c = chars[char_start];
+        let (kind, len, parts) = if is_base(c) {
+            let (parts, len) = read_syllable(&chars[char_start..]);
+            (SpanKind::Syllable, len, Some(parts))
+        } else {
+            let kind = run_kind(c);
+            let len = chars[char_start..].iter().take_while(|&&next| !is_base(next) && run_kind(next) == kind).count();
+            (kind, len, None)
+        };
+
+        let char_end = char_start + len;
+        let byte_end = byte_start + chars[char_start..char_end].iter().map(|c| c.len_utf8()).sum::<usize>();
+        spans.push(TextSpan {
+            kind,
+            text: text[byte_start..byte_end].to_string(),
+            byte_start,
+            byte_end,
+            char_start,
+            char_end,
+            parts,
+        });
+        char_start = char_end;
+        byte_start = byte_end;
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<String> {
+        segment_syllables(text).into_iter().map(|s| s.text).collect()
+    }
+
+    fn kinds(text: &str) -> Vec<SpanKind> {
+        segment_syllables(text).into_iter().map(|s| s.kind).collect()
+    }
+
+    fn only_syllable(text: &str) -> SyllableParts {
+        let spans = segment_syllables(text);
+        assert_eq!(spans.len(), 1, "{} should be one syllable, got {:?}", text, spans);
+        assert_eq!(spans[0].kind, SpanKind::Syllable);
+        spans[0].parts.clone().unwrap()
+    }
+
+    // every span is cut at the right place and the offsets agree with the text
+    fn assert_spans_cover(text: &str) {
+        let spans = segment_syllables(text);
+        let mut byte = 0;
+        let mut char = 0;
+        for span in &spans {
+            assert_eq!(span.byte_start, byte, "{:?}", spans);
+            assert_eq!(span.char_start, char, "{:?}", spans);
+            assert_eq!(&text[span.byte_start..span.byte_end], span.text);
+            assert_eq!(span.char_end - span.char_start, span.text.chars().count());
+            assert!(!span.text.is_empty());
+            assert_eq!(span.parts.is_some(), span.kind == SpanKind::Syllable);
+            byte = span.byte_end;
+            char = span.char_end;
+        }
+        assert_eq!(byte, text.len());
+        assert_eq!(char, text.chars().count());
+    }
+
+    // words from the dictionary, one per line: the syllables split with "|"
+    const CORPUS: &[&str] = &[
+        "ក",
+        "កា",
+        "កា|រ|ងា|រ",
+        "ឆ្មា",
+        "ឆ្កែ",
+        "ឈ្មោះ",
+        "ស្រ|ឡា|ញ់",
+        "ស្ត្រី",
+        "ក្រុ|ម",
+        "ប៉ុ|ន្តែ",
+        "ស៊ី",
+        "ម៉ឺ|ន",
+        "អោ|យ",
+        "ឱ្យ",
+        "ព្រះ",
+        "ធ|ម៌",
+        "ក|ម្ពុ|ជា",
+        "ខ្មែ|រ",
+        "ភា|សា|ខ្មែ|រ",
+        "សួ|ស្តី",
+        "អ|រ|គុ|ណ",
+        "ឯ|ក|សា|រ",
+        "ឥ|ឡូ|វ",
+        "រៀ|ន",
+        "ញ៉ាំ",
+        "ហ្វ៊ី|ល",
+        "ហ្គា|ស",
+        "ខ្ញុំ",
+        "ច|ង្ក្រា|ន",
+        "ស|ង្ឃ",
+        "សា|ស្ត្រា|ចា|រ្យ",
+        "ព្រ|ហ្ម",
+        "ឬ",
+        "ឮ",
+        "ឪ|ពុ|ក",
+        "ឧ|ទា|ហ|រ|ណ៍",
+        "ប្រ|ទេ|ស",
+        "ច|ម្រៀ|ង",
+        "សៀ|វ|ភៅ",
+        "ទឹ|ក",
+        "ពោ|ធិ៍",
+        "ក៏",
+        "ក៎",
+        "ញុំា",
+        "លោ|ក",
+        "ខ្លាំ|ង",
+        "ស្កា",
+        "កំ|ពុ|ង",
+        "ន|គ|រ",
+        "អ្ន|ក",
+    ];
+
+    #[test]
+    fn corpus_syllables() {
+        for line in CORPUS {
+            let word = line.replace('|', "");
+            let expected: Vec<&str> = line.split('|').collect();
+            assert_eq!(texts(&word), expected, "{}", word);
+            assert!(kinds(&word).iter().all(|k| *k == SpanKind::Syllable), "{}", word);
+            assert_spans_cover(&word);
+        }
+    }
+
+    #[test]
+    fn every_consonant_is_a_syllable_base() {
+        for c in '\u{1780}'..='\u{17A2}' {
+            let parts = only_syllable(&c.to_string());
+            assert_eq!(parts.base, c);
+            assert_eq!(parts.base_kind, BaseKind::Consonant);
+            assert!(parts.subscripts.is_empty() && parts.vowels.is_empty() && parts.signs.is_empty());
+        }
+    }
+
+    #[test]
+    fn every_independent_vowel_is_a_syllable_base() {
+        for c in '\u{17A3}'..='\u{17B3}' {
+            let parts = only_syllable(&c.to_string());
+            assert_eq!(parts.base, c);
+            assert_eq!(parts.base_kind, BaseKind::IndependentVowel);
+        }
+    }
+
+    #[test]
+    fn every_consonant_can_be_a_subscript() {
+        for sub in '\u{1780}'..='\u{17A2}' {
+            let text = format!("ក{}{}", COENG, sub);
+            assert_eq!(only_syllable(&text).subscripts, vec![sub], "{}", text);
+        }
+    }
+
+    #[test]
+    fn every_dependent_vowel_attaches_to_the_base() {
+        for v in ('\u{17B4}'..='\u{17C5}').filter(|&v| is_dependent_vowel(v) || is_inherent_vowel(v)) {
+            let text = format!("ក{}", v);
+            assert_eq!(only_syllable(&text).vowels, vec![v], "{}", text);
+        }
+    }
+
+    #[test]
+    fn every_sign_attaches_to_the_base() {
+        for sign in ('\u{17C6}'..='\u{17DD}').filter(|&c| is_sign(c)) {
+            let text = format!("ក{}", sign);
+            let parts = only_syllable(&text);
+            match sign {
+                ROBAT => assert!(parts.robat),
+                '\u{17C9}' | '\u{17CA}' => assert_eq!(parts.shifter, Some(sign)),
+                _ => assert_eq!(parts.signs, vec![sign], "{}", text),
+            }
+        }
+    }
+
+    #[test]
+    fn parts_of_a_full_syllable() {
+        // base, two subscripts, vowel, sign
+        assert_eq!(
+            only_syllable("ស្ត្រី"),
+            SyllableParts {
+                base: 'ស',
+                base_kind: BaseKind::Consonant,
+                robat: false,
+                subscripts: vec!['ត', 'រ'],
+                shifter: None,
+                vowels: vec!['ី'],
+                signs: vec![],
+            }
+        );
+        assert_eq!(
+            only_syllable("ញ៉ាំ"),
+            SyllableParts {
+                base: 'ញ',
+                base_kind: BaseKind::Consonant,
+                robat: false,
+                subscripts: vec![],
+                shifter: Some('៉'),
+                vowels: vec!['ា'],
+                signs: vec!['ំ'],
+            }
+        );
+        assert_eq!(
+            only_syllable("ម៌"),
+            SyllableParts {
+                base: 'ម',
+                base_kind: BaseKind::Consonant,
+                robat: true,
+                subscripts: vec![],
+                shifter: None,
+                vowels: vec![],
+                signs: vec![],
+            }
+        );
+        // subscript on an independent vowel (ឱ្យ)
+        let parts = only_syllable("ឱ្យ");
+        assert_eq!(parts.base_kind, BaseKind::IndependentVowel);
+        assert_eq!(parts.subscripts, vec!['យ']);
+    }
+
+    #[test]
+    fn malformed_marks_stay_on_their_base() {
+        // a second vowel, a doubled sign, marks before the subscript: all still one syllable
+        assert_eq!(only_syllable("កាា").vowels, vec!['ា', 'ា']);
+        assert_eq!(only_syllable("ក់់").signs, vec!['់', '់']);
+        let parts = only_syllable("កា្រ");
+        assert_eq!(parts.vowels, vec!['ា']);
+        assert_eq!(parts.subscripts, vec!['រ']);
+        // a second shifter or robat is kept as a sign
+        assert_eq!(only_syllable("ស៊៊").signs, vec!['៊']);
+        assert_eq!(only_syllable("ក៌៌").signs, vec!['៌']);
+        // a coeng with nothing to subscript
+        assert_eq!(only_syllable("ក្").signs, vec![COENG]);
+        assert_eq!(only_syllable("ក្្ក").subscripts, vec!['ក']);
+        assert_eq!(only_syllable("ក្្ក").signs, vec![COENG]);
+        // joiners are part of the syllable
+        assert_eq!(only_syllable("ក\u{200D}").signs, vec![ZWJ]);
+    }
+
+    #[test]
+    fn marks_without_a_base_are_stray() {
+        assert_eq!(kinds("ាំ"), vec![SpanKind::StrayMarks]);
+        assert_eq!(texts("ាំក"), vec!["ាំ", "ក"]);
+        assert_eq!(kinds(" ្ក"), vec![SpanKind::Space, SpanKind::StrayMarks, SpanKind::Syllable]);
+        assert_eq!(kinds("a\u{17D2}"), vec![SpanKind::Other, SpanKind::StrayMarks]);
+        assert_eq!(texts("abក"), vec!["ab", "ក"]);
+        assert_spans_cover("ាំក ្ក");
+    }
+
+    #[test]
+    fn runs_between_syllables() {
+        let text = "ខ្ញុំ  ទៅ\u{200B}ផ្សារ ១២៣ 45។ hello, ៗ";
+        assert_eq!(
+            segment_syllables(text).into_iter().map(|s| (s.kind, s.text)).collect::<Vec<_>>(),
+            vec![
+                (SpanKind::Syllable, "ខ្ញុំ".to_string()),
+                (SpanKind::Space, "  ".to_string()),
+                (SpanKind::Syllable, "ទៅ".to_string()),
+                (SpanKind::Space, "\u{200B}".to_string()),
+                (SpanKind::Syllable, "ផ្សា".to_string()),
+                (SpanKind::Syllable, "រ".to_string()),
+                (SpanKind::Space, " ".to_string()),
+                (SpanKind::Digits, "១២៣".to_string()),
+                (SpanKind::Space, " ".to_string()),
+                (SpanKind::Digits, "45".to_string()),
+                (SpanKind::Punctuation, "។".to_string()),
+                (SpanKind::Space, " ".to_string()),
+                (SpanKind::Other, "hello,".to_string()),
+                (SpanKind::Space, " ".to_string()),
+                (SpanKind::Punctuation, "ៗ".to_string()),
+            ]
+        );
+        assert_spans_cover(text);
+    }
+
+    #[test]
+    fn byte_and_char_offsets() {
+        // Khmer chars are 3 bytes, ASCII 1, the emoji 4
+        let spans = segment_syllables("ab😀ក្រុង");
+        let offsets: Vec<_> = spans.iter().map(|s| (s.byte_start, s.byte_end, s.char_start, s.char_end)).collect();
+        assert_eq!(offsets, vec![(0, 6, 0, 3), (6, 18, 3, 7), (18, 21, 7, 8)]);
+        assert_eq!(spans[1].text, "ក្រុ");
+        assert_eq!(spans[1].parts.as_ref().unwrap().subscripts, vec!['រ']);
+        assert_eq!(spans[1].parts.as_ref().unwrap().vowels, vec!['ុ']);
+    }
+
+    #[test]
+    fn every_khmer_char_in_every_position_is_covered() {
+        let block: Vec<char> = ('\u{1780}'..='\u{17FF}').chain('\u{19E0}'..='\u{19FF}').collect();
+        for &a in &block {
+            for &b in &block {
+                assert_spans_cover(&format!("{}{}", a, b));
+                assert_spans_cover(&format!("ក{}{}x", a, b));
+            }
+        }
+    }
+
+    #[test]
+    fn empty_text() {
+        assert!(segment_syllables("").is_empty());
+    }
+}
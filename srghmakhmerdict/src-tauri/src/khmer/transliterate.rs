@@ -0,0 +1,443 @@
+// Rule-based Latin spellings of Khmer words, for words no dictionary gives a pronunciation of:
+// - UNGEGN: the romanization of place names and most textbooks, reads each syllable (ស្រឡាញ់ -> srâlănh)
+// - ALA-LC: library transliteration, letter by letter, the series isn't read (ស្រឡាញ់ -> sraḷāñ)
+// - IPA: broad transcription of standard Khmer (ស្រឡាញ់ -> srɑlaɲ)
+// All three split the word into orthographic syllables (syllables.rs), then decide which bare consonants
+// are the finals of the syllable before them. Loanwords and minor syllables aren't special-cased,
+// so the result is what the spelling says, which is not always how the word is said
+
+use serde::{Deserialize, Serialize};
+use super::chars::is_punctuation;
+use super::series::{Series, cluster_series};
+use super::syllables::{BaseKind, SpanKind, SyllableParts, segment_syllables};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Scheme {
+    Ungegn,
+    AlaLc,
+    Ipa,
+}
+
+const NIKAHIT: char = '\u{17C6}'; // ំ
+const REAHMUK: char = '\u{17C7}'; // ះ
+const MUUSIKATOAN: char = '\u{17C9}'; // ៉
+const TRIISAP: char = '\u{17CA}'; // ៊
+const BANTOC: char = '\u{17CB}'; // ់
+
+struct Consonant {
+    ungegn: &'static str,
+    ala_lc: &'static str,
+    ipa: &'static str,
+    // as the final of a syllable
+    ipa_final: &'static str,
+}
+
+const fn c(ungegn: &'static str, ala_lc: &'static str, ipa: &'static str, ipa_final: &'static str) -> Consonant {
+    Consonant { ungegn, ala_lc, ipa, ipa_final }
+}
+
+fn consonant(ch: char) -> Option<Consonant> {
+    let def = match ch {
+        'ក' => c("k", "k", "k", "k"),
+        'ខ' => c("kh", "kh", "kʰ", "k"),
+        'គ' => c("k", "g", "k", "k"),
+        'ឃ' => c("kh", "gh", "kʰ", "k"),
+        'ង' => c("ng", "ṅ", "ŋ", "ŋ"),
+        'ច' => c("ch", "c", "c", "c"),
+        'ឆ' => c("chh", "ch", "cʰ", "c"),
+        'ជ' => c("ch", "j", "c", "c"),
+        'ឈ' => c("chh", "jh", "cʰ", "c"),
+        'ញ' => c("nh", "ñ", "ɲ", "ɲ"),
+        'ដ' => c("d", "ṭ", "ɗ", "t"),
+        'ឋ' => c("th", "ṭh", "tʰ", "t"),
+        'ឌ' => c("d", "ḍ", "ɗ", "t"),
+        'ឍ' => c("th", "ḍh", "tʰ", "t"),
+        'ណ' => c("n", "ṇ", "n", "n"),
+        'ត' => c("t", "t", "t", "t"),
+        'ថ' => c("th", "th", "tʰ", "t"),
+        'ទ' => c("t", "d", "t", "t"),
+        'ធ' => c("th", "dh", "tʰ", "t"),
+        'ន' => c("n", "n", "n", "n"),
+        'ប' => c("b", "p", "ɓ", "p"),
+        'ផ' => c("ph", "ph", "pʰ", "p"),
+        'ព' => c("p", "b", "p", "p"),
+        'ភ' => c("ph", "bh", "pʰ", "p"),
+        'ម' => c("m", "m", "m", "m"),
+        'យ' => c("y", "y", "j", "j"),
+        // a final r is silent
+        'រ' => c("r", "r", "r", ""),
+        'ល' => c("l", "l", "l", "l"),
+        'វ' => c("v", "v", "ʋ", "w"),
+        'ឝ' => c("s", "ś", "s", "h"),
+        'ឞ' => c("s", "ṣ", "s", "h"),
+        'ស' => c("s", "s", "s", "h"),
+        'ហ' => c("h", "h", "h", "h"),
+        'ឡ' => c("l", "ḷ", "l", "l"),
+        'អ' => c("", "ʼ", "ʔ", ""),
+        _ => return None,
+    };
+    Some(def)
+}
+
+struct Vowel {
+    // dependent vowels plus ំ / ះ, "" for the inherent vowel
+    written: &'static str,
+    ungegn: (&'static str, &'static str),
+    ipa: (&'static str, &'static str),
+}
+
+// readings in the a-series and in the o-series
+const VOWELS: &[Vowel] = &[
+    Vowel { written: "", ungegn: ("â", "ô"), ipa: ("ɑː", "ɔː") },
+    Vowel { written: "ា", ungegn: ("a", "éa"), ipa: ("aː", "iə") },
+    Vowel { written: "ិ", ungegn: ("ĕ", "ĭ"), ipa: ("e", "i") },
+    Vowel { written: "ី", ungegn: ("ei", "i"), ipa: ("əj", "iː") },
+    Vowel { written: "ឹ", ungegn: ("œ", "œ"), ipa: ("ə", "ɨ") },
+    Vowel { written: "ឺ", ungegn: ("œ", "œ"), ipa: ("əɨ", "ɨː") },
+    Vowel { written: "ុ", ungegn: ("ŏ", "ŭ"), ipa: ("o", "u") },
+    Vowel { written: "ូ", ungegn: ("o", "u"), ipa: ("ou", "uː") },
+    Vowel { written: "ួ", ungegn: ("uŏ", "uŏ"), ipa: ("uə", "uə") },
+    Vowel { written: "ើ", ungegn: ("aeu", "eu"), ipa: ("aə", "əː") },
+    Vowel { written: "ឿ", ungegn: ("eua", "eua"), ipa: ("ɨə", "ɨə") },
+    Vowel { written: "ៀ", ungegn: ("iĕ", "iĕ"), ipa: ("iə", "iə") },
+    Vowel { written: "េ", ungegn: ("é", "é"), ipa: ("eː", "eː") },
+    Vowel { written: "ែ", ungegn: ("ê", "ê"), ipa: ("ae", "ɛː") },
+    Vowel { written: "ៃ", ungegn: ("ai", "ey"), ipa: ("aj", "ɨj") },
+    Vowel { written: "ោ", ungegn: ("aô", "ô"), ipa: ("aə", "oː") },
+    Vowel { written: "ៅ", ungegn: ("au", "ŏu"), ipa: ("aw", "ɨw") },
+    Vowel { written: "ុំ", ungegn: ("ŏm", "ŭm"), ipa: ("om", "um") },
+    Vowel { written: "ំ", ungegn: ("âm", "um"), ipa: ("ɑm", "um") },
+    Vowel { written: "ាំ", ungegn: ("ăm", "ŏâm"), ipa: ("am", "oəm") },
+    Vowel { written: "ះ", ungegn: ("ăh", "eăh"), ipa: ("ah", "eəh") },
+    Vowel { written: "ាះ", ungegn: ("ăh", "eăh"), ipa: ("ah", "eəh") },
+    Vowel { written: "ិះ", ungegn: ("ĕh", "ĭh"), ipa: ("eh", "ih") },
+    Vowel { written: "ុះ", ungegn: ("ŏh", "ŭh"), ipa: ("oh", "uh") },
+    Vowel { written: "េះ", ungegn: ("éh", "éh"), ipa: ("eh", "eh") },
+    Vowel { written: "ោះ", ungegn: ("aŏh", "uŏh"), ipa: ("ɑh", "uəh") },
+];
+
+fn in_series<'a>(readings: (&'a str, &'a str), series: Series) -> &'a str {
+    match series {
+        Series::A => readings.0,
+        Series::O => readings.1,
+    }
+}
+
+fn vowel(written: &str, series: Series, scheme: Scheme) -> Option<&'static str> {
+    let vowel = VOWELS.iter().find(|v| v.written == written)?;
+    match scheme {
+        Scheme::Ungegn => Some(in_series(vowel.ungegn, series)),
+        Scheme::Ipa => Some(in_series(vowel.ipa, series)),
+        Scheme::AlaLc => None,
+    }
+}
+
+/// UNGEGN or IPA reading of a written vowel, sign by sign if the combination is unknown
+pub(super) fn vowel_reading(written: &str, series: Series, scheme: Scheme) -> String {
+    match vowel(written, series, scheme) {
+        Some(reading) => reading.to_string(),
+        None => written.chars().map(|c| vowel(c.encode_utf8(&mut [0; 4]), series, scheme).unwrap_or("")).collect(),
+    }
+}
+
+/// ់ over the final shortens the vowel
+fn shortened_vowel(written: &str, series: Series, scheme: Scheme) -> Option<&'static str> {
+    let readings = match (written, scheme) {
+        ("", Scheme::Ungegn) => ("a", "ŏă"),
+        ("ា", Scheme::Ungegn) => ("ă", "ŏă"),
+        ("" | "ា", Scheme::Ipa) => ("a", "oə"),
+        _ => return None,
+    };
+    Some(in_series(readings, series))
+}
+
+/// ALA-LC spells each vowel sign on its own, whatever the series
+fn ala_lc_vowel_sign(c: char) -> &'static str {
+    match c {
+        'ា' => "ā",
+        'ិ' => "i",
+        'ី' => "ī",
+        'ឹ' => "ẏ",
+        'ឺ' => "ȳ",
+        'ុ' => "u",
+        'ូ' => "ū",
+        'ួ' => "ua",
+        'ើ' => "oe",
+        'ឿ' => "ẏa",
+        'ៀ' => "ia",
+        'េ' => "e",
+        'ែ' => "ae",
+        'ៃ' => "ai",
+        'ោ' => "o",
+        'ៅ' => "au",
+        NIKAHIT => "ṃ",
+        REAHMUK => "ḥ",
+        _ => "",
+    }
+}
+
+pub(super) fn independent_vowel(c: char, scheme: Scheme) -> Option<&'static str> {
+    // UNGEGN, ALA-LC, IPA
+    let (ungegn, ala_lc, ipa) = match c {
+        'ឣ' => ("â", "a", "ʔɑː"),
+        'ឤ' => ("a", "ā", "ʔaː"),
+        'ឥ' => ("ĕ", "i", "ʔe"),
+        'ឦ' => ("ei", "ī", "ʔəj"),
+        'ឧ' => ("ŏ", "u", "ʔo"),
+        'ឩ' => ("ŏu", "ū", "ʔou"),
+        'ឪ' | 'ឳ' => ("âu", "au", "ʔɨw"),
+        'ឫ' => ("rœ", "ṛ", "rɨ"),
+        'ឬ' => ("rœ", "ṝ", "rɨː"),
+        'ឭ' => ("lœ", "ḷ", "lɨ"),
+        'ឮ' => ("lœ", "ḹ", "lɨː"),
+        'ឯ' => ("ê", "e", "ʔae"),
+        'ឰ' => ("ai", "ai", "ʔaj"),
+        'ឱ' | 'ឲ' => ("aô", "o", "ʔaə"),
+        _ => return None,
+    };
+    Some(match scheme {
+        Scheme::Ungegn => ungegn,
+        Scheme::AlaLc => ala_lc,
+        Scheme::Ipa => ipa,
+    })
+}
+
+/// An orthographic syllable as the readings need it: base consonant with its subscripts, shifter and written vowel;
+/// or an independent vowel
+#[derive(Default, Debug)]
+pub(super) struct Cluster {
+    // base and subscripts; only the subscripts after an independent vowel
+    pub(super) consonants: Vec<char>,
+    independent: Option<char>,
+    pub(super) shifter: Option<char>,
+    robat: bool,
+    // dependent vowel signs plus ំ / ះ
+    pub(super) vowel: String,
+    bantoc: bool,
+}
+
+impl From<&SyllableParts> for Cluster {
+    fn from(parts: &SyllableParts) -> Self {
+        let (consonants, independent) = match parts.base_kind {
+            BaseKind::Consonant => (std::iter::once(parts.base).chain(parts.subscripts.iter().copied()).collect(), None),
+            BaseKind::IndependentVowel => (parts.subscripts.clone(), Some(parts.base)),
+        };
+        let mut vowel: String = parts.vowels.iter().collect();
+        vowel.extend(parts.signs.iter().filter(|&&c| c == NIKAHIT || c == REAHMUK));
+        // other signs (toandakhiat, ...) don't change the reading
+        Cluster {
+            consonants,
+            independent,
+            shifter: parts.shifter,
+            robat: parts.robat,
+            vowel,
+            bantoc: parts.signs.contains(&BANTOC),
+        }
+    }
+}
+
+impl Cluster {
+    // a lone consonant with no vowel, can be the final of the syllable before it
+    fn is_bare(&self) -> bool {
+        self.independent.is_none() && self.consonants.len() == 1 && self.vowel.is_empty()
+    }
+
+    // ំ and ះ end the syllable, nothing can follow as its final
+    fn is_closed(&self) -> bool {
+        self.vowel.contains([NIKAHIT, REAHMUK])
+    }
+
+    fn series(&self) -> Series {
+        cluster_series(&self.consonants, self.shifter).map_or(Series::A, |(series, _)| series)
+    }
+
+    fn onset(&self, scheme: Scheme, out: &mut String) {
+        // ALA-LC writes the robat as the r it was, the others read it as silent (ធម៌)
+        if scheme == Scheme::AlaLc && self.robat {
+            out.push('r');
+        }
+        for (i, &c) in self.consonants.iter().enumerate() {
+            let Some(consonant) = consonant(c) else { continue };
+            // ប៉ is p
+            let is_p = i == 0 && c == 'ប' && self.shifter == Some(MUUSIKATOAN);
+            out.push_str(match scheme {
+                Scheme::Ungegn if is_p => "p",
+                Scheme::Ipa if is_p => "p",
+                Scheme::Ungegn => consonant.ungegn,
+                Scheme::AlaLc => consonant.ala_lc,
+                Scheme::Ipa => consonant.ipa,
+            });
+        }
+        if scheme == Scheme::AlaLc {
+            match self.shifter {
+                Some(MUUSIKATOAN) => out.push('ʺ'),
+                Some(TRIISAP) => out.push('ʹ'),
+                _ => {}
+            }
+        }
+    }
+
+    // spelled as the final of the syllable before it
+    fn coda(&self, scheme: Scheme, out: &mut String) {
+        match (scheme, self.consonants.first().and_then(|&c| consonant(c))) {
+            (Scheme::Ipa, Some(consonant)) => out.push_str(consonant.ipa_final),
+            _ => self.onset(scheme, out),
+        }
+    }
+}
+
+struct Syllable<'a> {
+    onset: &'a Cluster,
+    coda: Option<&'a Cluster>,
+}
+
+fn push_vowel(s: &Syllable, is_last: bool, scheme: Scheme, out: &mut String) {
+    let written = s.onset.vowel.as_str();
+    if scheme == Scheme::AlaLc {
+        if written.is_empty() {
+            out.push('a');
+        }
+        out.extend(written.chars().map(ala_lc_vowel_sign));
+        return;
+    }
+    let series = s.onset.series();
+    // the inherent vowel of an open syllable before the stressed one is short (ស្រឡាញ់ srɑlaɲ)
+    if scheme == Scheme::Ipa && written.is_empty() && s.coda.is_none() && !is_last {
+        out.push_str(in_series(("ɑ", "ɔ"), series));
+        return;
+    }
+    let shortened = s.coda.filter(|coda| coda.bantoc).and_then(|_| shortened_vowel(written, series, scheme));
+    match shortened {
+        Some(reading) => out.push_str(reading),
+        None => out.push_str(&vowel_reading(written, series, scheme)),
+    }
+}
+
+fn push_syllable(s: &Syllable, is_last: bool, scheme: Scheme, out: &mut String) {
+    if let Some(independent) = s.onset.independent {
+        out.push_str(independent_vowel(independent, scheme).unwrap_or(""));
+        // ឱ្យ
+        s.onset.onset(scheme, out);
+    } else {
+        s.onset.onset(scheme, out);
+        push_vowel(s, is_last, scheme, out);
+    }
+    if let Some(coda) = s.coda {
+        coda.coda(scheme, out);
+    }
+}
+
+fn push_clusters(clusters: &[Cluster], scheme: Scheme, out: &mut String) {
+    let mut syllables: Vec<Syllable> = Vec::new();
+
+    for (i, cluster) in clusters.iter().enumerate() {
+        let next_is_bare = clusters.get(i + 1).is_some_and(|next| next.is_bare());
+        let takes_as_coda = cluster.is_bare()
+            && syllables.last().is_some_and(|prev| {
+                prev.coda.is_none()
+                    && !prev.onset.is_closed()
+                    // ក|ម|ល: ម starts a syllable so that ល can close it
+                    && (!prev.onset.vowel.is_empty() || prev.onset.independent.is_some() || !next_is_bare)
+            });
+
+        match syllables.last_mut() {
+            Some(prev) if takes_as_coda => prev.coda = Some(cluster),
+            _ => syllables.push(Syllable { onset: cluster, coda: None }),
+        }
+    }
+
+    for (i, s) in syllables.iter().enumerate() {
+        push_syllable(s, i + 1 == syllables.len(), scheme, out);
+    }
+}
+
+/// Latin spelling of `text` in `scheme`. Each run of Khmer syllables is read as one word;
+/// non-Khmer text is kept, Khmer digits become ASCII digits, Khmer punctuation becomes a space
+pub fn transliterate(text: &str, scheme: Scheme) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut run: Vec<Cluster> = Vec::new();
+
+    for span in segment_syllables(text) {
+        if let Some(parts) = &span.parts {
+            run.push(Cluster::from(parts));
+            continue;
+        }
+        push_clusters(&run, scheme, &mut out);
+        run.clear();
+        match span.kind {
+            // the marks have no base to be read with
+            SpanKind::StrayMarks => {}
+            _ => {
+                for c in span.text.chars() {
+                    match c {
+                        '\u{17E0}'..='\u{17E9}' => out.push(char::from(b'0' + (c as u32 - 0x17E0) as u8)),
+                        'ៗ' => {}
+                        c if is_punctuation(c) => out.push(' '),
+                        c => out.push(c),
+                    }
+                }
+            }
+        }
+    }
+    push_clusters(&run, scheme, &mut out);
+
+    out
+}
+
+/// A word in every scheme, shown as its pronunciation when the dictionaries have none
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct KhmerTransliteration {
+    pub ungegn: String,
+    pub ala_lc: String,
+    pub ipa: String,
+}
+
+pub fn transliterate_all(text: &str) -> KhmerTransliteration {
+    KhmerTransliteration {
+        ungegn: transliterate(text, Scheme::Ungegn),
+        ala_lc: transliterate(text, Scheme::AlaLc),
+        ipa: transliterate(text, Scheme::Ipa),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // word, UNGEGN, ALA-LC, IPA
+    const WORDS: &[(&str, &str, &str, &str)] = &[
+        ("ក", "kâ", "ka", "kɑː"),
+        ("កា", "ka", "kā", "kaː"),
+        ("គោ", "kô", "go", "koː"),
+        ("ឆ្មា", "chhma", "chmā", "cʰmaː"),
+        ("ឈ្មោះ", "chhmuŏh", "jhmoḥ", "cʰmuəh"),
+        ("ស្រឡាញ់", "srâlănh", "sraḷāñ", "srɑlaɲ"),
+        ("ការងារ", "karngéar", "kārṅār", "kaːŋiə"),
+        ("ខ្មែរ", "khmêr", "khmaer", "kʰmae"),
+        ("កម្ពុជា", "kâmpŭchéa", "kambujā", "kɑmpuciə"),
+        ("ភាសា", "phéasa", "bhāsā", "pʰiəsaː"),
+        ("ស៊ី", "si", "sʹī", "siː"),
+        ("ប៉ុន្តែ", "pŏntê", "pʺuntae", "pontae"),
+        ("ធម៌", "thôm", "dharm", "tʰɔːm"),
+        ("ទឹក", "tœk", "dẏk", "tɨk"),
+        ("រៀន", "riĕn", "rian", "riən"),
+        ("ឱ្យ", "aôy", "oy", "ʔaəj"),
+        ("អរគុណ", "ârkŭn", "ʼarguṇ", "ʔɑːkun"),
+    ];
+
+    #[test]
+    fn words_in_every_scheme() {
+        for &(word, ungegn, ala_lc, ipa) in WORDS {
+            let expected = KhmerTransliteration { ungegn: ungegn.into(), ala_lc: ala_lc.into(), ipa: ipa.into() };
+            assert_eq!(transliterate_all(word), expected, "{}", word);
+        }
+    }
+
+    #[test]
+    fn other_text_is_kept() {
+        assert_eq!(transliterate("ឆ្មា ១២។ cat", Scheme::Ungegn), "chhma 12  cat");
+        assert_eq!(transliterate("ឆ្មា\u{200B}ឆ្កែ", Scheme::Ipa), "cʰmaː\u{200B}cʰkae");
+        assert_eq!(transliterate("\u{17B6}ក", Scheme::AlaLc), "ka");
+        assert_eq!(transliterate("", Scheme::Ipa), "");
+    }
+}
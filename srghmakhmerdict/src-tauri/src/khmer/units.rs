@@ -0,0 +1,60 @@
+use super::chars::{COENG, is_combining, is_consonant};
+
+/// Splits a word into the pieces a typo changes as a whole: a subscript (coeng + consonant) is one unit,
+/// every other char (base, vowel sign, diacritic, non-Khmer char) is its own unit.
+/// So a missing coeng (ស្រ vs សរ) or two swapped vowel signs cost one edit, not two.
+pub fn split_edit_units(s: &str) -> Vec<&str> {
+    let mut units = Vec::with_capacity(s.len() / 3);
+    let mut iter = s.char_indices().peekable();
+
+    while let Some((start, c)) = iter.next() {
+        let end = match iter.peek() {
+            Some(&(next_start, next)) if c == COENG && is_consonant(next) => {
+                iter.next();
+                next_start + next.len_utf8()
+            }
+            _ => start + c.len_utf8(),
+        };
+        units.push(&s[start..end]);
+    }
+
+    units
+}
+
+/// Whether a unit of `split_edit_units` begins a new orthographic cluster (a base, or a non-Khmer letter),
+/// as opposed to a subscript, vowel sign or diacritic attached to the one before
+pub fn starts_cluster(unit: &str) -> bool {
+    unit.chars().next().is_some_and(|c| !is_combining(c) && !matches!(c, '\u{0300}'..='\u{036F}'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_distance::osa_distance;
+
+    #[test]
+    fn subscripts_are_one_unit() {
+        assert_eq!(split_edit_units("ស្រឡាញ់"), ["ស", "្រ", "ឡ", "ា", "ញ", "់"]);
+        assert_eq!(split_edit_units("ស្ត្រី"), ["ស", "្ត", "្រ", "ី"]);
+        // a coeng with no consonant after it is a unit of its own
+        assert_eq!(split_edit_units("ក្"), ["ក", "្"]);
+        assert_eq!(split_edit_units("ក្ា"), ["ក", "្", "ា"]);
+        assert_eq!(split_edit_units("ab"), ["a", "b"]);
+        assert!(split_edit_units("").is_empty());
+    }
+
+    #[test]
+    fn missing_coeng_and_swapped_signs_are_one_edit() {
+        let distance = |a: &str, b: &str| osa_distance(&split_edit_units(a), &split_edit_units(b));
+        assert_eq!(distance("ស្រ", "សរ"), 1.0);
+        assert_eq!(distance("ស្រ", "ស"), 1.0);
+        assert_eq!(distance("កុំ", "កំុ"), 1.0);
+    }
+
+    #[test]
+    fn clusters_start_at_bases() {
+        let starts: Vec<bool> = split_edit_units("ខ្មែរ a").into_iter().map(starts_cluster).collect();
+        assert_eq!(starts, [true, false, false, true, true, true]);
+        assert!(!starts_cluster("e\u{301}".get(1..).unwrap()));
+    }
+}
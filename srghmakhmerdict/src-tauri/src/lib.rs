@@ -7,8 +7,12 @@ mod app_state;
 mod constants;
 mod db;
 mod db_initialize;
+mod edit_distance;
+mod english;
 mod image_manager;
+mod khmer;
 mod protocols;
+mod russian;
 pub mod utils;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -75,6 +79,9 @@ pub fn run() {
             app.manage(AppState {
                 dict_pool: RwLock::new(None),
                 init_error: RwLock::new(None),
+                autocomplete: Default::default(),
+                inflight: Default::default(),
+                km_spell_lexicon: Default::default(),
             });
 
             let handle = app.handle().clone();
@@ -93,14 +100,46 @@ pub fn run() {
             db::dict::en::get_en_words,
             db::dict::km::get_km_words,
             db::dict::ru::get_ru_words,
+            db::dict::en::get_en_words_page,
+            db::dict::km::get_km_words_page,
+            db::dict::ru::get_ru_words_page,
+            db::dict::en::stream_en_words,
+            db::dict::km::stream_km_words,
+            db::dict::ru::stream_ru_words,
+            db::dict::autocomplete::autocomplete_km,
+            db::dict::autocomplete::autocomplete_en,
+            db::dict::autocomplete::autocomplete_ru,
+            db::dict::headword_pattern::search_km_pattern,
+            db::dict::headword_pattern::search_en_pattern,
+            db::dict::headword_pattern::search_ru_pattern,
+            db::dict::global_search::search_all,
+            db::dict::inflight::cancel_query,
             db::dict::km::get_word_detail_km,
             db::dict::en::get_word_detail_en,
             db::dict::ru::get_word_detail_ru,
+            db::dict::en::get_word_plain_text_en,
+            db::dict::km::get_word_plain_text_km,
+            db::dict::ru::get_word_plain_text_ru,
+            db::dict::suggest::get_word_suggestions_km,
+            db::dict::suggest::get_word_suggestions_en,
+            db::dict::suggest::get_word_suggestions_ru,
+            db::dict::km_roman::search_km_by_romanization,
+            db::dict::km_homophones::get_km_homophones,
+            db::dict::km_segment::segment_km_text,
+            db::dict::km_spellcheck::spellcheck_km_text,
+            khmer::commands::segment_khmer_syllables,
+            khmer::commands::transliterate_khmer,
+            khmer::commands::analyze_khmer_series,
+            khmer::commands::khmer_sort_keys,
+            khmer::commands::validate_khmer_orthography,
+            khmer::commands::repair_khmer_orthography,
             db::dict::en::search_en_content,
             db::dict::km::search_km_content,
             db::dict::ru::search_ru_content,
             db::dict::get_db_status,
             db::dict::en::get_en_km_com_images_ocr,
+            db::dict::ocr_search::get_en_km_com_image_words,
+            db::dict::ocr_search::search_en_km_com_ocr,
             db::dict::km::km_for_many_short_description_none_if_word_not_found,
             db::dict::km::km_for_many_short_description_throws_if_word_not_found,
             db::dict::km::km_for_many_full_details_none_if_word_not_found,
@@ -113,6 +152,9 @@ pub fn run() {
             db::dict::ru::ru_for_many_short_description_throws_if_word_not_found,
             db::dict::ru::ru_for_many_full_details_none_if_word_not_found,
             db::dict::ru::ru_for_many_full_details_throws_if_word_not_found,
+            db::dict::km::stream_km_full_details,
+            db::dict::en::stream_en_full_details,
+            db::dict::ru::stream_ru_full_details,
             // db::anki::get_all_anki_cards,
             // db::anki::save_anki_cards,
             image_manager::check_offline_images_status,
@@ -0,0 +1,158 @@
+// Russian forms -> dictionary forms: Snowball stemmer for regular inflection,
+// plus a short table of suppletive forms a stemmer can't relate to their lemma (шёл -> идти)
+
+use rust_stemmers::{Algorithm, Stemmer};
+use std::sync::LazyLock;
+
+static STEMMER: LazyLock<Stemmer> = LazyLock::new(|| Stemmer::create(Algorithm::Russian));
+
+/// Case and ё/е insensitive form of a Russian char
+pub fn fold_ru(c: char) -> char {
+    match c {
+        'ё' | 'Ё' => 'е',
+        _ => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+pub fn fold_ru_word(word: &str) -> String {
+    word.trim().chars().map(fold_ru).collect()
+}
+
+/// Snowball stem of the folded word ("кошки", "Кошку" -> "кошк")
+pub fn stem_ru(word: &str) -> String {
+    STEMMER.stem(&fold_ru_word(word)).into_owned()
+}
+
+/// What a content search term is searched as: its stem, so inflected forms match (кошки -> кошк).
+/// Phrases are kept as typed, their words wouldn't stay adjacent once stemmed.
+/// Stems shorter than the trigram index allows would be searched with a broad LIKE, so the folded word is used instead
+pub fn stem_ru_term(text: &str) -> String {
+    if text.contains(char::is_whitespace) {
+        return text.to_string();
+    }
+    let stem = stem_ru(text);
+    if stem.chars().count() >= 3 { stem } else { fold_ru_word(text) }
+}
+
+// (folded form, lemmas), the lemmas as they are spelled in ru_Dict
+const SUPPLETIVE_FORMS: &[(&str, &[&str])] = &[
+    ("шел", &["идти"]),
+    ("шла", &["идти"]),
+    ("шло", &["идти"]),
+    ("шли", &["идти"]),
+    ("пошел", &["пойти"]),
+    ("пошла", &["пойти"]),
+    ("пошло", &["пойти", "пошлый"]),
+    ("пошли", &["пойти", "послать"]),
+    ("ушел", &["уйти"]),
+    ("ушла", &["уйти"]),
+    ("ушли", &["уйти"]),
+    ("пришел", &["прийти"]),
+    ("пришла", &["прийти"]),
+    ("пришли", &["прийти", "прислать"]),
+    ("нашел", &["найти"]),
+    ("нашла", &["найти"]),
+    ("нашли", &["найти"]),
+    ("вышел", &["выйти"]),
+    ("вышла", &["выйти"]),
+    ("вышли", &["выйти", "выслать"]),
+    ("зашел", &["зайти"]),
+    ("вошел", &["войти"]),
+    ("прошел", &["пройти"]),
+    ("прошла", &["пройти"]),
+    ("прошли", &["пройти"]),
+    ("ел", &["есть"]),
+    ("ела", &["есть"]),
+    ("ели", &["есть", "ель"]),
+    ("ем", &["есть"]),
+    ("ешь", &["есть"]),
+    ("ест", &["есть"]),
+    ("едим", &["есть"]),
+    ("едят", &["есть"]),
+    ("был", &["быть"]),
+    ("была", &["быть"]),
+    ("было", &["быть"]),
+    ("были", &["быть"]),
+    ("буду", &["быть"]),
+    ("будет", &["быть"]),
+    ("будут", &["быть"]),
+    ("люди", &["человек"]),
+    ("людей", &["человек"]),
+    ("людям", &["человек"]),
+    ("дети", &["ребенок", "ребёнок"]),
+    ("детей", &["ребенок", "ребёнок"]),
+    ("детям", &["ребенок", "ребёнок"]),
+    ("лучше", &["хороший", "хорошо"]),
+    ("лучший", &["хороший"]),
+    ("хуже", &["плохой", "плохо"]),
+    ("худший", &["плохой"]),
+    ("больше", &["большой", "много"]),
+    ("меньше", &["маленький", "мало"]),
+    ("меня", &["я"]),
+    ("мне", &["я"]),
+    ("мной", &["я"]),
+    ("тебя", &["ты"]),
+    ("тебе", &["ты"]),
+    ("тобой", &["ты"]),
+    ("его", &["он"]),
+    ("ему", &["он"]),
+    ("него", &["он"]),
+    ("нему", &["он"]),
+    ("нем", &["он"]),
+    ("им", &["он", "они"]),
+    ("ним", &["он", "они"]),
+    ("ее", &["она"]),
+    ("ей", &["она"]),
+    ("нее", &["она"]),
+    ("ней", &["она"]),
+    ("нас", &["мы"]),
+    ("нам", &["мы"]),
+    ("нами", &["мы"]),
+    ("вас", &["вы"]),
+    ("вам", &["вы"]),
+    ("вами", &["вы"]),
+    ("их", &["они"]),
+    ("них", &["они"]),
+    ("ими", &["они"]),
+    ("ними", &["они"]),
+];
+
+/// Dictionary forms of a suppletive form, most likely first; empty for anything else
+pub fn suppletive_lemmas(word: &str) -> &'static [&'static str] {
+    let folded = fold_ru_word(word);
+    SUPPLETIVE_FORMS
+        .iter()
+        .find(|(form, _)| *form == folded)
+        .map(|(_, lemmas)| *lemmas)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_case_and_yo() {
+        assert_eq!(fold_ru_word(" Ёлка "), "елка");
+        assert_eq!(fold_ru_word("ПОШЁЛ"), "пошел");
+        assert_eq!(fold_ru_word("кошка"), "кошка");
+    }
+
+    #[test]
+    fn terms_are_stemmed_unless_too_short_or_a_phrase() {
+        assert_eq!(stem_ru_term("кошки"), "кошк");
+        assert_eq!(stem_ru_term("Кошку"), "кошк");
+        assert_eq!(stem_ru_term("Ёлки"), "елк");
+        // the stem would be too short for the trigram index
+        assert_eq!(stem_ru_term("Ели"), "ели");
+        assert_eq!(stem_ru_term("домашнее животное"), "домашнее животное");
+    }
+
+    #[test]
+    fn suppletive_forms_list_likelier_lemma_first() {
+        assert_eq!(suppletive_lemmas("Шёл"), ["идти"]);
+        assert_eq!(suppletive_lemmas("пошли"), ["пойти", "послать"]);
+        assert_eq!(suppletive_lemmas("людей"), ["человек"]);
+        assert!(suppletive_lemmas("кошки").is_empty());
+    }
+}
@@ -299,6 +299,7 @@ export const AnkiCardDetailView = React.memo(
           <DetailViewHeader
             backButton_desktopOnlyStyles_showButton={false}
             backButton_goBack={onBack}
+            generatedPronunciation={data.generated_pronunciation}
             isAutoFocusAnswerEnabled={isAutoFocusAnswerEnabled}
             isKhmerLinksEnabled={isKhmerLinksEnabled}
             isKhmerWordsHidingEnabled={isKhmerWordsHidingEnabled_prop}
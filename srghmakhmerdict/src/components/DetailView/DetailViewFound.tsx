@@ -177,6 +177,7 @@ export const DetailViewFound = ({
       <DetailViewHeader
         backButton_desktopOnlyStyles_showButton={backButton_desktopOnlyStyles_showButton}
         backButton_goBack={backButton_goBack}
+        generatedPronunciation={data.generated_pronunciation}
         isFav={isFav}
         isKhmerLinksEnabled={isKhmerLinksEnabled}
         isKhmerWordsHidingEnabled={isKhmerWordsHidingEnabled}
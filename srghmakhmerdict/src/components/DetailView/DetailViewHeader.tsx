@@ -9,6 +9,7 @@ import { HiArrowLeft } from 'react-icons/hi2'
 import type { MaybeColorizationMode } from '../../utils/text-processing/utils'
 import { useI18nContext } from '../../i18n/i18n-react-custom'
 import type { TranslationFunctions } from '../../i18n/i18n-types'
+import type { KhmerTransliteration } from '../../db/dict'
 
 interface DetailViewBackButtonProps {
   onPress: () => void
@@ -40,6 +41,7 @@ export interface DetailViewHeaderProps_Common extends DetailViewActionsProps_Com
 export interface DetailViewHeaderProps_KnownWord extends DetailViewHeaderProps_Common {
   type: 'known_word'
   phonetic: NonEmptyStringTrimmed | undefined
+  generatedPronunciation: KhmerTransliteration | undefined // shown when there is no phonetic
   khmerFontFamily: NonEmptyStringTrimmed | undefined
   word_displayHtml: NonEmptyStringTrimmed
 
@@ -60,6 +62,7 @@ export interface DetailViewHeaderProps_KnownWord extends DetailViewHeaderProps_C
 export interface DetailViewHeaderProps_AnkiGame_Back extends DetailViewHeaderProps_Common {
   type: 'anki_game_back'
   phonetic: NonEmptyStringTrimmed | undefined
+  generatedPronunciation: KhmerTransliteration | undefined // shown when there is no phonetic
   khmerFontFamily: NonEmptyStringTrimmed | undefined
   word_displayHtml: NonEmptyStringTrimmed
 
@@ -110,7 +113,7 @@ export type DetailViewHeaderProps =
 const DetailViewHeaderWord = (
   props: (DetailViewHeaderProps_KnownWord | DetailViewHeaderProps_AnkiGame_Back) & { LL: TranslationFunctions },
 ) => {
-  const { khmerFontFamily, word_displayHtml, phonetic, word_or_sentence__language } = props
+  const { khmerFontFamily, word_displayHtml, phonetic, generatedPronunciation, word_or_sentence__language } = props
 
   const h1Style = useMemo(
     () => (word_or_sentence__language === 'km' && khmerFontFamily ? { fontFamily: khmerFontFamily } : undefined),
@@ -138,6 +141,17 @@ const DetailViewHeaderWord = (
               /{phonetic}/
             </Chip>
           )}
+          {!phonetic && generatedPronunciation && (
+            <Chip
+              className="font-mono"
+              color="default"
+              size="sm"
+              title={`UNGEGN: ${generatedPronunciation.ungegn} · ALA-LC: ${generatedPronunciation.ala_lc}`}
+              variant="bordered"
+            >
+              /{generatedPronunciation.ipa}/ · {props.LL.DETAIL.GENERATED_PRONUNCIATION()}
+            </Chip>
+          )}
         </div>
         <div className="mt-1 text-tiny font-mono uppercase text-default-400 tracking-widest">
           {
@@ -1,4 +1,4 @@
-import React, { useMemo } from 'react'
+import React, { useEffect, useMemo, useState } from 'react'
 import { TooltipMobileFriendly } from './TooltipMobileFriendly'
 import { clsx } from 'clsx'
 
@@ -31,6 +31,8 @@ import {
   String_toNonEmptyString_orUndefined_afterTrim,
   type NonEmptyStringTrimmed,
 } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed'
+import { analyzeKhmerSeries } from '../db/dict/khmer'
+import type { KhmerSyllableSeries } from '../db/dict/types'
 
 // --- Helper Components ---
 
@@ -228,14 +230,58 @@ TokenRenderer.displayName = 'TokenRenderer'
 
 // --- Sub-Component: Khmer Word Block ---
 
+/**
+ * enrichWithSeries only looks at the last consonant; the native analysis also applies subscripts and shifters.
+ * Gives each vowel token the series of the syllable it is in, found by the token's char offset
+ */
+const withSyllableSeries = (
+  tokens: readonly EnrichedToken[],
+  syllables: readonly KhmerSyllableSeries[],
+): readonly EnrichedToken[] => {
+  let offset = 0
+
+  return tokens.map(token => {
+    const start = offset
+
+    offset += typeof token.v === 'string' ? 1 : token.v.length
+    if (token.type !== 'vowel' && token.type !== 'vowel_combination') return token
+    const series = syllables.find(s => s.char_start <= start && start < s.char_end)?.series
+
+    return series ? { ...token, series } : token
+  })
+}
+
 const KhmerWordBlock = React.memo(
   ({ word, definition }: { word: TypedKhmerWord; definition?: NonEmptyStringTrimmed }) => {
+    const [syllables, setSyllables] = useState<KhmerSyllableSeries[] | undefined>(undefined)
+
+    useEffect(() => {
+      let active = true
+
+      const fetchSeries = async () => {
+        setSyllables(undefined)
+        try {
+          const result = await analyzeKhmerSeries(word)
+
+          if (active) setSyllables(result)
+        } catch {
+          // keep the series enrichWithSeries guessed
+        }
+      }
+
+      fetchSeries()
+
+      return () => {
+        active = false
+      }
+    }, [word])
+
     const enrichedTokens = useMemo(() => {
       const chars = CharArray_mkFromString(word)
-      const tokens = tokenize(chars)
+      const tokens = enrichWithSeries(tokenize(chars))
 
-      return enrichWithSeries(tokens)
-    }, [word])
+      return syllables ? withSyllableSeries(tokens, syllables) : tokens
+    }, [word, syllables])
 
     return (
       <div className={`flex flex-col items-center ${enrichedTokens.length < 2 ? 'max-w-[80px]' : ''}`}>
@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core'
+import { assertNever } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/asserts'
+import type { DictionaryLanguage } from '../../types'
+import type { LanguageToCompletionMap } from './types'
+
+// Served from the in-memory index on the Rust side, no need to load the full word list first
+export const autocompleteEn = (prefix: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['en'][]>('autocomplete_en', { prefix, limit })
+
+export const autocompleteKm = (prefix: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['km'][]>('autocomplete_km', { prefix, limit })
+
+export const autocompleteRu = (prefix: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['ru'][]>('autocomplete_ru', { prefix, limit })
+
+export const autocompleteByMode = <L extends DictionaryLanguage>(
+  mode: L,
+  prefix: string,
+  limit?: number,
+): Promise<LanguageToCompletionMap[L][]> => {
+  switch (mode) {
+    case 'en':
+      return autocompleteEn(prefix, limit) as Promise<LanguageToCompletionMap[L][]>
+    case 'km':
+      return autocompleteKm(prefix, limit) as Promise<LanguageToCompletionMap[L][]>
+    case 'ru':
+      return autocompleteRu(prefix, limit) as Promise<LanguageToCompletionMap[L][]>
+    default:
+      assertNever(mode as never)
+  }
+}
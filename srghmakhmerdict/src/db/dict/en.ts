@@ -5,11 +5,14 @@ import type { NonEmptySet } from '@gemini-ocr-automate-images-upload-chrome-exte
 import type { NonEmptyRecord } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-record'
 import { memoizeAsync0_throwIfInFly } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/memoize-async'
 import { WordDetailEnSchema } from './schema'
-import type { WordDetailEn, ShortDefinitionEn } from './types'
+import type { WordDetailEn, ShortDefinitionEn, SourcePreferences, Page, WordPlainTextEn, OcrSearchHit } from './types'
 import type { ValidNonNegativeInt } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/toNumber'
 
 export const getEnWords = memoizeAsync0_throwIfInFly(() => invoke<NonEmptyArray<NonEmptyStringTrimmed>>('get_en_words'))
 
+export const getEnWordsPage = (cursor?: string, pageSize?: number) =>
+  invoke<Page<NonEmptyStringTrimmed>>('get_en_words_page', { cursor, pageSize })
+
 // only en db has images (with khmer text)
 export const get_en_km_com_images_ocr = async (ids: NonEmptySet<ValidNonNegativeInt>) => {
   return await invoke<Record<ValidNonNegativeInt, NonEmptyStringTrimmed>>('get_en_km_com_images_ocr', {
@@ -17,13 +20,26 @@ export const get_en_km_com_images_ocr = async (ids: NonEmptySet<ValidNonNegative
   })
 }
 
+// en headwords whose en_km_com definition shows each image
+export const get_en_km_com_image_words = async (ids: NonEmptySet<ValidNonNegativeInt>) => {
+  return await invoke<Record<ValidNonNegativeInt, NonEmptyStringTrimmed[]>>('get_en_km_com_image_words', {
+    ids: Array.from(ids),
+  })
+}
+
+// images whose OCR text contains `query` as a phrase, with the headwords showing them
+export const searchEnKmComOcr = (query: NonEmptyStringTrimmed, cursor?: string, pageSize?: number) =>
+  invoke<Page<OcrSearchHit>>('search_en_km_com_ocr', { query, cursor, pageSize })
+
 export const getWordDetailEn = async (
   word: NonEmptyStringTrimmed,
   useExtensionDb: boolean,
+  sources?: SourcePreferences<ShortDefinitionEn['source']>,
 ): Promise<WordDetailEn | undefined> => {
   const res = await invoke<unknown>('get_word_detail_en', {
     word,
     useExtensionDb,
+    sources,
   })
 
   if (res === undefined) throw new Error('undefined is unexpected')
@@ -32,16 +48,21 @@ export const getWordDetailEn = async (
   return WordDetailEnSchema.parse(res)
 }
 
+export const getWordPlainTextEn = async (word: NonEmptyStringTrimmed): Promise<WordPlainTextEn | undefined> =>
+  (await invoke<WordPlainTextEn | null>('get_word_plain_text_en', { word })) ?? undefined
+
 export const getEnWordsDetailShort = async (
   words: NonEmptySet<NonEmptyStringTrimmed>,
+  sources?: SourcePreferences<ShortDefinitionEn['source']>,
 ): Promise<NonEmptyRecord<NonEmptyStringTrimmed, ShortDefinitionEn | null>> => {
-  return invoke('en_for_many_short_description_none_if_word_not_found', { words: Array.from(words) })
+  return invoke('en_for_many_short_description_none_if_word_not_found', { words: Array.from(words), sources })
 }
 
 export const getEnWordsDetailShort_Strict = async (
   words: NonEmptySet<NonEmptyStringTrimmed>,
+  sources?: SourcePreferences<ShortDefinitionEn['source']>,
 ): Promise<NonEmptyRecord<NonEmptyStringTrimmed, ShortDefinitionEn>> => {
-  return invoke('en_for_many_short_description_throws_if_word_not_found', { words: Array.from(words) })
+  return invoke('en_for_many_short_description_throws_if_word_not_found', { words: Array.from(words), sources })
 }
 
 export const getEnWordsDetailFull = async (
@@ -5,3 +5,8 @@ export * from './km'
 export * from './ru'
 export * from './search'
 export * from './details'
+export * from './suggest'
+export * from './autocomplete'
+export * from './pattern'
+export * from './stream'
+export * from './khmer'
@@ -0,0 +1,71 @@
+import { invoke } from '@tauri-apps/api/core'
+import { memoizeAsync0_cachePromise } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/memoize-async'
+import {
+  CONSONANTS,
+  DIACRITICS,
+  EXTRA_CONSONANTS,
+  INDEPENDENT_VOWELS,
+  VOWELS,
+  VOWEL_COMBINATIONS,
+} from '@gemini-ocr-automate-images-upload-chrome-extension/utils/khmer-consonants-vovels'
+import type {
+  KhmerOrthographyViolation,
+  KhmerSyllableSeries,
+  KhmerTextSpan,
+  KhmerTransliteration,
+} from './types'
+
+// Orthographic syllables of `text`, with the runs of other chars between them; the spans cover the whole text
+export const segmentKhmerSyllables = (text: string): Promise<KhmerTextSpan[]> =>
+  invoke<KhmerTextSpan[]>('segment_khmer_syllables', { text })
+
+// UNGEGN, ALA-LC and IPA spellings generated from the Khmer spelling; strings are empty for text without Khmer
+export const transliterateKhmer = (text: string): Promise<Record<keyof KhmerTransliteration, string>> =>
+  invoke<Record<keyof KhmerTransliteration, string>>('transliterate_khmer', { text })
+
+// Series of every syllable (base, subscripts and shifter decide it) and how its vowel reads there
+export const analyzeKhmerSeries = (text: string): Promise<KhmerSyllableSeries[]> =>
+  invoke<KhmerSyllableSeries[]>('analyze_khmer_series', { text })
+
+// Sequences Khmer spelling doesn't allow (double coeng, vowel typed before its consonant, ...), with fixes
+export const validateKhmerOrthography = (text: string): Promise<KhmerOrthographyViolation[]> =>
+  invoke<KhmerOrthographyViolation[]>('validate_khmer_orthography', { text })
+
+// `text` with every fix of validateKhmerOrthography applied, unchanged if there is nothing to fix
+export const repairKhmerOrthography = (text: string): Promise<string> =>
+  invoke<string>('repair_khmer_orthography', { text })
+
+// Khmer dictionary sort keys (the order of the "khmer" collation of the word lists), one per text.
+// Keys compare with plain `<`, not localeCompare
+export const getKhmerSortKeys = (texts: readonly string[]): Promise<string[]> =>
+  invoke<string[]>('khmer_sort_keys', { texts })
+
+const compareCodePoints = (a: string, b: string): number => (a < b ? -1 : a > b ? 1 : 0)
+
+// Comparator of the letters the word list is grouped by (consonants, vowels, combinations, ...), in dictionary order.
+// Strings outside the inventory come after it, by code point
+export const getKhmerLetterComparator = memoizeAsync0_cachePromise(
+  async (): Promise<(a: string, b: string) => number> => {
+    const letters = [
+      ...CONSONANTS.map(c => c.letter),
+      ...EXTRA_CONSONANTS.map(c => c.letters.join('')),
+      ...VOWELS.map(v => v.letter),
+      ...VOWEL_COMBINATIONS.map(v => v.letters.join('')),
+      ...INDEPENDENT_VOWELS.map(v => v.letters),
+      ...DIACRITICS.map(d => d.symbol),
+    ]
+    const keys = await getKhmerSortKeys(letters)
+    const keyOf = new Map<string, string | undefined>(letters.map((letter, i) => [letter, keys[i]]))
+
+    return (a, b) => {
+      const ka = keyOf.get(a)
+      const kb = keyOf.get(b)
+
+      if (ka !== undefined && kb !== undefined) return compareCodePoints(ka, kb) || compareCodePoints(a, b)
+      if (ka !== undefined) return -1
+      if (kb !== undefined) return 1
+
+      return compareCodePoints(a, b)
+    }
+  },
+)
@@ -7,7 +7,19 @@ import type { NonEmptySet } from '@gemini-ocr-automate-images-upload-chrome-exte
 import type { NonEmptyRecord } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-record'
 import type { TypedContainsKhmer } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/string-contains-khmer-char'
 import { WordDetailKmSchema } from './schema'
-import type { KhmerWordsMap, KhmerWordsMapValue, WordDetailKm, ShortDefinitionKm } from './types'
+import type {
+  KhmerWordsMap,
+  KhmerWordsMapValue,
+  WordDetailKm,
+  ShortDefinitionKm,
+  Page,
+  SourcePreferences,
+  WordPlainTextKm,
+  RomanizedMatch,
+  KmHomophone,
+  KmSegmentation,
+  KmSpellingIssue,
+} from './types'
 
 type KhmerWordRow_Raw = { word: NonEmptyStringTrimmed; is_verified: boolean }
 
@@ -22,6 +34,9 @@ export const getKmWords = memoizeAsync0_throwIfInFly(async (): Promise<KhmerWord
   return Map_toNonEmptyMap_orThrow(map)
 })
 
+export const getKmWordsPage = (cursor?: string, pageSize?: number) =>
+  invoke<Page<KhmerWordRow_Raw>>('get_km_words_page', { cursor, pageSize })
+
 export function* yieldOnlyVerifiedKhmerWords(map: KhmerWordsMap): Generator<NonEmptyStringTrimmed> {
   for (const [word, value] of map.entries()) {
     if (value.isKhmer && value.is_verified) {
@@ -30,8 +45,11 @@ export function* yieldOnlyVerifiedKhmerWords(map: KhmerWordsMap): Generator<NonE
   }
 }
 
-export const getWordDetailKm = async (word: NonEmptyStringTrimmed): Promise<WordDetailKm | undefined> => {
-  const res = await invoke<unknown>('get_word_detail_km', { word })
+export const getWordDetailKm = async (
+  word: NonEmptyStringTrimmed,
+  sources?: SourcePreferences<ShortDefinitionKm['source']>,
+): Promise<WordDetailKm | undefined> => {
+  const res = await invoke<unknown>('get_word_detail_km', { word, sources })
 
   if (res === undefined) throw new Error('undefined is unexpected')
   if (res === null) return undefined
@@ -39,16 +57,41 @@ export const getWordDetailKm = async (word: NonEmptyStringTrimmed): Promise<Word
   return WordDetailKmSchema.parse(res)
 }
 
+export const getWordPlainTextKm = async (word: NonEmptyStringTrimmed): Promise<WordPlainTextKm | undefined> =>
+  (await invoke<WordPlainTextKm | null>('get_word_plain_text_km', { word })) ?? undefined
+
+// "chhmuos" -> ឈ្មោះ, for users who can't type Khmer
+export const searchKmByRomanization = (query: NonEmptyStringTrimmed, limit?: number): Promise<RomanizedMatch[]> =>
+  invoke<RomanizedMatch[]>('search_km_by_romanization', { query, limit })
+
+// "Sounds like" section of the detail view, [] if the word has no pronunciation
+export const getKmHomophones = (
+  word: NonEmptyStringTrimmed,
+  sources?: SourcePreferences<ShortDefinitionKm['source']>,
+): Promise<KmHomophone[]> => invoke<KmHomophone[]>('get_km_homophones', { word, sources })
+
+// Splits unspaced Khmer text into headwords, with short definitions and the other plausible splits
+export const segmentKmText = (
+  text: string,
+  sources?: SourcePreferences<ShortDefinitionKm['source']>,
+): Promise<KmSegmentation> => invoke<KmSegmentation>('segment_km_text', { text, sources })
+
+// Spans of pasted text that aren't known words, with ranked corrections
+export const spellcheckKmText = (text: string, limit?: number): Promise<KmSpellingIssue[]> =>
+  invoke<KmSpellingIssue[]>('spellcheck_km_text', { text, limit })
+
 export const getKmWordsDetailShort = async (
   words: NonEmptySet<TypedContainsKhmer>,
+  sources?: SourcePreferences<ShortDefinitionKm['source']>,
 ): Promise<NonEmptyRecord<TypedContainsKhmer, ShortDefinitionKm | null>> => {
-  return invoke('km_for_many_short_description_none_if_word_not_found', { words: Array.from(words) })
+  return invoke('km_for_many_short_description_none_if_word_not_found', { words: Array.from(words), sources })
 }
 
 export const getKmWordsDetailShort_Strict = async (
   words: NonEmptySet<TypedContainsKhmer>,
+  sources?: SourcePreferences<ShortDefinitionKm['source']>,
 ): Promise<NonEmptyRecord<TypedContainsKhmer, ShortDefinitionKm>> => {
-  return invoke('km_for_many_short_description_throws_if_word_not_found', { words: Array.from(words) })
+  return invoke('km_for_many_short_description_throws_if_word_not_found', { words: Array.from(words), sources })
 }
 
 export const getKmWordsDetailFull = async (
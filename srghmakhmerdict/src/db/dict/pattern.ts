@@ -0,0 +1,31 @@
+import { invoke } from '@tauri-apps/api/core'
+import { assertNever } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/asserts'
+import type { DictionaryLanguage } from '../../types'
+import type { LanguageToCompletionMap } from './types'
+
+// `?` is one cluster (ក្រុ) or letter, `*` any run of them, `[កខ]` / `[ក-ឃ]` one of the consonants, `[^កខ]` any other
+export const searchPatternEn = (pattern: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['en'][]>('search_en_pattern', { pattern, limit })
+
+export const searchPatternKm = (pattern: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['km'][]>('search_km_pattern', { pattern, limit })
+
+export const searchPatternRu = (pattern: string, limit?: number) =>
+  invoke<LanguageToCompletionMap['ru'][]>('search_ru_pattern', { pattern, limit })
+
+export const searchPatternByMode = <L extends DictionaryLanguage>(
+  mode: L,
+  pattern: string,
+  limit?: number,
+): Promise<LanguageToCompletionMap[L][]> => {
+  switch (mode) {
+    case 'en':
+      return searchPatternEn(pattern, limit) as Promise<LanguageToCompletionMap[L][]>
+    case 'km':
+      return searchPatternKm(pattern, limit) as Promise<LanguageToCompletionMap[L][]>
+    case 'ru':
+      return searchPatternRu(pattern, limit) as Promise<LanguageToCompletionMap[L][]>
+    default:
+      assertNever(mode as never)
+  }
+}
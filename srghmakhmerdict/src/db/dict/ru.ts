@@ -5,10 +5,13 @@ import type { NonEmptySet } from '@gemini-ocr-automate-images-upload-chrome-exte
 import type { NonEmptyRecord } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-record'
 import { memoizeAsync0_throwIfInFly } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/memoize-async'
 import { WordDetailRuSchema } from './schema'
-import type { WordDetailRu, ShortDefinitionRu } from './types'
+import type { WordDetailRu, ShortDefinitionRu, Page, WordPlainTextRu } from './types'
 
 export const getRuWords = memoizeAsync0_throwIfInFly(() => invoke<NonEmptyArray<NonEmptyStringTrimmed>>('get_ru_words'))
 
+export const getRuWordsPage = (cursor?: string, pageSize?: number) =>
+  invoke<Page<NonEmptyStringTrimmed>>('get_ru_words_page', { cursor, pageSize })
+
 export const getWordDetailRu = async (word: NonEmptyStringTrimmed): Promise<WordDetailRu | undefined> => {
   const res = await invoke<unknown>('get_word_detail_ru', { word })
 
@@ -18,6 +21,9 @@ export const getWordDetailRu = async (word: NonEmptyStringTrimmed): Promise<Word
   return WordDetailRuSchema.parse(res)
 }
 
+export const getWordPlainTextRu = async (word: NonEmptyStringTrimmed): Promise<WordPlainTextRu | undefined> =>
+  (await invoke<WordPlainTextRu | null>('get_word_plain_text_ru', { word })) ?? undefined
+
 export const getRuWordsDetailShort = async (
   words: NonEmptySet<NonEmptyStringTrimmed>,
 ): Promise<NonEmptyRecord<NonEmptyStringTrimmed, ShortDefinitionRu | null>> => {
@@ -2,6 +2,13 @@ import * as z from 'zod/mini'
 import { NonEmptyArraySchema } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-array-zod'
 import { NonEmptyStringTrimmedSchema } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed-zod'
 
+// Spellings generated from the Khmer spelling by rules (transliterate.rs)
+export const KhmerTransliterationSchema = z.strictObject({
+  ungegn: NonEmptyStringTrimmedSchema,
+  ala_lc: NonEmptyStringTrimmedSchema,
+  ipa: NonEmptyStringTrimmedSchema,
+})
+
 export const WordDetailKmSchema = z.strictObject({
   desc: z.optional(NonEmptyStringTrimmedSchema), // html
   phonetic: z.optional(NonEmptyStringTrimmedSchema),
@@ -15,6 +22,18 @@ export const WordDetailKmSchema = z.strictObject({
   from_russian_wiki: z.optional(NonEmptyStringTrimmedSchema), // html
   gorgoniev: z.optional(NonEmptyStringTrimmedSchema), // html
   en_km_com: z.optional(NonEmptyStringTrimmedSchema), // html
+  generated_pronunciation: z.optional(KhmerTransliterationSchema), // only when phonetic and from_csv_pronunciations are missing
+})
+
+// How a form that is neither a headword nor in en_Extension was resolved: running -> run, [{ Suffix: '-ing' }]
+export const EnLemmaStepSchema = z.union([
+  z.enum(['CaseFolded', 'SpellingVariant', 'Irregular']),
+  z.strictObject({ Suffix: NonEmptyStringTrimmedSchema }),
+])
+
+export const EnLemmaMatchSchema = z.strictObject({
+  lemma: NonEmptyStringTrimmedSchema,
+  steps: z.array(EnLemmaStepSchema),
 })
 
 export const WordDetailEnSchema = z.strictObject({
@@ -22,11 +41,19 @@ export const WordDetailEnSchema = z.strictObject({
   desc: z.optional(NonEmptyStringTrimmedSchema), // html
   desc_en_only: z.optional(NonEmptyStringTrimmedSchema), // html
   en_km_com: z.optional(NonEmptyStringTrimmedSchema), // html
+  lemma: z.optional(EnLemmaMatchSchema),
+})
+
+// Set when the requested form is not a headword itself (кошки -> кошка)
+export const RuLemmaMatchSchema = z.strictObject({
+  lemma: NonEmptyStringTrimmedSchema,
+  via: z.enum(['Folded', 'Suppletive', 'Stem']),
 })
 
 export const WordDetailRuSchema = z.strictObject({
   word_display: z.optional(NonEmptyStringTrimmedSchema),
   desc: z.optional(NonEmptyStringTrimmedSchema), // html
+  lemma: z.optional(RuLemmaMatchSchema),
 })
 
 export const ShortDefinitionEnSourceSchema = z.enum(['Desc', 'EnKmCom', 'DescEnOnly'])
@@ -2,28 +2,98 @@ import { invoke } from '@tauri-apps/api/core'
 import { assertNever } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/asserts'
 import type { NonEmptyStringTrimmed } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed'
 import type { DictionaryLanguage } from '../../types'
+import type { GlobalSearchHit, LanguageToContentSearchHitMap, Page, RuContentSearchPage } from './types'
 
-export const searchEnContent = (query: NonEmptyStringTrimmed) =>
-  invoke<NonEmptyStringTrimmed[]>('search_en_content', { query })
+type ContentSearchSource<L extends DictionaryLanguage> = LanguageToContentSearchHitMap[L]['source']
 
-export const searchKmContent = (query: NonEmptyStringTrimmed) =>
-  invoke<NonEmptyStringTrimmed[]>('search_km_content', { query })
+// Error of a query superseded by a newer one with the same `requestKey`, safe to ignore
+export const QUERY_CANCELLED = 'CANCELLED'
 
-export const searchRuContent = (query: NonEmptyStringTrimmed) =>
-  invoke<NonEmptyStringTrimmed[]>('search_ru_content', { query })
+export const isQueryCancelled = (e: unknown): boolean => e === QUERY_CANCELLED
 
-export const searchContentByMode = async (
-  mode: DictionaryLanguage,
+export const cancelQuery = (requestKey: string) => invoke<void>('cancel_query', { requestKey })
+
+// `query` supports "phrases", AND / OR / NOT (or -word), (groups) and source:name prefixes.
+// `sources` restricts the whole search to these sources (all if omitted).
+// Starting a search with the same `requestKey` cancels the older one, which then rejects with QUERY_CANCELLED
+export const searchEnContent = (
   query: NonEmptyStringTrimmed,
-): Promise<NonEmptyStringTrimmed[]> => {
+  cursor?: string,
+  pageSize?: number,
+  sources?: ContentSearchSource<'en'>[],
+  requestKey?: string,
+) => invoke<Page<LanguageToContentSearchHitMap['en']>>('search_en_content', {
+  query,
+  sources,
+  cursor,
+  pageSize,
+  requestKey,
+})
+
+export const searchKmContent = (
+  query: NonEmptyStringTrimmed,
+  cursor?: string,
+  pageSize?: number,
+  sources?: ContentSearchSource<'km'>[],
+  requestKey?: string,
+) => invoke<Page<LanguageToContentSearchHitMap['km']>>('search_km_content', {
+  query,
+  sources,
+  cursor,
+  pageSize,
+  requestKey,
+})
+
+export const searchRuContent = (
+  query: NonEmptyStringTrimmed,
+  cursor?: string,
+  pageSize?: number,
+  sources?: ContentSearchSource<'ru'>[],
+  requestKey?: string,
+) => invoke<RuContentSearchPage>('search_ru_content', {
+  query,
+  sources,
+  cursor,
+  pageSize,
+  requestKey,
+})
+
+// Headwords and content of all three dictionaries in one round trip
+export const searchAll = (query: NonEmptyStringTrimmed, limit?: number, requestKey?: string) =>
+  invoke<GlobalSearchHit[]>('search_all', { query, limit, requestKey })
+
+export const searchContentHitsByMode = async <L extends DictionaryLanguage>(
+  mode: L,
+  query: NonEmptyStringTrimmed,
+  cursor?: string,
+  pageSize?: number,
+  sources?: ContentSearchSource<L>[],
+  requestKey?: string,
+): Promise<Page<LanguageToContentSearchHitMap[L]>> => {
   switch (mode) {
     case 'en':
-      return searchEnContent(query)
+      return searchEnContent(query, cursor, pageSize, sources as ContentSearchSource<'en'>[] | undefined, requestKey) as Promise<
+        Page<LanguageToContentSearchHitMap[L]>
+      >
     case 'km':
-      return searchKmContent(query)
+      return searchKmContent(query, cursor, pageSize, sources as ContentSearchSource<'km'>[] | undefined, requestKey) as Promise<
+        Page<LanguageToContentSearchHitMap[L]>
+      >
     case 'ru':
-      return searchRuContent(query)
+      return searchRuContent(query, cursor, pageSize, sources as ContentSearchSource<'ru'>[] | undefined, requestKey) as Promise<
+        Page<LanguageToContentSearchHitMap[L]>
+      >
     default:
       assertNever(mode as never)
   }
 }
+
+export const searchContentByMode = async (
+  mode: DictionaryLanguage,
+  query: NonEmptyStringTrimmed,
+  requestKey?: string,
+): Promise<NonEmptyStringTrimmed[]> => {
+  const page = await searchContentHitsByMode(mode, query, undefined, undefined, undefined, requestKey)
+
+  return page.items.map(hit => hit.word)
+}
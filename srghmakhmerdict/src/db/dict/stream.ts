@@ -0,0 +1,54 @@
+import { Channel, invoke } from '@tauri-apps/api/core'
+import type { NonEmptyStringTrimmed } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed'
+import type { NonEmptySet } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-set'
+import type { TypedContainsKhmer } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/string-contains-khmer-char'
+import type { StreamEvent, StreamedDetail, WordDetailEn, WordDetailKm, WordDetailRu } from './types'
+
+type KhmerWordRow_Raw = { word: NonEmptyStringTrimmed; is_verified: boolean }
+
+// Calls `onBatch` for every batch as it arrives; resolves with the total once the `end` message is received
+const streamCommand = <T>(
+  command: string,
+  args: Record<string, unknown>,
+  onBatch: (rows: T[]) => void,
+): Promise<number> =>
+  new Promise((resolve, reject) => {
+    const onEvent = new Channel<StreamEvent<T>>()
+
+    onEvent.onmessage = message => {
+      switch (message.event) {
+        case 'batch':
+          onBatch(message.data)
+          break
+        case 'end':
+          resolve(message.data.total)
+          break
+      }
+    }
+
+    invoke<void>(command, { ...args, onEvent }).catch(reject)
+  })
+
+export const streamKmWords = (onBatch: (rows: KhmerWordRow_Raw[]) => void) =>
+  streamCommand('stream_km_words', {}, onBatch)
+
+export const streamEnWords = (onBatch: (rows: NonEmptyStringTrimmed[]) => void) =>
+  streamCommand('stream_en_words', {}, onBatch)
+
+export const streamRuWords = (onBatch: (rows: NonEmptyStringTrimmed[]) => void) =>
+  streamCommand('stream_ru_words', {}, onBatch)
+
+export const streamKmWordsDetailFull = (
+  words: NonEmptySet<TypedContainsKhmer>,
+  onBatch: (rows: StreamedDetail<WordDetailKm>[]) => void,
+) => streamCommand('stream_km_full_details', { words: Array.from(words) }, onBatch)
+
+export const streamEnWordsDetailFull = (
+  words: NonEmptySet<NonEmptyStringTrimmed>,
+  onBatch: (rows: StreamedDetail<WordDetailEn>[]) => void,
+) => streamCommand('stream_en_full_details', { words: Array.from(words) }, onBatch)
+
+export const streamRuWordsDetailFull = (
+  words: NonEmptySet<NonEmptyStringTrimmed>,
+  onBatch: (rows: StreamedDetail<WordDetailRu>[]) => void,
+) => streamCommand('stream_ru_full_details', { words: Array.from(words) }, onBatch)
@@ -0,0 +1,23 @@
+import { invoke } from '@tauri-apps/api/core'
+import { assertNever } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/asserts'
+import type { NonEmptyStringTrimmed } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed'
+import type { DictionaryLanguage } from '../../types'
+import type { WordSuggestion } from './types'
+
+// "Did you mean", for when getWordDetailByMode returned undefined
+export const getWordSuggestionsByMode = (
+  mode: DictionaryLanguage,
+  word: NonEmptyStringTrimmed,
+  limit?: number,
+): Promise<WordSuggestion[]> => {
+  switch (mode) {
+    case 'en':
+      return invoke<WordSuggestion[]>('get_word_suggestions_en', { word, limit })
+    case 'km':
+      return invoke<WordSuggestion[]>('get_word_suggestions_km', { word, limit })
+    case 'ru':
+      return invoke<WordSuggestion[]>('get_word_suggestions_ru', { word, limit })
+    default:
+      assertNever(mode)
+  }
+}
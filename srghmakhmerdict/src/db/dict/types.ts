@@ -1,7 +1,9 @@
 import type { NonEmptyStringTrimmed } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-string-trimmed'
 import type { NonEmptyMap } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/non-empty-map'
+import type { ValidNonNegativeInt } from '@gemini-ocr-automate-images-upload-chrome-extension/utils/toNumber'
 import type * as z from 'zod/mini'
 import type {
+  KhmerTransliterationSchema,
   WordDetailKmSchema,
   WordDetailEnSchema,
   WordDetailRuSchema,
@@ -14,9 +16,27 @@ export type KhmerWordsMapValue = { isKhmer: boolean; is_verified: boolean }
 export type KhmerWordsMap = NonEmptyMap<NonEmptyStringTrimmed, KhmerWordsMapValue>
 
 export type WordDetailKm = z.infer<typeof WordDetailKmSchema>
+export type KhmerTransliteration = z.infer<typeof KhmerTransliterationSchema>
 export type WordDetailEn = z.infer<typeof WordDetailEnSchema>
 export type WordDetailRu = z.infer<typeof WordDetailRuSchema>
 
+// HTML-free text of the definition fields of WordDetail*, for TTS and copying
+export type WordPlainTextKm = Partial<
+  Record<
+    | 'desc'
+    | 'wiktionary'
+    | 'from_csv_raw_html'
+    | 'from_chuon_nath'
+    | 'from_chuon_nath_translated'
+    | 'from_russian_wiki'
+    | 'gorgoniev'
+    | 'en_km_com',
+    NonEmptyStringTrimmed
+  >
+>
+export type WordPlainTextEn = Partial<Record<'desc' | 'desc_en_only' | 'en_km_com', NonEmptyStringTrimmed>>
+export type WordPlainTextRu = Partial<Record<'desc', NonEmptyStringTrimmed>>
+
 export type ShortDefinitionEn = z.infer<typeof ShortDefinitionEnSchema>
 export type ShortDefinitionRu = z.infer<typeof ShortDefinitionRuSchema>
 export type ShortDefinitionKm = z.infer<typeof ShortDefinitionKmSchema>
@@ -45,3 +65,227 @@ export type LanguageToShortDefinitionSum =
   | { t: 'en'; v: ShortDefinitionEn }
   | { t: 'km'; v: ShortDefinitionKm }
   | { t: 'ru'; v: ShortDefinitionRu }
+
+// Which definition sources to prefer / hide, e.g. { order: ['Gorgoniev'], excluded: ['FromChuonNathTranslated'] }
+export type SourcePreferences<S> = {
+  order?: S[] // sources not listed keep their default order after these
+  excluded?: S[]
+}
+
+export type SnippetHighlight = { start: number; end: number } // UTF-16 offsets into SearchSnippet.text
+export type SearchSnippet = { text: string; highlights: SnippetHighlight[] }
+
+export type ContentSearchHit<S> = {
+  word: NonEmptyStringTrimmed
+  source: S
+  score: number
+  snippet: SearchSnippet
+}
+
+export type OcrSearchHit = {
+  image_id: ValidNonNegativeInt
+  score: number // higher is better, 0 if the query was too short for the index
+  snippet: SearchSnippet
+  words: NonEmptyStringTrimmed[] // en headwords showing the image
+}
+
+export type LanguageToContentSearchHitMap = {
+  en: ContentSearchHit<ShortDefinitionEn['source']>
+  km: ContentSearchHit<ShortDefinitionKm['source']>
+  ru: ContentSearchHit<ShortDefinitionRu['source']>
+}
+
+export type GlobalSearchMatchKind = 'ExactHeadword' | 'Prefix' | 'Content'
+
+export type GlobalSearchLanguageHit<S> = {
+  word: NonEmptyStringTrimmed
+  kind: GlobalSearchMatchKind
+  is_verified: boolean
+  source: S | null // headword hits: null if the word has no short definition
+  score?: number // content hits only, comparable within one language
+  snippet?: SearchSnippet // content hits only
+}
+
+// Ranked: exact headwords, then prefixes, then content; the dictionary in the script of the query first
+export type GlobalSearchHit =
+  | ({ language: 'en' } & GlobalSearchLanguageHit<ShortDefinitionEn['source']>)
+  | ({ language: 'km' } & GlobalSearchLanguageHit<ShortDefinitionKm['source']>)
+  | ({ language: 'ru' } & GlobalSearchLanguageHit<ShortDefinitionRu['source']>)
+
+export type Page<T> = {
+  items: T[]
+  next_cursor: string | null // opaque, pass back as-is; null on the last page
+  total_estimate: number
+}
+
+// A query term and what it was searched as: its stem ("кошки" -> "кошк"), or the folded word if that is too short
+export type RuSearchedTerm = { typed: string; searched: string }
+
+export type RuContentSearchPage = Page<LanguageToContentSearchHitMap['ru']> & {
+  terms: RuSearchedTerm[] // in query order, negated terms included
+}
+
+// `end` is always the last message of a stream that didn't fail
+export type StreamEvent<T> = { event: 'batch'; data: T[] } | { event: 'end'; data: { total: number } }
+
+export type StreamedDetail<T> = {
+  word: string
+  detail: T | null // null if the word was not found
+}
+
+export type WordSuggestion = {
+  word: NonEmptyStringTrimmed
+  distance: number
+  kind: 'EditDistance' | 'Phonetic'
+}
+
+export type Completion<S> = {
+  word: NonEmptyStringTrimmed
+  is_verified: boolean
+  source: S | null // null if the word has no short definition
+}
+
+export type LanguageToCompletionMap = {
+  en: Completion<ShortDefinitionEn['source']>
+  km: Completion<ShortDefinitionKm['source']>
+  ru: Completion<ShortDefinitionRu['source']>
+}
+
+export type RomanizationOrigin = 'Phonetic' | 'CsvPronunciation' | 'Transliterated'
+
+export type RomanizedMatch = {
+  word: NonEmptyStringTrimmed
+  romanization: string // the spelling that matched, as written in its origin
+  origin: RomanizationOrigin
+  distance: number // lower is better, 0 if the query is that spelling up to variants
+}
+
+export type KmHomophone = {
+  word: NonEmptyStringTrimmed
+  pronunciation: string // the shared pronunciation, as the other word's source writes it
+  short: ShortDefinitionKm | null // null if none of the allowed sources has a definition
+}
+
+export type KhmerSpanKind = 'Syllable' | 'Space' | 'Digits' | 'Punctuation' | 'StrayMarks' | 'Other'
+
+// What an orthographic syllable is made of, in the order the chars are written
+export type KhmerSyllableParts = {
+  base: string
+  base_kind: 'Consonant' | 'IndependentVowel'
+  robat: boolean
+  subscripts: string[] // consonants written under the base, without their coeng
+  shifter: string | null // ៉ or ៊
+  vowels: string[] // one in well-formed text
+  signs: string[]
+}
+
+// Offsets are end exclusive; char offsets count code points, not UTF-16 units
+export type KhmerTextSpan = {
+  kind: KhmerSpanKind
+  text: string
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  parts: KhmerSyllableParts | null // non-null for syllables only
+}
+
+export type KhmerSeries = 'a' | 'o'
+
+export type KhmerVowelReading = { ungegn: string; ipa: string }
+
+// Series of one orthographic syllable and the reading of its vowel there; offsets as in KhmerTextSpan
+export type KhmerSyllableSeries = {
+  text: string
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  base: string
+  base_kind: 'Consonant' | 'IndependentVowel'
+  subscripts: string[]
+  shifter: string | null // ៉ or ៊
+  series: KhmerSeries | null // null for independent vowels
+  series_source: 'Base' | 'Subscript' | 'Shifter' | null
+  vowel: string // dependent vowel signs plus ំ / ះ, '' for the inherent vowel
+  reading: KhmerVowelReading // of the independent vowel itself for independent vowels
+  other_series_reading: KhmerVowelReading | null
+}
+
+export type KhmerViolationKind =
+  | 'DoubleCoeng'
+  | 'DanglingCoeng'
+  | 'StrayMark'
+  | 'VowelBeforeConsonant'
+  | 'SubscriptOnIndependentVowel'
+  | 'DuplicateMark'
+  | 'SplitVowel'
+  | 'MarkOrder'
+
+// Replace char_start..char_end of the input with `replacement`
+export type KhmerOrthographyFix = {
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  replacement: string
+}
+
+// The offending chars, offsets as in KhmerTextSpan
+export type KhmerOrthographyViolation = {
+  kind: KhmerViolationKind
+  text: string
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  fix: KhmerOrthographyFix // the whole syllable rewritten, shared by the violations in it
+}
+
+export type KmSegmentKind = 'Word' | 'Unknown' | 'Space' | 'Digits' | 'Punctuation' | 'StrayMarks' | 'Other'
+
+export type KmSegment = {
+  text: string
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  kind: KmSegmentKind // Unknown: Khmer syllables no headword covers
+  headword: NonEmptyStringTrimmed | null // the stored headword, non-null for words only
+  short: ShortDefinitionKm | null // null if not a word or none of the allowed sources has a definition
+}
+
+// A span the dictionary splits in more than one plausible way, the chosen split is in KmSegmentation.segments
+export type KmAmbiguity = {
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  alternatives: KmSegment[][] // best first
+}
+
+export type KmSegmentation = {
+  segments: KmSegment[] // cover the whole text
+  ambiguities: KmAmbiguity[]
+}
+
+export type KmSpellingSuggestion = {
+  word: NonEmptyStringTrimmed
+  distance: number // grapheme edits
+  list_count: number // how many word lists have it, stands in for frequency
+  // what it replaces: the misspelled span, or that span with up to two syllables on each side
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+}
+
+// Khmer syllables no known word covers
+export type KmSpellingIssue = {
+  text: string
+  byte_start: number
+  byte_end: number
+  char_start: number
+  char_end: number
+  suggestions: KmSpellingSuggestion[] // best first
+}
@@ -7,7 +7,7 @@ import {
 // DB & Utils
 import * as DictDb from '../db/dict'
 import { processData, type ProcessDataOutput } from '../utils/toGroup'
-import { processDataKhmer, type ProcessDataOutputKhmer } from '../utils/toGroupKhmer'
+import { processDataKhmer, type CompareKhmerKeys, type ProcessDataOutputKhmer } from '../utils/toGroupKhmer'
 import { extractKeysEn, extractKeysRu } from '../utils/keyExtractionGeneric'
 import { extractKeysKhmer } from '../utils/keyExtractionKhmer'
 import { mkMakeFilterQuery_memoized, type FilterQuery } from '../utils/mkFilterQuery'
@@ -47,7 +47,11 @@ function* mapKhmerInput(words: Iterable<NonEmptyStringTrimmed>) {
   }
 }
 
-const performProcessing = (words: Iterable<NonEmptyStringTrimmed>, mode: DictionaryLanguage): ProcessedDataState => {
+const performProcessing = (
+  words: Iterable<NonEmptyStringTrimmed>,
+  mode: DictionaryLanguage,
+  compareKhmerKeys: CompareKhmerKeys | undefined,
+): ProcessedDataState => {
   if (mode === 'en') {
     return { mode: 'en', data: processData(extractKeysEn, words) }
   }
@@ -55,7 +59,7 @@ const performProcessing = (words: Iterable<NonEmptyStringTrimmed>, mode: Diction
     return { mode: 'ru', data: processData(extractKeysRu, words) }
   }
 
-  const data = processDataKhmer(mapKhmerInput(words))
+  const data = processDataKhmer(mapKhmerInput(words), compareKhmerKeys)
   // console.log('performProcessing data khmer', data)
 
   return { mode: 'km', data }
@@ -276,8 +280,10 @@ export function useDictionarySearch({ activeTab, mode, searchMode, searchInConte
       let results
 
       try {
-        results = await DictDb.searchContentByMode(l, debouncedQueryNonEmpty)
+        results = await DictDb.searchContentByMode(l, debouncedQueryNonEmpty, 'content-search')
       } catch (e: unknown) {
+        // superseded by the search for a newer query
+        if (DictDb.isQueryCancelled(e)) return
         toast.error('Search content by mode failed' as NonEmptyStringTrimmed, unknown_to_errorMessage(e))
 
         return
@@ -353,8 +359,13 @@ export function useDictionarySearch({ activeTab, mode, searchMode, searchInConte
         }
       })()
 
-      // 3. Process
-      const processed = performProcessing(countingIter, activeTab)
+      // 3. Process (Khmer groups in dictionary order; localeCompare if the sort keys can't be loaded)
+      const compareKhmerKeys =
+        activeTab === 'km' ? await DictDb.getKhmerLetterComparator().catch(() => undefined) : undefined
+
+      if (signal.aborted) return
+
+      const processed = performProcessing(countingIter, activeTab, compareKhmerKeys)
 
       if (signal.aborted) return
 
@@ -172,6 +172,7 @@ const en = {
     NOT_FOUND: 'Word "{word}" not found in dictionary.',
     ANALYSIS_HINT: 'Try adjusting the text or checking the Khmer Analyzer below.',
     PLACEHOLDER: 'Translate text...',
+    GENERATED_PRONUNCIATION: 'generated',
     SECTION: {
       DEFINITION: 'Definition',
       DEFINITION_EN: 'English Definition',
@@ -550,6 +550,10 @@ type RootTranslation = {
 		 * T​r​a​n​s​l​a​t​e​ ​t​e​x​t​.​.​.
 		 */
 		PLACEHOLDER: string
+		/**
+		 * g​e​n​e​r​a​t​e​d
+		 */
+		GENERATED_PRONUNCIATION: string
 		SECTION: {
 			/**
 			 * D​e​f​i​n​i​t​i​o​n
@@ -1344,6 +1348,10 @@ export type TranslationFunctions = {
 		 * Translate text...
 		 */
 		PLACEHOLDER: () => LocalizedString
+		/**
+		 * generated
+		 */
+		GENERATED_PRONUNCIATION: () => LocalizedString
 		SECTION: {
 			/**
 			 * Definition
@@ -172,6 +172,7 @@ const ru = {
     NOT_FOUND: 'Слово "{word}" не найдено в словаре.',
     ANALYSIS_HINT: 'Попробуйте изменить текст или проверьте кхмерский анализатор ниже.',
     PLACEHOLDER: 'Перевести текст...',
+    GENERATED_PRONUNCIATION: 'сгенерировано',
     SECTION: {
       DEFINITION: 'Определение',
       DEFINITION_EN: 'Английское определение',
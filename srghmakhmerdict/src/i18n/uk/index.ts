@@ -172,6 +172,7 @@ const uk = {
     NOT_FOUND: 'Слово "{word}" не знайдено в словнику.',
     ANALYSIS_HINT: 'Спробуйте змінити текст або перевірте кхмерський аналізатор нижче.',
     PLACEHOLDER: 'Перекласти текст...',
+    GENERATED_PRONUNCIATION: 'згенеровано',
     SECTION: {
       DEFINITION: 'Визначення',
       DEFINITION_EN: 'Англійське визначення',
@@ -37,6 +37,7 @@ export const processors: { [K in keyof WordDetailKm]-?: Processor<K> } = {
     return strToWithoutKhmerAndHtml_remove_orUndefined(x, [ipaTag])
   },
   en_km_com: x => strToWithoutKhmerAndHtml_remove_orUndefined_(x),
+  generated_pronunciation: () => undefined,
 }
 
 // Helper to pick the best HTML source from the DB object
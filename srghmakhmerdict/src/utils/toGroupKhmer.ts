@@ -198,15 +198,21 @@ const addWordToAccumulator = (
   }
 }
 
+// Order of the group keys (letters), e.g. the dictionary order from getKhmerLetterComparator
+export type CompareKhmerKeys = (a: string, b: string) => number
+
+const compareKhmerKeys_locale: CompareKhmerKeys = (a, b) => a.localeCompare(b)
+
 /**
  * transformAccumulatorMap converts the mutable arrays in the accumulator Maps
  * into immutable NonEmptyArrays, while sorting keys to ensure Map iteration order is alphabetical.
  */
 const transformAccumulatorMap = <K extends Char | NonEmptyStringTrimmed>(
   input: Map<K, NonEmptyStringTrimmed[]>,
+  compareKeys: CompareKhmerKeys,
 ): Map<K, NonEmptyArray<NonEmptyStringTrimmed>> => {
   const out = new Map<K, NonEmptyArray<NonEmptyStringTrimmed>>()
-  const sortedKeys = Array.from(input.keys()).sort((a, b) => a.localeCompare(b))
+  const sortedKeys = Array.from(input.keys()).sort(compareKeys)
 
   for (const k of sortedKeys) {
     out.set(k, Array_toNonEmptyArray_orThrow(assertIsDefinedAndReturn(input.get(k))))
@@ -215,28 +221,34 @@ const transformAccumulatorMap = <K extends Char | NonEmptyStringTrimmed>(
   return out
 }
 
-const accumulatorToGroup = (acc: Accumulator): AlphabetGroupKhmer => ({
-  subGroups_consonant: transformAccumulatorMap(acc.sub_consonant),
-  subGroups_vowel: transformAccumulatorMap(acc.sub_vowel),
-  subGroups_independent_vowel: transformAccumulatorMap(acc.sub_indep),
-  subGroups_diacritic: transformAccumulatorMap(acc.sub_diacritic),
-  subGroups_extraConsonant: transformAccumulatorMap(acc.sub_extra),
-  subGroups_vowelCombination: transformAccumulatorMap(acc.sub_vowelCombo),
+const accumulatorToGroup = (acc: Accumulator, compareKeys: CompareKhmerKeys): AlphabetGroupKhmer => ({
+  subGroups_consonant: transformAccumulatorMap(acc.sub_consonant, compareKeys),
+  subGroups_vowel: transformAccumulatorMap(acc.sub_vowel, compareKeys),
+  subGroups_independent_vowel: transformAccumulatorMap(acc.sub_indep, compareKeys),
+  subGroups_diacritic: transformAccumulatorMap(acc.sub_diacritic, compareKeys),
+  subGroups_extraConsonant: transformAccumulatorMap(acc.sub_extra, compareKeys),
+  subGroups_vowelCombination: transformAccumulatorMap(acc.sub_vowelCombo, compareKeys),
   subGroups_noSecondChar: Array_toNonEmptyArray_orUndefined(acc.noSecond),
 })
 
-const finalizeGroups = <L extends Char>(map: Map<L, Accumulator>): Map<L, AlphabetGroupKhmer> => {
+const finalizeGroups = <L extends Char>(
+  map: Map<L, Accumulator>,
+  compareKeys: CompareKhmerKeys,
+): Map<L, AlphabetGroupKhmer> => {
   const out = new Map<L, AlphabetGroupKhmer>()
-  const sortedKeys = Array.from(map.keys()).sort((a, b) => a.localeCompare(b))
+  const sortedKeys = Array.from(map.keys()).sort(compareKeys)
 
   for (const k of sortedKeys) {
-    out.set(k, accumulatorToGroup(map.get(k)!))
+    out.set(k, accumulatorToGroup(map.get(k)!, compareKeys))
   }
 
   return out
 }
 
-export function processDataKhmer(data: Iterable<readonly [NonEmptyStringTrimmed, KhmerInfo]>): ProcessDataOutputKhmer {
+export function processDataKhmer(
+  data: Iterable<readonly [NonEmptyStringTrimmed, KhmerInfo]>,
+  compareKeys: CompareKhmerKeys = compareKhmerKeys_locale,
+): ProcessDataOutputKhmer {
   // 1. Accumulate
   const acc_consonant = new Map<CharKhmerConsonant, Accumulator>()
   const acc_vowel = new Map<CharKhmerVowel, Accumulator>()
@@ -301,10 +313,10 @@ export function processDataKhmer(data: Iterable<readonly [NonEmptyStringTrimmed,
 
   // 2. Finalize
   return {
-    words_consonant: finalizeGroups(acc_consonant),
-    words_vowel: finalizeGroups(acc_vowel),
-    words_independent_vowel: finalizeGroups(acc_indep),
-    words_diacritic: finalizeGroups(acc_diacritic),
+    words_consonant: finalizeGroups(acc_consonant, compareKeys),
+    words_vowel: finalizeGroups(acc_vowel, compareKeys),
+    words_independent_vowel: finalizeGroups(acc_indep, compareKeys),
+    words_diacritic: finalizeGroups(acc_diacritic, compareKeys),
     numbers: Array_toNonEmptyArray_orUndefined(numbers),
     punctuation: Array_toNonEmptyArray_orUndefined(punctuation),
     lunarDates: Array_toNonEmptyArray_orUndefined(lunarDates),